@@ -0,0 +1,76 @@
+// 对比逐类型过滤克隆与一次遍历分桶：cargo bench --bench notices
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+
+#[allow(dead_code)]
+#[path = "../src/compat.rs"]
+mod compat;
+#[allow(dead_code)]
+#[path = "../src/models.rs"]
+mod models;
+
+use models::{Notice, NoticeType, NoticesByType};
+
+fn notices(count: u64) -> Vec<Notice> {
+  let types = [
+    "Normal",
+    "NewChallenge",
+    "NewHint",
+    "FirstBlood",
+    "SecondBlood",
+    "ThirdBlood",
+  ];
+  (0..count)
+    .map(|id| {
+      serde_json::from_value(serde_json::json!({
+        "id": id,
+        "type": types[id as usize % types.len()],
+        "values": [format!("team {}", id), format!("challenge {}", id % 50)],
+        "time": 1_700_000_000_000u64 + id,
+      }))
+      .unwrap()
+    })
+    .collect()
+}
+
+// 重构前的做法：每种类型完整遍历一次并克隆匹配的公告
+fn filter_by_type(notices: &[Notice], notice_type: NoticeType) -> Vec<Notice> {
+  notices
+    .iter()
+    .filter(|n| NoticeType::from_str(&n.notice_type) == Some(notice_type.clone()))
+    .cloned()
+    .collect()
+}
+
+fn bucket_notices(c: &mut Criterion) {
+  let mut group = c.benchmark_group("bucket_notices");
+  for count in [1_000, 10_000] {
+    let notices = notices(count);
+    group.bench_with_input(
+      BenchmarkId::new("filter_by_type", count),
+      &notices,
+      |b, notices| {
+        b.iter(|| {
+          for notice_type in NoticeType::all() {
+            black_box(filter_by_type(notices, notice_type));
+          }
+        })
+      },
+    );
+    group.bench_with_input(
+      BenchmarkId::new("by_type", count),
+      &notices,
+      |b, notices| {
+        b.iter(|| {
+          let by_type = NoticesByType::new(notices);
+          for notice_type in NoticeType::all() {
+            black_box(by_type.get(&notice_type));
+          }
+        })
+      },
+    );
+  }
+  group.finish();
+}
+
+criterion_group!(benches, bucket_notices);
+criterion_main!(benches);
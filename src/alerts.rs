@@ -0,0 +1,498 @@
+use anyhow::Result;
+use serde_json::json;
+use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::model::channel::{Reaction, ReactionType};
+use serenity::model::colour::Colour;
+use serenity::model::id::{ChannelId, MessageId, UserId};
+use serenity::prelude::Context;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, sleep};
+
+use crate::audit;
+use crate::config::AlertsConfig;
+use crate::discord::DeliveryState;
+use crate::log;
+use crate::queue::MessageQueue;
+use crate::tasks;
+
+const ACK_EMOJI: &str = "✅";
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq)]
+enum AlertState {
+  Firing,
+  Acknowledged(UserId),
+}
+
+#[derive(Debug, Clone)]
+struct Alert {
+  title: String,
+  description: String,
+  message_id: MessageId,
+  state: AlertState,
+  raised_at: Instant,
+  last_ping: Instant,
+  escalated: bool,
+  // 由监控循环管理的告警会在条件消失后自动解除
+  monitored: bool,
+}
+
+// 需要告警的条件，由监控循环统一评估
+#[derive(Debug, Default)]
+struct Health {
+  // match_id -> (首次失败时间, 最近一次错误)
+  poll_failures: HashMap<u32, (Instant, String)>,
+  gateway: GatewayStats,
+  last_poll_success: Option<Instant>,
+}
+
+// 网关连接统计，用于赛后评估转发的可靠性；ready 和 shard 状态变化都会上报，重复的状态不计数
+#[derive(Debug, Clone, Default)]
+pub struct GatewayStats {
+  pub connects: u64,
+  pub disconnects: u64,
+  // 已结束的断线时长之和，不含当前这次
+  downtime: Duration,
+  pub down_since: Option<Instant>,
+  pub connected_since: Option<Instant>,
+}
+
+impl GatewayStats {
+  // 返回刚结束的断线时长
+  fn connected(&mut self, now: Instant) -> Option<Duration> {
+    if self.connected_since.is_some() {
+      return None;
+    }
+    self.connects += 1;
+    self.connected_since = Some(now);
+    let down = self.down_since.take().map(|since| now - since);
+    self.downtime += down.unwrap_or_default();
+    down
+  }
+
+  fn disconnected(&mut self, now: Instant) {
+    if self.connected_since.take().is_some() {
+      self.disconnects += 1;
+    }
+    self.down_since.get_or_insert(now);
+  }
+
+  pub fn reconnects(&self) -> u64 {
+    self.connects.saturating_sub(1)
+  }
+
+  pub fn total_downtime(&self) -> Duration {
+    self.downtime
+      + self
+        .down_since
+        .map(|since| since.elapsed())
+        .unwrap_or_default()
+  }
+}
+
+// 管理频道告警：Firing -> Acknowledged（✅ 反应）-> 解除（resolve）
+pub struct AlertManager {
+  channel_id: Option<u64>,
+  config: AlertsConfig,
+  bot_user_id: OnceLock<UserId>,
+  alerts: Mutex<HashMap<String, Alert>>,
+  health: Mutex<Health>,
+  monitor_started: AtomicBool,
+  delivery: Arc<DeliveryState>,
+}
+
+impl AlertManager {
+  pub fn new(channel_id: Option<u64>, config: &AlertsConfig, delivery: Arc<DeliveryState>) -> Self {
+    Self {
+      channel_id,
+      config: config.clone(),
+      bot_user_id: OnceLock::new(),
+      alerts: Mutex::new(HashMap::new()),
+      health: Mutex::new(Health::default()),
+      monitor_started: AtomicBool::new(false),
+      delivery,
+    }
+  }
+
+  pub fn set_bot_user(&self, user_id: UserId) {
+    let _ = self.bot_user_id.set(user_id);
+  }
+
+  pub async fn record_poll_success(&self, match_id: u32) {
+    let mut health = self.health.lock().await;
+    health.poll_failures.remove(&match_id);
+    health.last_poll_success = Some(Instant::now());
+  }
+
+  // 供机器人状态展示：最近一次成功轮询的时间，以及是否有比赛正在轮询失败
+  pub async fn poll_health(&self) -> (Option<Instant>, bool) {
+    let health = self.health.lock().await;
+    (health.last_poll_success, !health.poll_failures.is_empty())
+  }
+
+  pub async fn record_poll_failure(&self, match_id: u32, error: String) {
+    let mut health = self.health.lock().await;
+    health
+      .poll_failures
+      .entry(match_id)
+      .and_modify(|(_, last_error)| *last_error = error.clone())
+      .or_insert_with(|| (Instant::now(), error));
+  }
+
+  pub async fn record_gateway(&self, connected: bool) {
+    let mut health = self.health.lock().await;
+    if !connected {
+      health.gateway.disconnected(Instant::now());
+      return;
+    }
+    if let Some(down) = health.gateway.connected(Instant::now())
+      && health.gateway.connects > 1
+    {
+      log::info(format!(
+        "Gateway reconnected after {}s (reconnect #{})",
+        down.as_secs(),
+        health.gateway.reconnects()
+      ));
+    }
+  }
+
+  pub async fn gateway_stats(&self) -> GatewayStats {
+    self.health.lock().await.gateway.clone()
+  }
+
+  pub fn start_monitor(self: Arc<Self>, ctx: Arc<Context>, message_queue: Arc<MessageQueue>) {
+    if self.channel_id.is_none() {
+      log::info("No admin channel configured, alert monitor disabled.");
+      return;
+    }
+    if self.monitor_started.swap(true, Ordering::SeqCst) {
+      return;
+    }
+
+    tokio::spawn(async move {
+      log::info("Alert monitor started.");
+      tasks::started("alerts");
+      loop {
+        sleep(CHECK_INTERVAL).await;
+        let queue_len = message_queue.len().await;
+        self.reconcile(&ctx, queue_len).await;
+        self.reping_and_escalate(&ctx).await;
+        tasks::touch("alerts");
+      }
+    });
+  }
+
+  async fn reconcile(&self, ctx: &Context, queue_len: usize) {
+    let desired = self.evaluate(queue_len).await;
+
+    let stale: Vec<String> = {
+      let alerts = self.alerts.lock().await;
+      alerts
+        .iter()
+        .filter(|(key, alert)| alert.monitored && !desired.contains_key(*key))
+        .map(|(key, _)| key.clone())
+        .collect()
+    };
+    for key in stale {
+      self.resolve(ctx, &key).await;
+    }
+
+    for (key, (title, description)) in desired {
+      self
+        .raise_inner(ctx, &key, &title, &description, true)
+        .await;
+    }
+  }
+
+  async fn evaluate(&self, queue_len: usize) -> HashMap<String, (String, String)> {
+    let mut desired = HashMap::new();
+    let health = self.health.lock().await;
+
+    let poll_limit = Duration::from_secs(self.config.poll_failure_minutes * 60);
+    for (match_id, (since, error)) in &health.poll_failures {
+      if since.elapsed() >= poll_limit {
+        desired.insert(
+          format!("api:{}", match_id),
+          (
+            format!("GZCTF API 不可用 (比赛 {})", match_id),
+            format!(
+              "已连续失败 {} 分钟，最近错误: {}",
+              since.elapsed().as_secs() / 60,
+              error
+            ),
+          ),
+        );
+      }
+    }
+
+    if queue_len > self.config.queue_threshold {
+      desired.insert(
+        "queue".to_string(),
+        (
+          "消息队列积压".to_string(),
+          format!(
+            "待重发消息 {} 条，超过阈值 {}",
+            queue_len, self.config.queue_threshold
+          ),
+        ),
+      );
+    }
+
+    for (channel_id, since, reason) in self.delivery.degraded_channels() {
+      desired.insert(
+        format!("channel:{}", channel_id),
+        (
+          format!("频道 {} 缺少权限", channel_id),
+          format!(
+            "已暂停向该频道发送 {} 分钟，消息保留在队列中，权限恢复后自动继续。Discord 返回: {}",
+            since.as_secs() / 60,
+            reason
+          ),
+        ),
+      );
+    }
+
+    let gateway_limit = Duration::from_secs(self.config.gateway_disconnect_minutes * 60);
+    if let Some(since) = health.gateway.down_since
+      && since.elapsed() >= gateway_limit
+    {
+      desired.insert(
+        "gateway".to_string(),
+        (
+          "Discord Gateway 断开".to_string(),
+          format!("已断开 {} 分钟", since.elapsed().as_secs() / 60),
+        ),
+      );
+    }
+
+    desired
+  }
+
+  // 同一 key 的告警在解除之前只会发送一次
+  pub async fn raise(&self, ctx: &Context, key: &str, title: &str, description: &str) {
+    self.raise_inner(ctx, key, title, description, false).await;
+  }
+
+  async fn raise_inner(
+    &self,
+    ctx: &Context,
+    key: &str,
+    title: &str,
+    description: &str,
+    monitored: bool,
+  ) {
+    let Some(channel_id) = self.channel_id else {
+      return;
+    };
+    if self.alerts.lock().await.contains_key(key) {
+      return;
+    }
+
+    // 发送期间不持有锁，避免一次慢请求阻塞其他告警的触发和解除
+    let message_id = match self.post_alert(ctx, channel_id, title, description).await {
+      Ok(message_id) => message_id,
+      Err(e) => {
+        log::error(format!("Failed to post alert '{}': {}", key, e));
+        return;
+      }
+    };
+
+    let mut alerts = self.alerts.lock().await;
+    if alerts.contains_key(key) {
+      return;
+    }
+    log::info(format!("Raised alert '{}' in admin channel", key));
+    audit::record("alert_raised", "bot", json!({ "key": key, "title": title }));
+    alerts.insert(
+      key.to_string(),
+      Alert {
+        title: title.to_string(),
+        description: description.to_string(),
+        message_id,
+        state: AlertState::Firing,
+        raised_at: Instant::now(),
+        last_ping: Instant::now(),
+        escalated: false,
+        monitored,
+      },
+    );
+  }
+
+  pub async fn resolve(&self, ctx: &Context, key: &str) {
+    let Some(alert) = self.alerts.lock().await.remove(key) else {
+      return;
+    };
+    let Some(channel_id) = self.channel_id else {
+      return;
+    };
+
+    log::success(format!("Alert '{}' resolved", key));
+    audit::record("alert_resolved", "bot", json!({ "key": key }));
+    let message = CreateMessage::new()
+      .content(format!("{} 已恢复: {}", ACK_EMOJI, alert.title))
+      .reference_message((ChannelId::new(channel_id), alert.message_id));
+    if let Err(e) = ChannelId::new(channel_id)
+      .send_message(&ctx.http, message)
+      .await
+    {
+      log::error(format!("Failed to post alert resolution: {}", e));
+    }
+  }
+
+  pub async fn handle_reaction(&self, reaction: &Reaction) {
+    let Some(user_id) = reaction.user_id else {
+      return;
+    };
+    if self.bot_user_id.get() == Some(&user_id)
+      || !matches!(&reaction.emoji, ReactionType::Unicode(e) if e == ACK_EMOJI)
+    {
+      return;
+    }
+
+    let mut alerts = self.alerts.lock().await;
+    if let Some((key, alert)) = alerts
+      .iter_mut()
+      .find(|(_, a)| a.message_id == reaction.message_id && a.state == AlertState::Firing)
+    {
+      alert.state = AlertState::Acknowledged(user_id);
+      log::success(format!("Alert '{}' acknowledged by user {}", key, user_id));
+    }
+  }
+
+  async fn reping_and_escalate(&self, ctx: &Context) {
+    let Some(channel_id) = self.channel_id else {
+      return;
+    };
+    let channel = ChannelId::new(channel_id);
+    let reping_interval = Duration::from_secs(self.config.reping_interval);
+    let escalate_after = Duration::from_secs(self.config.escalate_after_minutes * 60);
+
+    // 先取出需要处理的告警再发送，发送期间不持有锁
+    let due: Vec<(String, Alert)> = self
+      .alerts
+      .lock()
+      .await
+      .iter()
+      .filter(|(_, alert)| {
+        alert.state == AlertState::Firing
+          && (alert.last_ping.elapsed() >= reping_interval
+            || (!alert.escalated && alert.raised_at.elapsed() >= escalate_after))
+      })
+      .map(|(key, alert)| (key.clone(), alert.clone()))
+      .collect();
+
+    for (key, alert) in due {
+      let mut pinged = false;
+      if alert.last_ping.elapsed() >= reping_interval {
+        let message = CreateMessage::new()
+          .content(format!(
+            "@here 告警尚未确认: {}（点击 {} 确认）",
+            alert.title, ACK_EMOJI
+          ))
+          .reference_message((channel, alert.message_id));
+        match channel.send_message(&ctx.http, message).await {
+          Ok(_) => pinged = true,
+          Err(e) => log::error(format!("Failed to re-ping alert: {}", e)),
+        }
+      }
+
+      let escalate = !alert.escalated && alert.raised_at.elapsed() >= escalate_after;
+      if escalate {
+        self.escalate(ctx, &alert).await;
+      }
+
+      // 发送期间告警可能已被确认或解除
+      if let Some(current) = self.alerts.lock().await.get_mut(&key) {
+        if pinged {
+          current.last_ping = Instant::now();
+        }
+        current.escalated |= escalate;
+      }
+    }
+  }
+
+  async fn escalate(&self, ctx: &Context, alert: &Alert) {
+    let content = format!(
+      "告警已持续 {} 分钟未解除: {}\n{}",
+      alert.raised_at.elapsed().as_secs() / 60,
+      alert.title,
+      alert.description
+    );
+
+    for user_id in &self.config.escalate_user_ids {
+      let result = async {
+        let dm = UserId::new(*user_id).create_dm_channel(&ctx.http).await?;
+        dm.send_message(&ctx.http, CreateMessage::new().content(&content))
+          .await?;
+        anyhow::Ok(())
+      }
+      .await;
+
+      match result {
+        Ok(_) => log::info(format!(
+          "Escalated alert '{}' to user {}",
+          alert.title, user_id
+        )),
+        Err(e) => log::error(format!(
+          "Failed to escalate alert to user {}: {}",
+          user_id, e
+        )),
+      }
+    }
+  }
+
+  // 不经过 DiscordMessenger：维护模式和频道降级期间告警照常发出
+  async fn post_alert(
+    &self,
+    ctx: &Context,
+    channel_id: u64,
+    title: &str,
+    description: &str,
+  ) -> Result<MessageId> {
+    let embed = CreateEmbed::new()
+      .title(format!("**告警: {}**", title))
+      .description(description)
+      .color(Colour::from_rgb(239, 68, 68))
+      .field("确认", format!("点击 {} 确认此告警", ACK_EMOJI), false);
+
+    let message = ChannelId::new(channel_id)
+      .send_message(&ctx.http, CreateMessage::new().embed(embed.clone()))
+      .await?;
+    self.delivery.audit(ctx, channel_id, None, &embed);
+    message
+      .react(&ctx.http, ReactionType::Unicode(ACK_EMOJI.to_string()))
+      .await?;
+
+    Ok(message.id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn gateway_stats_count_transitions_and_downtime() {
+    let start = Instant::now();
+    let mut stats = GatewayStats::default();
+    assert_eq!(stats.connected(start), None);
+    // ready 和 shard 状态变化会重复上报同一状态
+    assert_eq!(stats.connected(start), None);
+
+    stats.disconnected(start + Duration::from_secs(10));
+    stats.disconnected(start + Duration::from_secs(12));
+    assert_eq!(
+      stats.connected(start + Duration::from_secs(40)),
+      Some(Duration::from_secs(30))
+    );
+
+    assert_eq!(stats.connects, 2);
+    assert_eq!(stats.disconnects, 1);
+    assert_eq!(stats.reconnects(), 1);
+    assert_eq!(stats.total_downtime(), Duration::from_secs(30));
+  }
+}
@@ -0,0 +1,50 @@
+use chrono::Local;
+use serde_json::{Map, Value, json};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+use crate::log;
+
+// 与诊断日志分开的操作审计文件，每行一个 JSON 对象
+static SINK: Mutex<Option<File>> = Mutex::new(None);
+
+// 启动和重新加载配置时打开，未配置时关闭
+pub fn init(path: Option<&str>) -> std::io::Result<()> {
+  let file = match path {
+    Some(path) => Some(OpenOptions::new().create(true).append(true).open(path)?),
+    None => None,
+  };
+  *SINK.lock().unwrap() = file;
+  Ok(())
+}
+
+// actor 为触发操作的一方：Discord 用户名、"bot"（自动行为）或 "signal" 等；
+// details 为对象时其字段并入记录
+pub fn record(action: &str, actor: &str, details: Value) {
+  let mut sink = SINK.lock().unwrap();
+  let Some(file) = sink.as_mut() else {
+    return;
+  };
+
+  let mut entry = Map::new();
+  entry.insert("time".to_string(), json!(Local::now().to_rfc3339()));
+  entry.insert("action".to_string(), json!(action));
+  entry.insert("actor".to_string(), json!(actor));
+  if let Some(correlation) = log::current_correlation() {
+    entry.insert("correlation".to_string(), json!(correlation));
+  }
+  match details {
+    Value::Object(fields) => entry.extend(fields),
+    Value::Null => {}
+    other => {
+      entry.insert("details".to_string(), other);
+    }
+  }
+
+  let line = format!("{}\n", Value::Object(entry));
+  if let Err(e) = file.write_all(line.as_bytes()) {
+    // 不经过 log::error，避免诊断日志和审计互相影响
+    eprintln!("[-] Failed to write audit log: {}", e);
+  }
+}
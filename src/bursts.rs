@@ -0,0 +1,245 @@
+use serenity::prelude::Context;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use tokio::time::{Duration, sleep};
+
+use crate::alerts::AlertManager;
+use crate::config::{Config, MatchConfig, PlatformKind};
+use crate::gzctf::GzctfClient;
+use crate::limiter::RequestLimiter;
+use crate::log;
+use crate::models::GameEvent;
+use crate::scheduler::Scheduler;
+use crate::tasks;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+// 不含容器事件；一个检查间隔内的提交超过这个数时只统计最新的部分
+const EVENT_PAGE: u32 = 200;
+// 提交结果由服务器本地化，除这些之外都算作错误提交
+const ACCEPTED: [&str; 3] = ["Accepted", "正确", "答案正确"];
+
+// 一场比赛的错误提交记录：题目 -> 窗口内错误提交的时间
+#[derive(Default)]
+struct Window {
+  latest: Option<u64>,
+  wrong: HashMap<String, VecDeque<u64>>,
+}
+
+impl Window {
+  // 加入新事件并清理窗口外的记录，返回超过阈值的题目和次数；窗口以最新事件为准，不受时钟偏差影响
+  fn update(
+    &mut self,
+    events: &[GameEvent],
+    window_ms: u64,
+    threshold: usize,
+  ) -> Vec<(String, usize)> {
+    let newest = events.iter().map(|e| e.time).max().unwrap_or(0);
+    let since = self.latest.replace(newest.max(self.latest.unwrap_or(0)));
+    let Some(since) = since else {
+      return Vec::new();
+    };
+
+    let mut fresh: Vec<&GameEvent> = events.iter().filter(|e| e.time > since).collect();
+    fresh.sort_by_key(|e| e.time);
+    for event in fresh {
+      if let Some(challenge) = wrong_submission(event) {
+        self
+          .wrong
+          .entry(challenge.to_string())
+          .or_default()
+          .push_back(event.time);
+      }
+    }
+
+    let cutoff = self.latest.unwrap_or(0).saturating_sub(window_ms);
+    self.wrong.retain(|_, times| {
+      while times.front().is_some_and(|t| *t <= cutoff) {
+        times.pop_front();
+      }
+      !times.is_empty()
+    });
+    let mut bursts: Vec<(String, usize)> = self
+      .wrong
+      .iter()
+      .filter(|(_, times)| times.len() >= threshold)
+      .map(|(challenge, times)| (challenge.clone(), times.len()))
+      .collect();
+    bursts.sort();
+    bursts
+  }
+}
+
+// FlagSubmit 事件的 values 依次为提交结果、提交内容、题目名
+fn wrong_submission(event: &GameEvent) -> Option<&str> {
+  if event.event_type != "FlagSubmit" {
+    return None;
+  }
+  let result = event.values.first()?;
+  if ACCEPTED.contains(&result.as_str()) {
+    return None;
+  }
+  event.values.get(2).map(String::as_str)
+}
+
+// 配置了 submission_burst 的比赛定期统计各题的错误提交，超过阈值时在管理频道告警，回落后自动解除
+pub fn start(
+  ctx: Arc<Context>,
+  config: Arc<Config>,
+  scheduler: Arc<Scheduler>,
+  alerts: Arc<AlertManager>,
+  limiter: Arc<RequestLimiter>,
+) {
+  let matches: Vec<MatchConfig> = config
+    .get_matches()
+    .into_iter()
+    .filter(|m| m.submission_burst.is_some())
+    .collect();
+  if matches.is_empty() {
+    return;
+  }
+  if config.gzctf.platform != PlatformKind::Gzctf {
+    log::warn("Submission burst monitoring only supports GZCTF, skipping");
+    return;
+  }
+  if config.gzctf.session_cookie.is_none() {
+    log::warn("Submission burst monitoring needs gzctf.session_cookie of a Monitor account");
+  }
+
+  let count = matches.len();
+  let task = async move {
+    let client = match GzctfClient::new(&config.gzctf, limiter) {
+      Ok(client) => client,
+      Err(e) => {
+        log::error(format!(
+          "Failed to create client for submission monitoring: {}",
+          e
+        ));
+        return;
+      }
+    };
+    let mut windows: HashMap<u32, Window> = HashMap::new();
+    let mut firing: HashSet<String> = HashSet::new();
+    let mut failing = HashSet::new();
+
+    loop {
+      for match_config in &matches {
+        let Some(threshold) = match_config.submission_burst else {
+          continue;
+        };
+        let events = match client
+          .fetch_events(match_config.id, EVENT_PAGE, false)
+          .await
+        {
+          Ok(events) => {
+            failing.remove(&match_config.id);
+            events
+          }
+          Err(e) => {
+            if failing.insert(match_config.id) {
+              log::error(format!(
+                "Failed to fetch submissions for match {}: {}",
+                match_config.id, e
+              ));
+            }
+            continue;
+          }
+        };
+
+        let bursts = windows.entry(match_config.id).or_default().update(
+          &events,
+          match_config.burst_window * 1000,
+          threshold.max(1),
+        );
+        let prefix = format!("burst:{}:", match_config.id);
+        let current: HashSet<String> = bursts
+          .iter()
+          .map(|(challenge, _)| format!("{}{}", prefix, challenge))
+          .collect();
+
+        for (challenge, count) in &bursts {
+          let key = format!("{}{}", prefix, challenge);
+          if firing.insert(key.clone()) {
+            log::warn(format!(
+              "Match {} challenge '{}' received {} wrong submissions within {}s",
+              match_config.id, challenge, count, match_config.burst_window
+            ));
+          }
+          alerts
+            .raise(
+              &ctx,
+              &key,
+              &format!("错误提交激增 (比赛 {})", match_config.id),
+              &format!(
+                "题目 **{}** 在 {} 秒内收到 {} 次错误提交（阈值 {}），可能有队伍在暴力尝试 flag",
+                challenge, match_config.burst_window, count, threshold
+              ),
+            )
+            .await;
+        }
+        let ended: Vec<String> = firing
+          .iter()
+          .filter(|key| key.starts_with(&prefix) && !current.contains(*key))
+          .cloned()
+          .collect();
+        for key in ended {
+          firing.remove(&key);
+          alerts.resolve(&ctx, &key).await;
+        }
+      }
+      tasks::touch("scheduler:bursts");
+      sleep(CHECK_INTERVAL).await;
+    }
+  };
+  if scheduler.schedule_at("bursts", scheduler.now(), task) {
+    log::info(format!(
+      "Monitoring wrong submission bursts for {} match(es)",
+      count
+    ));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn submission(result: &str, challenge: &str, time: u64) -> GameEvent {
+    GameEvent {
+      event_type: "FlagSubmit".to_string(),
+      values: vec![
+        result.to_string(),
+        "flag{x}".to_string(),
+        challenge.to_string(),
+      ],
+      time,
+      user: None,
+      team: None,
+    }
+  }
+
+  #[test]
+  fn bursts_count_wrong_submissions_within_the_window() {
+    let mut window = Window::default();
+    assert!(
+      window
+        .update(&[submission("WrongAnswer", "Web 1", 1_000)], 60_000, 3)
+        .is_empty()
+    );
+
+    let events = [
+      submission("WrongAnswer", "Web 1", 2_000),
+      submission("WrongAnswer", "Web 1", 3_000),
+      submission("Accepted", "Web 1", 4_000),
+      submission("WrongAnswer", "Web 1", 5_000),
+      submission("WrongAnswer", "Pwn 1", 5_000),
+    ];
+    assert_eq!(
+      window.update(&events, 60_000, 3),
+      vec![("Web 1".to_string(), 3)]
+    );
+
+    // 同样的事件不会重复计数，窗口外的提交被清理
+    let later = [submission("WrongAnswer", "Pwn 1", 64_000)];
+    assert_eq!(window.update(&events, 60_000, 3).len(), 1);
+    assert!(window.update(&later, 60_000, 3).is_empty());
+  }
+}
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use crate::models::Scoreboard;
+use crate::platform::CtfPlatform;
+
+// 查不到题目时可能刚上新题，超过该间隔即可提前刷新
+const MIN_REFRESH: Duration = Duration::from_secs(30);
+
+// 题目的静态信息；解出人数变化太快，不在这里缓存
+#[derive(Debug, Clone)]
+pub struct Challenge {
+  pub id: u32,
+  pub title: String,
+  pub category: String,
+  pub score: u32,
+  pub challenge_type: Option<String>,
+}
+
+struct Snapshot {
+  fetched_at: Instant,
+  challenges: Vec<Challenge>,
+}
+
+// 每场比赛的题目信息缓存，来自排行榜接口中的题目列表，过期后在下次查询时刷新
+pub struct ChallengeStore {
+  ttl: Duration,
+  matches: RwLock<HashMap<u32, Snapshot>>,
+}
+
+impl ChallengeStore {
+  pub fn new(ttl_secs: u64) -> Self {
+    Self {
+      ttl: Duration::from_secs(ttl_secs),
+      matches: RwLock::new(HashMap::new()),
+    }
+  }
+
+  // 其他功能已经拉取了排行榜时顺便更新，省去一次请求
+  pub async fn update(&self, match_id: u32, scoreboard: &Scoreboard) {
+    let mut challenges: Vec<Challenge> = scoreboard
+      .challenges
+      .values()
+      .flatten()
+      .map(|c| Challenge {
+        id: c.id,
+        title: c.title.clone(),
+        category: c.category.clone(),
+        score: c.score,
+        challenge_type: c.challenge_type.clone(),
+      })
+      .collect();
+    challenges.sort_by(|a, b| (&a.category, &a.title).cmp(&(&b.category, &b.title)));
+
+    self.matches.write().await.insert(
+      match_id,
+      Snapshot {
+        fetched_at: Instant::now(),
+        challenges,
+      },
+    );
+  }
+
+  pub async fn refresh(&self, client: &dyn CtfPlatform, match_id: u32) -> Result<()> {
+    let scoreboard = client.fetch_scoreboard(match_id).await?;
+    self.update(match_id, &scoreboard).await;
+    Ok(())
+  }
+
+  pub async fn refresh_if_stale(&self, client: &dyn CtfPlatform, match_id: u32) -> Result<()> {
+    if self.age(match_id).await.is_some_and(|age| age < self.ttl) {
+      return Ok(());
+    }
+    self.refresh(client, match_id).await
+  }
+
+  // 按题目名查找，缓存过期或找不到时先刷新；刷新后仍找不到则返回 None
+  pub async fn find(
+    &self,
+    client: &dyn CtfPlatform,
+    match_id: u32,
+    title: &str,
+  ) -> Result<Option<Challenge>> {
+    if let Some(age) = self.age(match_id).await {
+      let cached = self.cached(match_id, title).await;
+      match cached {
+        Some(_) if age < self.ttl => return Ok(cached),
+        None if age < MIN_REFRESH => return Ok(None),
+        _ => {}
+      }
+    }
+
+    self.refresh(client, match_id).await?;
+    Ok(self.cached(match_id, title).await)
+  }
+
+  // 只查缓存，不发请求
+  pub async fn cached(&self, match_id: u32, title: &str) -> Option<Challenge> {
+    self
+      .matches
+      .read()
+      .await
+      .get(&match_id)?
+      .challenges
+      .iter()
+      .find(|c| c.title == title)
+      .cloned()
+  }
+
+  // 按分类、题目名排序
+  pub async fn list(&self, match_id: u32) -> Vec<Challenge> {
+    self
+      .matches
+      .read()
+      .await
+      .get(&match_id)
+      .map(|s| s.challenges.clone())
+      .unwrap_or_default()
+  }
+
+  async fn age(&self, match_id: u32) -> Option<Duration> {
+    self
+      .matches
+      .read()
+      .await
+      .get(&match_id)
+      .map(|s| s.fetched_at.elapsed())
+  }
+}
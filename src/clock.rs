@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+
+// 当前时间的来源，时间戳的换算统一在这里完成；测试中替换为跟随 tokio 时间的时钟
+pub trait Clock: Send + Sync {
+  fn utc(&self) -> DateTime<Utc>;
+
+  // Unix 时间戳（秒）
+  fn now(&self) -> u64 {
+    self.utc().timestamp() as u64
+  }
+
+  // Unix 时间戳（毫秒），与 GZCTF 公告的时间单位一致
+  fn now_millis(&self) -> u64 {
+    self.utc().timestamp_millis() as u64
+  }
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+  fn utc(&self) -> DateTime<Utc> {
+    Utc::now()
+  }
+}
+
+// 从创建时的真实时间出发，随 tokio 时间前进：tokio::time::pause 之后，
+// advance 同时推动 sleep 和时间戳；也可以用 advance 单独拨快
+#[cfg(test)]
+pub struct MockClock {
+  base: DateTime<Utc>,
+  started: tokio::time::Instant,
+  offset: std::sync::atomic::AtomicU64,
+}
+
+#[cfg(test)]
+impl MockClock {
+  pub fn new() -> Self {
+    Self {
+      base: Utc::now(),
+      started: tokio::time::Instant::now(),
+      offset: std::sync::atomic::AtomicU64::new(0),
+    }
+  }
+
+  pub fn advance(&self, secs: u64) {
+    self
+      .offset
+      .fetch_add(secs, std::sync::atomic::Ordering::SeqCst);
+  }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+  fn utc(&self) -> DateTime<Utc> {
+    let offset = self.offset.load(std::sync::atomic::Ordering::SeqCst);
+    let elapsed = self.started.elapsed() + std::time::Duration::from_secs(offset);
+    self.base + chrono::Duration::from_std(elapsed).unwrap()
+  }
+}
@@ -0,0 +1,1012 @@
+use anyhow::{Context as _, Result, anyhow};
+use serenity::builder::{
+  CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponseFollowup, CreateMessage,
+};
+use serenity::gateway::ShardManager;
+use serenity::model::application::{
+  ApplicationFlags, Command, CommandInteraction, CommandOptionType, ResolvedOption, ResolvedValue,
+};
+use serenity::model::channel::Message;
+use serenity::model::colour::Colour;
+use serenity::model::permissions::Permissions;
+use serenity::prelude::{Context, TypeMapKey};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::gzctf::{create_embed, trunc_text};
+use crate::handler::BotHandler;
+use crate::log;
+use crate::models::{NoticeType, NoticesByType};
+use crate::pages::{Page, Paginator};
+use crate::permissions::{self, Target};
+use crate::platform;
+use crate::tasks;
+
+// /last 最多展示的条数，每页一条，避免多条长公告超出单条消息 6000 字符的上限
+const MAX_LAST: usize = 25;
+// /debug tasks 每页列出的任务数
+const TASKS_PER_PAGE: usize = 20;
+// /setup check 每页列出的检查项数
+const CHECKS_PER_PAGE: usize = 15;
+// /scoreboard 每页列出的队伍数
+const TEAMS_PER_PAGE: usize = 20;
+// /challenges 每页列出的题目数
+const CHALLENGES_PER_PAGE: usize = 20;
+// /queue status 每页列出的消息数
+const QUEUE_PER_PAGE: usize = 15;
+
+// main 在创建客户端后放入，/ping 从中读取网关心跳延迟
+pub struct ShardManagerKey;
+
+impl TypeMapKey for ShardManagerKey {
+  type Value = Arc<ShardManager>;
+}
+
+// 多于一页时附上翻页按钮
+#[derive(Default)]
+pub struct Reply {
+  pages: Vec<Page>,
+}
+
+impl From<String> for Reply {
+  fn from(content: String) -> Self {
+    Self::paged(vec![Page {
+      content: Some(content),
+      embeds: Vec::new(),
+    }])
+  }
+}
+
+impl Reply {
+  fn embed(embed: CreateEmbed) -> Self {
+    Self::paged(vec![Page {
+      content: None,
+      embeds: vec![embed],
+    }])
+  }
+
+  fn paged(pages: Vec<Page>) -> Self {
+    Self { pages }
+  }
+}
+
+pub fn definitions() -> Vec<CreateCommand> {
+  vec![
+    CreateCommand::new("linkteam")
+      .description("申请将你的 Discord 账号关联到 GZCTF 队伍，管理员确认后生效")
+      .add_option(
+        CreateCommandOption::new(CommandOptionType::String, "team", "GZCTF 队伍名").required(true),
+      ),
+    CreateCommand::new("unlinkteam").description("取消你的 Discord 账号与队伍的关联或关联申请"),
+    CreateCommand::new("approvelink")
+      .description("确认用户的队伍关联申请，不指定用户时列出待确认的申请")
+      .default_member_permissions(Permissions::MANAGE_GUILD)
+      .add_option(CreateCommandOption::new(
+        CommandOptionType::User,
+        "user",
+        "提交申请的用户",
+      )),
+    CreateCommand::new("subscribe")
+      .description("订阅某场比赛某个分类的提示和血榜，通过私信推送")
+      .add_option(
+        CreateCommandOption::new(CommandOptionType::String, "category", "题目分类，如 Pwn")
+          .required(true),
+      )
+      .add_option(
+        CreateCommandOption::new(CommandOptionType::Integer, "match", "比赛 ID").required(true),
+      ),
+    CreateCommand::new("unsubscribe")
+      .description("取消私信订阅，不填条件时取消全部")
+      .add_option(CreateCommandOption::new(
+        CommandOptionType::String,
+        "category",
+        "题目分类",
+      ))
+      .add_option(CreateCommandOption::new(
+        CommandOptionType::Integer,
+        "match",
+        "比赛 ID",
+      )),
+    CreateCommand::new("last")
+      .description("重新展示某场比赛最近转发的公告")
+      .add_option(
+        CreateCommandOption::new(CommandOptionType::Integer, "match", "比赛 ID").required(true),
+      )
+      .add_option(
+        CreateCommandOption::new(CommandOptionType::Integer, "count", "条数（默认 5）")
+          .min_int_value(1)
+          .max_int_value(MAX_LAST as u64),
+      ),
+    CreateCommand::new("status").description("查看机器人运行状态和消息队列情况"),
+    CreateCommand::new("maintenance")
+      .description("暂停或恢复向 Discord 发送消息，期间公告继续轮询并暂存")
+      .default_member_permissions(Permissions::MANAGE_GUILD)
+      .add_option(
+        CreateCommandOption::new(CommandOptionType::String, "state", "开启或关闭")
+          .required(true)
+          .add_string_choice("on", "on")
+          .add_string_choice("off", "off"),
+      ),
+    CreateCommand::new("reinit")
+      .description("重新拉取某场比赛的全部公告，把现有公告全部标记为已处理")
+      .default_member_permissions(Permissions::MANAGE_GUILD)
+      .add_option(
+        CreateCommandOption::new(CommandOptionType::Integer, "match", "比赛 ID").required(true),
+      ),
+    CreateCommand::new("ping").description("查看网关延迟、GZCTF 响应时间和运行时长"),
+    CreateCommand::new("debug")
+      .description("运行时诊断信息")
+      .default_member_permissions(Permissions::MANAGE_GUILD)
+      .add_option(CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "tasks",
+        "列出后台任务及其最后活动时间",
+      )),
+    CreateCommand::new("setup")
+      .description("检查机器人在本服务器的配置")
+      .default_member_permissions(Permissions::MANAGE_GUILD)
+      .add_option(CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "check",
+        "列出已启用功能在本服务器缺少的权限和 Intent",
+      )),
+    CreateCommand::new("stats")
+      .description("查看某场比赛的参赛统计")
+      .add_option(
+        CreateCommandOption::new(CommandOptionType::Integer, "match", "比赛 ID").required(true),
+      ),
+    CreateCommand::new("scoreboard")
+      .description("查看某场比赛的排行榜")
+      .add_option(
+        CreateCommandOption::new(CommandOptionType::Integer, "match", "比赛 ID").required(true),
+      ),
+    CreateCommand::new("challenges")
+      .description("查看某场比赛的题目列表和解出人数")
+      .add_option(
+        CreateCommandOption::new(CommandOptionType::Integer, "match", "比赛 ID").required(true),
+      ),
+    CreateCommand::new("queue")
+      .description("消息队列诊断")
+      .add_option(CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "status",
+        "列出等待重试的消息",
+      )),
+  ]
+}
+
+pub async fn register(ctx: &Context) {
+  match Command::set_global_commands(&ctx.http, definitions()).await {
+    Ok(commands) => log::success(format!("Registered {} slash commands", commands.len())),
+    Err(e) => log::error(format!("Failed to register slash commands: {}", e)),
+  }
+}
+
+pub async fn execute(
+  ctx: &Context,
+  command: &CommandInteraction,
+  handler: &BotHandler,
+) -> Result<Reply> {
+  match command.data.name.as_str() {
+    "linkteam" => linkteam(command, handler).await.map(Reply::from),
+    "unlinkteam" => unlinkteam(command, handler).await.map(Reply::from),
+    "approvelink" => approvelink(command, handler).await.map(Reply::from),
+    "subscribe" => subscribe(command, handler).await.map(Reply::from),
+    "unsubscribe" => unsubscribe(command, handler).await.map(Reply::from),
+    "last" => last(command, handler).await,
+    "status" => status(handler).await,
+    "maintenance" => maintenance(command, handler).map(Reply::from),
+    "reinit" => reinit(command, handler).await.map(Reply::from),
+    "ping" => Ok(ping(ctx, handler).await),
+    "stats" => stats(command, handler).await,
+    "scoreboard" => scoreboard(command, handler).await,
+    "challenges" => challenges(command, handler).await,
+    "queue" => queue(command, handler).await,
+    "debug" => debug(command),
+    "setup" => setup(ctx, command, handler).await,
+    other => Err(anyhow!("unknown command: {}", other)),
+  }
+}
+
+// 回复延迟应答时占位的那条仅自己可见的消息，多页时记下页面供翻页按钮使用
+pub async fn respond(
+  ctx: &Context,
+  command: &CommandInteraction,
+  reply: Reply,
+  paginator: &Paginator,
+) -> Result<()> {
+  let message = command
+    .edit_response(&ctx.http, Paginator::first_page(&reply.pages))
+    .await?;
+  paginator.remember(message.id.get(), reply.pages);
+  Ok(())
+}
+
+// 公开命令失败时删掉公开的占位消息，改为发送仅调用者可见的后续消息
+pub async fn respond_privately(
+  ctx: &Context,
+  command: &CommandInteraction,
+  reply: Reply,
+  public: bool,
+  paginator: &Paginator,
+) -> Result<()> {
+  if !public {
+    return respond(ctx, command, reply, paginator).await;
+  }
+  command.delete_response(&ctx.http).await?;
+  let page = reply.pages.into_iter().next().unwrap_or_default();
+  let mut message = CreateInteractionResponseFollowup::new()
+    .ephemeral(true)
+    .embeds(page.embeds);
+  if let Some(content) = page.content {
+    message = message.content(content);
+  }
+  command.create_followup(&ctx.http, message).await?;
+  Ok(())
+}
+
+// 文字命令没有延迟应答，直接在原频道回复；只发送第一页
+pub async fn reply_to(ctx: &Context, msg: &Message, reply: Reply) -> Result<()> {
+  let page = reply.pages.into_iter().next().unwrap_or_default();
+  let mut message = CreateMessage::new()
+    .embeds(page.embeds)
+    .reference_message(msg);
+  if let Some(content) = page.content {
+    message = message.content(content);
+  }
+  msg.channel_id.send_message(&ctx.http, message).await?;
+  Ok(())
+}
+
+// 把错误链中的 HTTP 错误翻译成调用者能看懂的原因
+pub fn error_reply(error: &anyhow::Error) -> Reply {
+  let cause = error.chain().find_map(|cause| {
+    if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+      return Some(match e.status() {
+        Some(status) => format!("GZCTF 返回 {}", status),
+        None if e.is_timeout() => "请求 GZCTF 超时".to_string(),
+        None if e.is_connect() => "无法连接 GZCTF".to_string(),
+        None => format!("请求 GZCTF 失败: {}", e),
+      });
+    }
+    match cause.downcast_ref::<serenity::Error>() {
+      Some(serenity::Error::Http(e)) => Some(match e.status_code() {
+        Some(status) => format!("Discord 返回 {}", status),
+        None => format!("请求 Discord 失败: {}", e),
+      }),
+      _ => None,
+    }
+  });
+
+  let message = match cause {
+    Some(cause) if error.chain().count() > 1 => format!("{}: {}", error, cause),
+    Some(cause) => cause,
+    None => format!("{:#}", error),
+  };
+  Reply::from(format!("命令执行失败: {}", message))
+}
+
+fn string_option<'a>(options: &'a [ResolvedOption<'a>], name: &str) -> Option<&'a str> {
+  options.iter().find_map(|option| match option.value {
+    ResolvedValue::String(value) if option.name == name => Some(value),
+    _ => None,
+  })
+}
+
+fn integer_option(options: &[ResolvedOption<'_>], name: &str) -> Option<i64> {
+  options.iter().find_map(|option| match option.value {
+    ResolvedValue::Integer(value) if option.name == name => Some(value),
+    _ => None,
+  })
+}
+
+async fn linkteam(command: &CommandInteraction, handler: &BotHandler) -> Result<String> {
+  let options = command.data.options();
+  let team = string_option(&options, "team")
+    .map(str::trim)
+    .filter(|t| !t.is_empty())
+    .ok_or_else(|| anyhow!("缺少队伍名"))?;
+
+  let current = handler.links.request(command.user.id.get(), team).await?;
+  Ok(match current {
+    Some(old) if old != team => format!(
+      "已提交改为关联队伍 **{}** 的申请，管理员确认前仍关联 **{}**",
+      team, old
+    ),
+    _ => format!("已提交关联到队伍 **{}** 的申请，等待管理员确认", team),
+  })
+}
+
+// 只有确认过的关联才会在祝贺消息中被提及、获得一血身份组
+async fn approvelink(command: &CommandInteraction, handler: &BotHandler) -> Result<String> {
+  let options = command.data.options();
+  let user = options.iter().find_map(|option| match option.value {
+    ResolvedValue::User(user, _) if option.name == "user" => Some(user),
+    _ => None,
+  });
+
+  let Some(user) = user else {
+    let pending = handler.links.pending().await;
+    if pending.is_empty() {
+      return Ok("没有待确认的关联申请".to_string());
+    }
+    let lines: Vec<String> = pending
+      .iter()
+      .map(|(user_id, team)| format!("- <@{}> → **{}**", user_id, team))
+      .collect();
+    return Ok(trunc_text(
+      &format!("待确认的关联申请:\n{}", lines.join("\n")),
+      2000,
+    ));
+  };
+
+  Ok(
+    match handler
+      .links
+      .approve(user.id.get(), &command.user.name)
+      .await?
+    {
+      Some(team) => format!("已确认 <@{}> 关联到队伍 **{}**", user.id, team),
+      None => format!("<@{}> 没有待确认的关联申请", user.id),
+    },
+  )
+}
+
+async fn unlinkteam(command: &CommandInteraction, handler: &BotHandler) -> Result<String> {
+  Ok(match handler.links.unlink(command.user.id.get()).await? {
+    Some(team) => format!("已取消与队伍 **{}** 的关联", team),
+    None => "你尚未关联任何队伍".to_string(),
+  })
+}
+
+// 分类以题目缓存为准，大小写不同时按平台上的写法保存
+async fn subscribe(command: &CommandInteraction, handler: &BotHandler) -> Result<String> {
+  let options = command.data.options();
+  let match_id = integer_option(&options, "match")
+    .and_then(|id| u32::try_from(id).ok())
+    .ok_or_else(|| anyhow!("无效的比赛 ID"))?;
+  let category = string_option(&options, "category")
+    .map(str::trim)
+    .filter(|c| !c.is_empty())
+    .ok_or_else(|| anyhow!("缺少题目分类"))?;
+
+  let match_config = handler
+    .config
+    .get_matches()
+    .into_iter()
+    .find(|m| m.id == match_id)
+    .ok_or_else(|| anyhow!("未监控比赛 {}", match_id))?;
+
+  let client = platform::connect(&handler.config.gzctf, &handler.limiter)?;
+  handler
+    .challenges
+    .refresh_if_stale(client.as_ref(), match_id)
+    .await
+    .context("获取题目信息失败")?;
+  let mut categories: Vec<String> = handler
+    .challenges
+    .list(match_id)
+    .await
+    .into_iter()
+    .map(|c| c.category)
+    .collect();
+  categories.dedup();
+
+  let Some(category) = categories.iter().find(|c| c.eq_ignore_ascii_case(category)) else {
+    return Ok(format!(
+      "比赛 {} 没有分类 **{}**，现有分类: {}",
+      match_id,
+      category,
+      categories.join(", ")
+    ));
+  };
+  if !match_config.announces_category(category) {
+    return Ok(format!("比赛 {} 不播报分类 **{}**", match_id, category));
+  }
+
+  let user_id = command.user.id.get();
+  if !handler
+    .subscriptions
+    .subscribe(user_id, match_id, category)
+    .await?
+  {
+    return Ok(format!("你已订阅比赛 {} 的 **{}**", match_id, category));
+  }
+  Ok(format!(
+    "已订阅比赛 {} 的 **{}**，新的提示和血榜会私信给你（共 {} 项订阅）",
+    match_id,
+    category,
+    handler.subscriptions.of(user_id).await.len()
+  ))
+}
+
+async fn unsubscribe(command: &CommandInteraction, handler: &BotHandler) -> Result<String> {
+  let options = command.data.options();
+  let match_id = match integer_option(&options, "match") {
+    Some(id) => Some(u32::try_from(id).map_err(|_| anyhow!("无效的比赛 ID"))?),
+    None => None,
+  };
+  let category = string_option(&options, "category")
+    .map(str::trim)
+    .filter(|c| !c.is_empty());
+
+  let removed = handler
+    .subscriptions
+    .unsubscribe(command.user.id.get(), match_id, category)
+    .await?;
+  if removed.is_empty() {
+    return Ok("没有符合条件的订阅".to_string());
+  }
+  let removed: Vec<String> = removed
+    .iter()
+    .map(|s| format!("比赛 {} / {}", s.match_id, s.category))
+    .collect();
+  Ok(format!("已取消订阅: {}", removed.join(", ")))
+}
+
+async fn last(command: &CommandInteraction, handler: &BotHandler) -> Result<Reply> {
+  let options = command.data.options();
+  let match_id = integer_option(&options, "match")
+    .and_then(|id| u32::try_from(id).ok())
+    .ok_or_else(|| anyhow!("无效的比赛 ID"))?;
+  let count = integer_option(&options, "count")
+    .map(|c| c.clamp(1, MAX_LAST as i64) as usize)
+    .unwrap_or(5);
+
+  let notices = handler.recent.last(match_id, count).await;
+  if notices.is_empty() {
+    return Ok(Reply::from(format!("比赛 {} 暂无最近公告记录", match_id)));
+  }
+
+  let branding = handler
+    .config
+    .get_matches()
+    .into_iter()
+    .find(|m| m.id == match_id)
+    .map(|m| m.branding)
+    .unwrap_or_default();
+  let pages = notices
+    .iter()
+    .map(|recent| Page {
+      content: Some(format!("比赛 {} 最近 {} 条公告:", match_id, notices.len())),
+      embeds: vec![create_embed(
+        &recent.notice,
+        &recent.notice_type,
+        recent.match_name.as_deref(),
+        match_id,
+        &handler.config.gzctf.url,
+        handler.config.gzctf.platform,
+        &branding,
+      )],
+    })
+    .collect();
+
+  Ok(Reply::paged(pages))
+}
+
+async fn status(handler: &BotHandler) -> Result<Reply> {
+  let metrics = handler.message_queue.metrics().await;
+  let oldest = metrics
+    .oldest_age
+    .map(|age| format!("{} 前", format_age(age)))
+    .unwrap_or_else(|| "无".to_string());
+  let gateway = handler.alerts.gateway_stats().await;
+  let connection = match (gateway.connected_since, gateway.down_since) {
+    (Some(since), _) => format!("已连接 {}", format_age(since.elapsed())),
+    (None, Some(since)) => format!("已断开 {}", format_age(since.elapsed())),
+    (None, None) => "未连接".to_string(),
+  };
+
+  let embed = CreateEmbed::new()
+    .title("**运行状态**")
+    .color(Colour::from_rgb(59, 130, 246))
+    .field(
+      "监控比赛",
+      handler.config.get_matches().len().to_string(),
+      true,
+    )
+    .field(
+      "轮询间隔",
+      format!("{}s", handler.config.gzctf.poll_interval),
+      true,
+    )
+    .field("队列深度", metrics.depth.to_string(), true)
+    .field("累计重试", metrics.retries.to_string(), true)
+    .field("已持久化", metrics.persisted.to_string(), true)
+    .field("最早未送达", oldest, true)
+    .field(
+      "维护模式",
+      if handler.delivery.maintenance().is_active() {
+        "开启"
+      } else {
+        "关闭"
+      },
+      true,
+    )
+    .field("网关连接", connection, true)
+    .field("重连次数", gateway.reconnects().to_string(), true)
+    .field("累计断线", format_age(gateway.total_downtime()), true);
+
+  Ok(Reply::embed(embed))
+}
+
+fn maintenance(command: &CommandInteraction, handler: &BotHandler) -> Result<String> {
+  let options = command.data.options();
+  let active = match string_option(&options, "state") {
+    Some("on") => true,
+    Some("off") => false,
+    _ => return Err(anyhow!("无效的状态，应为 on 或 off")),
+  };
+  log::info(format!(
+    "{} turned maintenance mode {}",
+    command.user.name,
+    if active { "on" } else { "off" }
+  ));
+
+  Ok(
+    match (
+      handler
+        .delivery
+        .maintenance()
+        .set(active, &command.user.name),
+      active,
+    ) {
+      (true, true) => "已进入维护模式：暂停发送消息，公告继续轮询并暂存".to_string(),
+      (true, false) => "已退出维护模式：暂存的公告将在下一轮以汇总形式发出".to_string(),
+      (false, true) => "维护模式已处于开启状态".to_string(),
+      (false, false) => "维护模式未开启".to_string(),
+    },
+  )
+}
+
+// 手动修改 tracker 状态或 GZCTF 数据重置之后使用，已有公告不会再被播报
+async fn reinit(command: &CommandInteraction, handler: &BotHandler) -> Result<String> {
+  let options = command.data.options();
+  let match_id = integer_option(&options, "match")
+    .and_then(|id| u32::try_from(id).ok())
+    .ok_or_else(|| anyhow!("无效的比赛 ID"))?;
+  if !handler
+    .config
+    .get_matches()
+    .iter()
+    .any(|m| m.id == match_id)
+  {
+    return Ok(format!("未监控比赛 {}", match_id));
+  }
+
+  let client = platform::connect(&handler.config.gzctf, &handler.limiter)?;
+  let notices = client
+    .fetch_notices(match_id)
+    .await
+    .context("获取公告失败")?;
+
+  let by_type = NoticesByType::new(&notices);
+  let mut tracker = handler.tracker.write().await;
+  for notice_type in NoticeType::all() {
+    tracker.reset(
+      match_id,
+      &format!("{:?}", notice_type),
+      by_type.get(&notice_type),
+    );
+  }
+  tracker.save_to_disk().await.context("保存 tracker 失败")?;
+
+  log::info(format!(
+    "{} re-initialized the tracker for match {} ({} notices)",
+    command.user.name,
+    match_id,
+    notices.len()
+  ));
+  Ok(format!(
+    "已重新初始化比赛 {}：{} 条现有公告标记为已处理，之后只播报新公告",
+    match_id,
+    notices.len()
+  ))
+}
+
+async fn stats(command: &CommandInteraction, handler: &BotHandler) -> Result<Reply> {
+  let options = command.data.options();
+  let match_id = integer_option(&options, "match")
+    .and_then(|id| u32::try_from(id).ok())
+    .ok_or_else(|| anyhow!("无效的比赛 ID"))?;
+
+  let client = platform::connect(&handler.config.gzctf, &handler.limiter)?;
+  let scoreboard = client
+    .fetch_scoreboard(match_id)
+    .await
+    .context("获取排行榜失败")?;
+  handler.challenges.update(match_id, &scoreboard).await;
+
+  let active = scoreboard.items.iter().filter(|t| t.score > 0).count();
+  let challenges: Vec<_> = scoreboard.challenges.values().flatten().collect();
+  let solves: u64 = challenges.iter().map(|c| u64::from(c.solved)).sum();
+  let mut unsolved: Vec<String> = challenges
+    .iter()
+    .filter(|c| c.solved == 0)
+    .map(|c| format!("{} / {}", c.category, c.title))
+    .collect();
+  unsolved.sort();
+
+  // 分类 -> (题目数, 总分值)
+  let mut categories: Vec<(String, usize, u64)> = Vec::new();
+  for challenge in handler.challenges.list(match_id).await {
+    match categories.last_mut() {
+      Some((category, count, points)) if *category == challenge.category => {
+        *count += 1;
+        *points += u64::from(challenge.score);
+      }
+      _ => categories.push((challenge.category, 1, u64::from(challenge.score))),
+    }
+  }
+  let categories_text = if categories.is_empty() {
+    "无".to_string()
+  } else {
+    let lines: Vec<String> = categories
+      .iter()
+      .map(|(category, count, points)| format!("{}: {} 题 / {} 分", category, count, points))
+      .collect();
+    trunc_text(&lines.join("\n"), 1000)
+  };
+
+  let unsolved_text = if unsolved.is_empty() {
+    "无".to_string()
+  } else {
+    trunc_text(&unsolved.join("\n"), 1000)
+  };
+
+  let embed = CreateEmbed::new()
+    .title(format!("**比赛 {} 参赛统计**", match_id))
+    .color(Colour::from_rgb(59, 130, 246))
+    .field("报名队伍", scoreboard.items.len().to_string(), true)
+    .field("有效队伍", active.to_string(), true)
+    .field("总解题数", solves.to_string(), true)
+    .field("题目分类", categories_text, false)
+    .field(
+      format!("零解题目 ({})", unsolved.len()),
+      unsolved_text,
+      false,
+    );
+
+  Ok(Reply::embed(embed))
+}
+
+async fn scoreboard(command: &CommandInteraction, handler: &BotHandler) -> Result<Reply> {
+  let options = command.data.options();
+  let match_id = integer_option(&options, "match")
+    .and_then(|id| u32::try_from(id).ok())
+    .ok_or_else(|| anyhow!("无效的比赛 ID"))?;
+
+  let client = platform::connect(&handler.config.gzctf, &handler.limiter)?;
+  let scoreboard = client
+    .fetch_scoreboard(match_id)
+    .await
+    .context("获取排行榜失败")?;
+  handler.challenges.update(match_id, &scoreboard).await;
+
+  let mut teams: Vec<_> = scoreboard.items.iter().collect();
+  teams.sort_by_key(|team| team.rank);
+  let lines: Vec<String> = teams
+    .iter()
+    .map(|team| format!("`#{}` {} · {} 分", team.rank, team.name, team.score))
+    .collect();
+  let embed = || {
+    CreateEmbed::new()
+      .title(format!(
+        "**比赛 {} 排行榜 ({} 支队伍)**",
+        match_id,
+        lines.len()
+      ))
+      .color(Colour::from_rgb(59, 130, 246))
+  };
+  if lines.is_empty() {
+    return Ok(Reply::embed(embed().description("暂无队伍")));
+  }
+
+  let pages = lines
+    .chunks(TEAMS_PER_PAGE)
+    .map(|chunk| Page {
+      content: None,
+      embeds: vec![embed().description(trunc_text(&chunk.join("\n"), 4000))],
+    })
+    .collect();
+  Ok(Reply::paged(pages))
+}
+
+// 解出人数变化快，每次都从排行榜接口读取，顺带刷新题目缓存
+async fn challenges(command: &CommandInteraction, handler: &BotHandler) -> Result<Reply> {
+  let options = command.data.options();
+  let match_id = integer_option(&options, "match")
+    .and_then(|id| u32::try_from(id).ok())
+    .ok_or_else(|| anyhow!("无效的比赛 ID"))?;
+
+  let client = platform::connect(&handler.config.gzctf, &handler.limiter)?;
+  let scoreboard = client
+    .fetch_scoreboard(match_id)
+    .await
+    .context("获取排行榜失败")?;
+  handler.challenges.update(match_id, &scoreboard).await;
+
+  let mut challenges: Vec<_> = scoreboard.challenges.values().flatten().collect();
+  challenges.sort_by(|a, b| (&a.category, &a.title).cmp(&(&b.category, &b.title)));
+  let lines: Vec<String> = challenges
+    .iter()
+    .map(|c| {
+      format!(
+        "`{}` {} · {} 分 · {} 解",
+        c.category, c.title, c.score, c.solved
+      )
+    })
+    .collect();
+  let embed = || {
+    CreateEmbed::new()
+      .title(format!("**比赛 {} 题目 ({})**", match_id, lines.len()))
+      .color(Colour::from_rgb(59, 130, 246))
+  };
+  if lines.is_empty() {
+    return Ok(Reply::embed(embed().description("暂无题目")));
+  }
+
+  let pages = lines
+    .chunks(CHALLENGES_PER_PAGE)
+    .map(|chunk| Page {
+      content: None,
+      embeds: vec![embed().description(trunc_text(&chunk.join("\n"), 4000))],
+    })
+    .collect();
+  Ok(Reply::paged(pages))
+}
+
+async fn queue(command: &CommandInteraction, handler: &BotHandler) -> Result<Reply> {
+  let options = command.data.options();
+  match options.first().map(|option| option.name) {
+    Some("status") => Ok(queue_status(handler).await),
+    _ => Err(anyhow!("未知的队列命令")),
+  }
+}
+
+// 队列指标之外逐条列出等待重试的消息，方便判断是哪个频道一直发不出去
+async fn queue_status(handler: &BotHandler) -> Reply {
+  let metrics = handler.message_queue.metrics().await;
+  let lines: Vec<String> = handler
+    .message_queue
+    .entries()
+    .await
+    .iter()
+    .map(|entry| {
+      let channel = entry
+        .channel_id
+        .map(|id| format!("<#{}>", id))
+        .unwrap_or_else(|| "主播报频道".to_string());
+      format!(
+        "`{}` 比赛 {} {:?} → {} · 已重试 {} 次 · 入队 {} 前 · {} 后重试",
+        entry.id,
+        entry.match_id,
+        entry.notice_type,
+        channel,
+        entry.retry_count,
+        format_age(entry.age),
+        format_age(entry.retry_in)
+      )
+    })
+    .collect();
+  let embed = || {
+    CreateEmbed::new()
+      .title(format!("**消息队列 ({})**", metrics.depth))
+      .color(Colour::from_rgb(100, 116, 139))
+      .field("累计重试", metrics.retries.to_string(), true)
+      .field("已持久化", metrics.persisted.to_string(), true)
+  };
+  if lines.is_empty() {
+    return Reply::embed(embed().description("队列为空"));
+  }
+
+  let pages = lines
+    .chunks(QUEUE_PER_PAGE)
+    .map(|chunk| Page {
+      content: None,
+      embeds: vec![embed().description(trunc_text(&chunk.join("\n"), 4000))],
+    })
+    .collect();
+  Reply::paged(pages)
+}
+
+async fn setup(ctx: &Context, command: &CommandInteraction, handler: &BotHandler) -> Result<Reply> {
+  let options = command.data.options();
+  match options.first().map(|option| option.name) {
+    Some("check") => setup_check(ctx, command, handler).await,
+    _ => Err(anyhow!("未知的设置命令")),
+  }
+}
+
+// 按配置逐项检查本服务器内的频道和身份组权限，其他服务器的频道不显示；
+// 当前频道按播报频道的要求检查，方便确认新频道能否直接使用
+async fn setup_check(
+  ctx: &Context,
+  command: &CommandInteraction,
+  handler: &BotHandler,
+) -> Result<Reply> {
+  let guild_id = command
+    .guild_id
+    .ok_or_else(|| anyhow!("请在服务器内使用该命令"))?;
+  let bot_user_id = ctx
+    .http
+    .get_current_user()
+    .await
+    .context("获取机器人信息失败")?
+    .id;
+  let channels: HashSet<u64> = ctx
+    .http
+    .get_channels(guild_id)
+    .await
+    .context("获取频道列表失败")?
+    .iter()
+    .map(|channel| channel.id.get())
+    .collect();
+
+  let mut requirements = permissions::requirements(&handler.config);
+  let current = command.channel_id.get();
+  if !requirements
+    .iter()
+    .any(|r| r.target == Target::Channel(current))
+  {
+    requirements.push(permissions::Requirement {
+      feature: "current",
+      label: "当前频道（按播报频道检查）",
+      target: Target::Channel(current),
+      permissions: permissions::broadcast_permissions(),
+    });
+  }
+
+  let mut lines = vec![message_content_check(ctx).await];
+  for requirement in requirements {
+    let target = match requirement.target {
+      Target::Channel(id) if channels.contains(&id) => format!("<#{}>", id),
+      Target::Guild(id) if id == guild_id.get() => "本服务器".to_string(),
+      _ => continue,
+    };
+    let line = match permissions::check(ctx, &requirement, bot_user_id).await {
+      Ok((_, missing)) if missing.is_empty() => format!("✅ {} {}", requirement.label, target),
+      Ok((_, missing)) => format!(
+        "❌ {} {}: 缺少 {}",
+        requirement.label,
+        target,
+        missing.get_permission_names().join(", ")
+      ),
+      Err(e) => format!("⚠️ {} {}: 无法检查 ({})", requirement.label, target, e),
+    };
+    lines.push(line);
+  }
+
+  let failed = lines.iter().filter(|line| !line.starts_with('✅')).count();
+  let colour = if failed == 0 {
+    Colour::from_rgb(34, 197, 94)
+  } else {
+    Colour::from_rgb(239, 68, 68)
+  };
+  let pages = lines
+    .chunks(CHECKS_PER_PAGE)
+    .map(|chunk| Page {
+      content: None,
+      embeds: vec![
+        CreateEmbed::new()
+          .title(format!("**权限检查：{} 项有问题**", failed))
+          .color(colour)
+          .description(trunc_text(&chunk.join("\n"), 4000)),
+      ],
+    })
+    .collect();
+  Ok(Reply::paged(pages))
+}
+
+// Message Content 是特权 Intent，需要在开发者后台开启，!ping 依赖它读取消息
+async fn message_content_check(ctx: &Context) -> String {
+  let flags = match ctx.http.get_current_application_info().await {
+    Ok(info) => info.flags,
+    Err(e) => return format!("⚠️ Message Content Intent: 无法读取应用信息 ({})", e),
+  };
+  let enabled = flags.is_some_and(|flags| {
+    flags.intersects(
+      ApplicationFlags::GATEWAY_MESSAGE_CONTENT | ApplicationFlags::GATEWAY_MESSAGE_CONTENT_LIMITED,
+    )
+  });
+  if enabled {
+    "✅ Message Content Intent".to_string()
+  } else {
+    "❌ Message Content Intent: 未在开发者后台开启，!ping 无法读取消息".to_string()
+  }
+}
+
+// 任一项测量失败只显示在对应字段里，保证命令本身总能回复
+pub async fn ping(ctx: &Context, handler: &BotHandler) -> Reply {
+  let gateway = match ctx.data.read().await.get::<ShardManagerKey>() {
+    Some(manager) => manager
+      .runners
+      .lock()
+      .await
+      .get(&ctx.shard_id)
+      .and_then(|runner| runner.latency),
+    None => None,
+  };
+  let gateway = gateway
+    .map(|latency| format!("{}ms", latency.as_millis()))
+    .unwrap_or_else(|| "等待首次心跳".to_string());
+
+  let api = match handler.config.get_matches().first() {
+    Some(match_config) => {
+      let started = Instant::now();
+      let result = match platform::connect(&handler.config.gzctf, &handler.limiter) {
+        Ok(client) => client.fetch_game(match_config.id).await.map(|_| ()),
+        Err(e) => Err(e),
+      };
+      match result {
+        Ok(()) => format!("{}ms", started.elapsed().as_millis()),
+        Err(e) => {
+          log::error(format!("GZCTF ping failed: {:#}", e));
+          "请求失败".to_string()
+        }
+      }
+    }
+    None => "未配置比赛".to_string(),
+  };
+
+  let embed = CreateEmbed::new()
+    .title("**Pong!**")
+    .color(Colour::from_rgb(34, 197, 94))
+    .field("网关延迟", gateway, true)
+    .field("GZCTF 响应", api, true)
+    .field("运行时长", format_age(handler.started_at.elapsed()), true);
+
+  Reply::embed(embed)
+}
+
+fn debug(command: &CommandInteraction) -> Result<Reply> {
+  let options = command.data.options();
+  match options.first().map(|option| option.name) {
+    Some("tasks") => Ok(debug_tasks()),
+    _ => Err(anyhow!("未知的调试命令")),
+  }
+}
+
+// 最后活动时间远早于任务的循环间隔时，任务多半卡在某个 await 上
+fn debug_tasks() -> Reply {
+  let now = chrono::Local::now();
+  let lines: Vec<String> = tasks::snapshot()
+    .iter()
+    .map(|task| {
+      let idle = (now - task.last_active).to_std().unwrap_or_default();
+      format!(
+        "`{}` 启动于 {} · 最后活动 {} 前",
+        task.name,
+        task.started.format("%m-%d %H:%M:%S"),
+        format_age(idle)
+      )
+    })
+    .collect();
+  let embed = || {
+    CreateEmbed::new()
+      .title(format!("**后台任务 ({})**", lines.len()))
+      .color(Colour::from_rgb(100, 116, 139))
+  };
+  if lines.is_empty() {
+    return Reply::embed(embed().description("没有正在运行的后台任务"));
+  }
+
+  let pages = lines
+    .chunks(TASKS_PER_PAGE)
+    .map(|chunk| Page {
+      content: None,
+      embeds: vec![embed().description(trunc_text(&chunk.join("\n"), 4000))],
+    })
+    .collect();
+  Reply::paged(pages)
+}
+
+fn format_age(age: Duration) -> String {
+  let secs = age.as_secs();
+  match secs {
+    0..60 => format!("{}s", secs),
+    60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+    _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+  }
+}
@@ -0,0 +1,96 @@
+use chrono::DateTime;
+use serde::de::{DeserializeOwned, Deserializer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// GZCTF 各版本之间字段大小写、类型和外层结构都有过变化，这里集中做宽松解析
+
+// 服务器版本提示：auto 按返回内容自动兼容，legacy 为不支持分页、用 content 字段的旧版本
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiVersion {
+  #[default]
+  Auto,
+  Legacy,
+  V1,
+}
+
+impl ApiVersion {
+  pub fn supports_paging(self) -> bool {
+    self != ApiVersion::Legacy
+  }
+}
+
+// 列表接口可能直接返回数组，也可能包在 { "data": [...] } 里；
+// 逐项解析，单项失败只跳过该项并返回错误描述
+pub fn items<T: DeserializeOwned>(value: Value) -> (Vec<T>, Vec<String>) {
+  let list = match value {
+    Value::Array(list) => list,
+    Value::Object(mut object) => match object.remove("data").or_else(|| object.remove("Data")) {
+      Some(Value::Array(list)) => list,
+      _ => return (Vec::new(), vec!["response is not a list".to_string()]),
+    },
+    Value::Null => Vec::new(),
+    _ => return (Vec::new(), vec!["response is not a list".to_string()]),
+  };
+
+  let mut parsed = Vec::with_capacity(list.len());
+  let mut errors = Vec::new();
+  for item in list {
+    match serde_json::from_value(item) {
+      Ok(item) => parsed.push(item),
+      Err(e) => errors.push(e.to_string()),
+    }
+  }
+  (parsed, errors)
+}
+
+// 接受数字或数字字符串
+pub fn id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+  match Value::deserialize(deserializer)? {
+    Value::Number(n) => n
+      .as_u64()
+      .ok_or_else(|| serde::de::Error::custom(format!("invalid id {}", n))),
+    Value::String(s) => s
+      .trim()
+      .parse()
+      .map_err(|_| serde::de::Error::custom(format!("invalid id {:?}", s))),
+    other => Err(serde::de::Error::custom(format!("invalid id {}", other))),
+  }
+}
+
+// 接受毫秒/秒时间戳、数字字符串或 RFC 3339 时间，缺失时为 0；秒与毫秒的区分由调用方处理
+pub fn timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+  match Value::deserialize(deserializer)? {
+    Value::Null => Ok(0),
+    Value::Number(n) => Ok(
+      n.as_u64()
+        .or_else(|| n.as_f64().filter(|f| *f >= 0.0).map(|f| f as u64))
+        .unwrap_or(0),
+    ),
+    Value::String(s) => {
+      if let Ok(n) = s.trim().parse::<u64>() {
+        return Ok(n);
+      }
+      DateTime::parse_from_rfc3339(s.trim())
+        .map(|dt| dt.timestamp_millis().max(0) as u64)
+        .map_err(|_| serde::de::Error::custom(format!("invalid time {:?}", s)))
+    }
+    other => Err(serde::de::Error::custom(format!("invalid time {}", other))),
+  }
+}
+
+// 字符串数组中偶尔混入数字或 null
+pub fn strings<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+  let values = Option::<Vec<Value>>::deserialize(deserializer)?.unwrap_or_default();
+  Ok(
+    values
+      .into_iter()
+      .map(|value| match value {
+        Value::String(s) => s,
+        Value::Null => String::new(),
+        other => other.to_string(),
+      })
+      .collect(),
+  )
+}
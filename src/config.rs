@@ -1,9 +1,92 @@
-use serde::Deserialize;
+use anyhow::{Context as _, anyhow};
+use chrono::{DateTime, FixedOffset, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::model::colour::Colour;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::path::Path;
+use toml::Value;
+
+use crate::compat::ApiVersion;
+use crate::emoji;
+use crate::models::{Notice, NoticeType};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct DiscordConfig {
   pub token: String,
   pub channel_id: u64,
+  #[serde(default)]
+  pub admin_channel_id: Option<u64>,
+  // 抄送所有发出消息的只写审计频道
+  #[serde(default)]
+  pub audit_channel_id: Option<u64>,
+  // 每轮轮询最多发出的公告数，其余留到下一轮，0 表示不限制
+  #[serde(default = "default_max_messages_per_cycle")]
+  pub max_messages_per_cycle: usize,
+  // 在机器人状态中显示监控情况
+  #[serde(default = "default_presence")]
+  pub presence: bool,
+  // 被加入新服务器时私信邀请者上手说明和配置片段
+  #[serde(default = "default_onboarding")]
+  pub onboarding: bool,
+  // 主播报频道 embed 上方的纯文本，可用变量: {match} {match_id} {type} {team} {challenge}
+  #[serde(default)]
+  pub content_prefix: Option<String>,
+  // embed 页脚模板，默认只显示公告时间，可用变量同上，另有 {time}
+  #[serde(default)]
+  pub footer: Option<String>,
+  #[serde(default)]
+  pub format: MessageFormat,
+  #[serde(default)]
+  pub templates: Vec<NoticeTemplate>,
+}
+
+impl DiscordConfig {
+  pub fn prefix_for(&self, notice_type: &NoticeType) -> Option<&str> {
+    resolve_template(&self.templates, notice_type, |t| &t.content)
+      .or(self.content_prefix.as_deref())
+  }
+
+  pub fn footer_for(&self, notice_type: &NoticeType) -> Option<&str> {
+    resolve_template(&self.templates, notice_type, |t| &t.footer).or(self.footer.as_deref())
+  }
+}
+
+// 按公告类型覆盖所在频道的 content_prefix 和 footer，
+// 例如公开频道用热闹的文案，出题人频道只留关键信息
+#[derive(Debug, Deserialize, Clone)]
+pub struct NoticeTemplate {
+  // 适用的公告类型，留空表示全部
+  #[serde(default)]
+  pub types: Vec<NoticeType>,
+  #[serde(default)]
+  pub content: Option<String>,
+  #[serde(default)]
+  pub footer: Option<String>,
+}
+
+// 按配置顺序取第一个适用且设置了该项的模板
+fn resolve_template<'a>(
+  templates: &'a [NoticeTemplate],
+  notice_type: &NoticeType,
+  field: impl Fn(&NoticeTemplate) -> &Option<String>,
+) -> Option<&'a str> {
+  templates
+    .iter()
+    .filter(|t| t.types.is_empty() || t.types.contains(notice_type))
+    .find_map(|t| field(t).as_deref())
+}
+
+fn default_presence() -> bool {
+  true
+}
+
+fn default_onboarding() -> bool {
+  true
+}
+
+fn default_max_messages_per_cycle() -> usize {
+  10
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -14,37 +97,1376 @@ pub struct GzctfConfig {
   pub matches: Vec<MatchConfig>,
   #[serde(default)]
   pub match_id: Option<u32>,
+  // 单次请求遇到瞬时错误时的最大重试次数
+  #[serde(default = "default_max_retries")]
+  pub max_retries: u32,
+  // API 路径前缀，反向代理改写了 /api 时需要调整
+  #[serde(default = "default_api_prefix")]
+  pub api_prefix: String,
+  #[serde(flatten)]
+  pub http: HttpOptions,
+  // 增量拉取公告的分页大小，0 表示每次都拉取完整列表
+  #[serde(default = "default_page_size")]
+  pub page_size: u32,
+  // 每隔多少次增量拉取做一次完整拉取，以发现被编辑的公告
+  #[serde(default = "default_full_fetch_every")]
+  pub full_fetch_every: u32,
+  // 题目名称、分类、分值等信息的缓存时间（秒）
+  #[serde(default = "default_challenge_cache_ttl")]
+  pub challenge_cache_ttl: u64,
+  // 所有比赛、命令共享的最大并发请求数，0 表示不限制
+  #[serde(default = "default_max_in_flight")]
+  pub max_in_flight: usize,
+  // 相邻两次请求的最小间隔（毫秒）
+  #[serde(default)]
+  pub min_request_interval_ms: u64,
+  // 服务器版本提示，决定是否使用分页等新版接口特性
+  #[serde(default)]
+  pub api_version: ApiVersion,
+  // 比赛平台，非 GZCTF 平台只支持比赛公告
+  #[serde(default)]
+  pub platform: PlatformKind,
+  // CTFd 的访问令牌，比赛不公开时需要
+  #[serde(default)]
+  pub api_token: Option<String>,
+  // GZCTF 登录后的 Cookie（如 "GZCTF_Token=..."），读取比赛事件等需要权限的接口时使用
+  #[serde(default)]
+  pub session_cookie: Option<String>,
+  // platform = "feed" 时读取的公告源
+  #[serde(default)]
+  pub feed: Option<FeedConfig>,
+}
+
+// 任意 JSON 公告接口，字段位置用 JSONPath 风格的路径描述，如 "$.data.items" 或 "attributes.body"
+#[derive(Debug, Deserialize, Clone)]
+pub struct FeedConfig {
+  pub url: String,
+  // 公告数组的位置，"$" 表示响应本身就是数组
+  #[serde(default = "default_feed_root")]
+  pub items: String,
+  // 以下路径相对于单条公告
+  #[serde(default)]
+  pub id: Option<String>,
+  pub content: String,
+  #[serde(default)]
+  pub title: Option<String>,
+  #[serde(default)]
+  pub time: Option<String>,
+  // 映射到 GZCTF 公告类型（Normal、FirstBlood 等），留空均视为比赛公告
+  #[serde(rename = "type", default)]
+  pub notice_type: Option<String>,
+  // 两次请求的最短间隔（秒），期间的轮询复用上次结果
+  #[serde(default = "default_feed_interval")]
+  pub poll_interval: u64,
+}
+
+fn default_feed_root() -> String {
+  "$".to_string()
+}
+
+fn default_feed_interval() -> u64 {
+  30
+}
+
+// 单个 GZCTF 实例的连接选项，每个实例各自构建 HTTP 客户端
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpOptions {
+  // 不校验证书，兼容自签名证书的比赛平台
+  #[serde(default = "default_insecure")]
+  pub insecure: bool,
+  // 额外信任的 CA 证书（PEM）
+  #[serde(default)]
+  pub ca_path: Option<String>,
+  #[serde(default)]
+  pub proxy: Option<String>,
+  // 固定域名解析到指定 IP，绕过有问题的 DNS 记录
+  #[serde(default)]
+  pub resolve: HashMap<String, IpAddr>,
+  // 只通过 IPv4 连接，适用于 AAAA 记录不可用的环境
+  #[serde(default)]
+  pub prefer_ipv4: bool,
+}
+
+fn default_insecure() -> bool {
+  true
+}
+
+fn default_max_retries() -> u32 {
+  3
+}
+
+fn default_api_prefix() -> String {
+  "/api".to_string()
+}
+
+fn default_page_size() -> u32 {
+  20
+}
+
+fn default_full_fetch_every() -> u32 {
+  20
+}
+
+fn default_challenge_cache_ttl() -> u64 {
+  600
+}
+
+fn default_max_in_flight() -> usize {
+  4
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlatformKind {
+  #[default]
+  Gzctf,
+  Ctfd,
+  Feed,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BloodsMode {
+  First,
+  #[default]
+  All,
+  None,
+}
+
+// 配置了 medals 但某一名次不可用时使用的默认标记
+const DEFAULT_MEDALS: [&str; 3] = ["🥇", "🥈", "🥉"];
+
+// 区分同一频道中不同比赛的消息：标题前缀 emoji 和 "#RRGGBB" 颜色
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Branding {
+  #[serde(default)]
+  pub prefix_emoji: Option<String>,
+  #[serde(default)]
+  pub color: Option<String>,
+  // 血播报标题和队伍名前的名次标记，依次对应一二三血，可以是服务器表情 "<:name:id>"
+  #[serde(default)]
+  pub medals: Vec<String>,
+  // 比赛公告附上比赛海报：image 大图，thumbnail 右上角缩略图
+  #[serde(default)]
+  pub poster: Option<PosterPlacement>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PosterPlacement {
+  Image,
+  Thumbnail,
+}
+
+impl Branding {
+  pub fn colour(&self) -> Option<Colour> {
+    let hex = self.color.as_deref()?.trim_start_matches('#');
+    u32::from_str_radix(hex, 16).ok().map(Colour::new)
+  }
+
+  // rank 从 0 开始；未配置 medals 时不显示，格式无效或找不到的表情退回默认奖牌
+  pub fn medal(&self, rank: usize) -> Option<&str> {
+    if self.medals.is_empty() {
+      return None;
+    }
+    match self.medals.get(rank) {
+      Some(medal) if emoji::is_usable(medal) => Some(medal),
+      _ => DEFAULT_MEDALS.get(rank).copied(),
+    }
+  }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct MatchConfig {
   pub id: u32,
   pub name: Option<String>,
+  // 播报哪些血：first 仅一血，all 全部，none 不播报
+  #[serde(default)]
+  pub bloods: BloodsMode,
+  // 题目解出人数少于该值时，在血播报中隐藏题目名
+  #[serde(default)]
+  pub mask_challenges_below: Option<u32>,
+  // 只播报这些分类下题目的新题、提示和血，留空表示不限制
+  #[serde(default)]
+  pub announce_categories: Vec<String>,
+  // 在血榜中附上本题得分和队伍的最新排名
+  #[serde(default)]
+  pub enrich_bloods: bool,
+  // 同一轮内同一道题出现多个血时合并成一条血榜消息
+  #[serde(default)]
+  pub group_bloods: bool,
+  #[serde(flatten)]
+  pub branding: Branding,
+  // 队伍总分越过这些分数时播报
+  #[serde(default)]
+  pub score_milestones: Vec<u64>,
+  // 榜首易主时播报
+  #[serde(default)]
+  pub announce_lead_change: bool,
+  // 两次榜首播报之间的最短间隔（秒），避免两队来回交替时刷屏
+  #[serde(default = "default_lead_change_cooldown")]
+  pub lead_change_cooldown: u64,
+  // 报名截止时间（RFC 3339），留空时以比赛开始时间为准
+  #[serde(default)]
+  pub registration_deadline: Option<String>,
+  // 同一道题在 burst_window 秒内收到这么多次错误提交时告警，需要 gzctf.session_cookie
+  #[serde(default)]
+  pub submission_burst: Option<usize>,
+  #[serde(default = "default_burst_window")]
+  pub burst_window: u64,
+  // 第一次监控该比赛时如何处理已有的公告，之后重启一律跳过
+  #[serde(default)]
+  pub on_start: OnStart,
+}
+
+// "skip_existing" | "replay_last_N" | "replay_since <RFC 3339>"
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum OnStart {
+  #[default]
+  SkipExisting,
+  ReplayLast(usize),
+  // 毫秒时间戳，与公告时间一致
+  ReplaySince(u64),
+}
+
+impl TryFrom<String> for OnStart {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    let value = value.trim();
+    if value == "skip_existing" {
+      return Ok(Self::SkipExisting);
+    }
+    if let Some(count) = value.strip_prefix("replay_last_") {
+      return count
+        .parse()
+        .map(Self::ReplayLast)
+        .map_err(|_| format!("invalid count in on_start '{}'", value));
+    }
+    if let Some(time) = value.strip_prefix("replay_since ") {
+      return DateTime::parse_from_rfc3339(time.trim())
+        .map(|t| Self::ReplaySince(t.timestamp_millis().max(0) as u64))
+        .map_err(|_| format!("on_start '{}' is not followed by an RFC 3339 time", value));
+    }
+    Err(format!(
+      "unknown on_start '{}', expected skip_existing, replay_last_N or replay_since <time>",
+      value
+    ))
+  }
+}
+
+impl OnStart {
+  // 需要重放的公告 ID，其余的标记为已处理
+  pub fn replayed(&self, notices: &[&Notice]) -> HashSet<u64> {
+    match *self {
+      Self::SkipExisting => HashSet::new(),
+      Self::ReplayLast(count) => {
+        let mut ids: Vec<u64> = notices.iter().map(|n| n.id).collect();
+        ids.sort_unstable_by(|a, b| b.cmp(a));
+        ids.into_iter().take(count).collect()
+      }
+      Self::ReplaySince(since) => notices
+        .iter()
+        .filter(|n| n.time >= since)
+        .map(|n| n.id)
+        .collect(),
+    }
+  }
+}
+
+fn default_lead_change_cooldown() -> u64 {
+  300
+}
+
+fn default_burst_window() -> u64 {
+  60
+}
+
+impl MatchConfig {
+  pub fn watches_scores(&self) -> bool {
+    !self.score_milestones.is_empty() || self.announce_lead_change
+  }
+
+  pub fn announces_category(&self, category: &str) -> bool {
+    self.announce_categories.is_empty()
+      || self
+        .announce_categories
+        .iter()
+        .any(|c| c.eq_ignore_ascii_case(category))
+  }
+
+  pub fn announced_types(&self) -> Vec<NoticeType> {
+    NoticeType::all()
+      .into_iter()
+      .filter(|t| match t {
+        NoticeType::FirstBlood => self.bloods != BloodsMode::None,
+        NoticeType::SecondBlood | NoticeType::ThirdBlood => self.bloods == BloodsMode::All,
+        _ => true,
+      })
+      .collect()
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryMode {
+  #[default]
+  Realtime,
+  Digest,
+}
+
+// 频道无法显示 embed 时（例如缺少嵌入链接权限）改为发送 Markdown 文本
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageFormat {
+  #[default]
+  Embed,
+  Text,
+}
+
+// 多服务器部署：每个服务器有自己的播报频道和比赛子集，配置后取代 discord.channel_id
+#[derive(Debug, Deserialize, Clone)]
+pub struct GuildConfig {
+  pub guild_id: u64,
+  pub channel_id: u64,
+  // 只播报这些比赛，留空表示全部
+  #[serde(default)]
+  pub matches: Vec<u32>,
+  #[serde(default)]
+  pub content_prefix: Option<String>,
+  #[serde(default)]
+  pub footer: Option<String>,
+  #[serde(default)]
+  pub format: MessageFormat,
+  #[serde(default)]
+  pub templates: Vec<NoticeTemplate>,
+}
+
+impl GuildConfig {
+  pub fn wants_match(&self, match_id: u32) -> bool {
+    self.matches.is_empty() || self.matches.contains(&match_id)
+  }
+
+  pub fn prefix_for(&self, notice_type: &NoticeType) -> Option<&str> {
+    resolve_template(&self.templates, notice_type, |t| &t.content)
+      .or(self.content_prefix.as_deref())
+  }
+
+  pub fn footer_for(&self, notice_type: &NoticeType) -> Option<&str> {
+    resolve_template(&self.templates, notice_type, |t| &t.footer).or(self.footer.as_deref())
+  }
+}
+
+// 主播报频道之外的额外转发目标
+#[derive(Debug, Deserialize, Clone)]
+pub struct DestinationConfig {
+  pub channel_id: u64,
+  #[serde(default)]
+  pub mode: DeliveryMode,
+  // digest 模式的汇总窗口（秒）
+  #[serde(default = "default_digest_interval")]
+  pub digest_interval: u64,
+  // 只转发这些比赛，留空表示全部
+  #[serde(default)]
+  pub matches: Vec<u32>,
+  #[serde(default)]
+  pub content_prefix: Option<String>,
+  #[serde(default)]
+  pub footer: Option<String>,
+  #[serde(default)]
+  pub format: MessageFormat,
+  // 提示公告附上提示原文，适合只有出题人能看到的频道；需要 gzctf.session_cookie
+  #[serde(default)]
+  pub hint_content: bool,
+  // 这些字段用 ||...|| 包裹，点击后才显示
+  #[serde(default)]
+  pub spoilers: Vec<SpoilerField>,
+  #[serde(default)]
+  pub templates: Vec<NoticeTemplate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpoilerField {
+  Team,
+  Challenge,
+  Hint,
+}
+
+impl DestinationConfig {
+  pub fn wants_match(&self, match_id: u32) -> bool {
+    self.matches.is_empty() || self.matches.contains(&match_id)
+  }
+
+  pub fn prefix_for(&self, notice_type: &NoticeType) -> Option<&str> {
+    resolve_template(&self.templates, notice_type, |t| &t.content)
+      .or(self.content_prefix.as_deref())
+  }
+
+  pub fn footer_for(&self, notice_type: &NoticeType) -> Option<&str> {
+    resolve_template(&self.templates, notice_type, |t| &t.footer).or(self.footer.as_deref())
+  }
+}
+
+fn default_digest_interval() -> u64 {
+  3600
+}
+
+// 静默时段内非关键公告被暂存，结束后以汇总形式发送
+#[derive(Debug, Deserialize, Clone)]
+pub struct QuietHoursConfig {
+  // "HH:MM"，按 utc_offset 指定的赛事时区解释，允许跨越午夜
+  pub start: String,
+  pub end: String,
+  // 相对 UTC 的小时数，范围 -12 到 14，加载时校验
+  #[serde(default = "default_utc_offset")]
+  pub utc_offset: i32,
+}
+
+impl QuietHoursConfig {
+  pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+    let parse = |s: &str| NaiveTime::parse_from_str(s, "%H:%M");
+    let (Ok(start), Ok(end), Some(offset)) = (
+      parse(&self.start),
+      parse(&self.end),
+      FixedOffset::east_opt(self.utc_offset * 3600),
+    ) else {
+      return false;
+    };
+
+    let local = now.with_timezone(&offset).time();
+    if start <= end {
+      local >= start && local < end
+    } else {
+      local >= start || local < end
+    }
+  }
+}
+
+fn default_utc_offset() -> i32 {
+  8
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LogConfig {
+  // 日志文件路径，未设置时只输出到控制台
+  #[serde(default)]
+  pub file: Option<String>,
+  // 单个文件超过该大小（MB）时轮转，0 表示不按大小轮转
+  #[serde(default = "default_log_max_size_mb")]
+  pub max_size_mb: u64,
+  // 每天轮转一次
+  #[serde(default)]
+  pub daily: bool,
+  // 保留的历史文件数量
+  #[serde(default = "default_log_keep")]
+  pub keep: usize,
+  // 操作审计文件（JSON Lines），记录发出的消息、命令、配置重载和状态变更
+  #[serde(default)]
+  pub audit_file: Option<String>,
+}
+
+impl Default for LogConfig {
+  fn default() -> Self {
+    Self {
+      file: None,
+      max_size_mb: default_log_max_size_mb(),
+      daily: false,
+      keep: default_log_keep(),
+      audit_file: None,
+    }
+  }
+}
+
+fn default_log_max_size_mb() -> u64 {
+  10
+}
+
+fn default_log_keep() -> usize {
+  5
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct StorageConfig {
+  // 运行状态（tracker、重试队列、预写日志、订阅和队伍绑定）所在目录，不存在时自动创建
+  #[serde(default = "default_storage_dir")]
+  pub dir: String,
+}
+
+impl Default for StorageConfig {
+  fn default() -> Self {
+    Self {
+      dir: default_storage_dir(),
+    }
+  }
+}
+
+fn default_storage_dir() -> String {
+  ".".to_string()
+}
+
+impl StorageConfig {
+  pub fn path(&self, name: &str) -> String {
+    Path::new(&self.dir)
+      .join(name)
+      .to_string_lossy()
+      .into_owned()
+  }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CommandsConfig {
+  // 每场比赛在内存中保留的最近公告数量（/last）
+  #[serde(default = "default_recent_capacity")]
+  pub recent_capacity: usize,
+  // 同一用户两次使用同一命令的最短间隔（秒）
+  #[serde(default = "default_user_cooldown")]
+  pub user_cooldown: u64,
+  // 同一频道内两次使用同一命令的最短间隔（秒）
+  #[serde(default = "default_channel_cooldown")]
+  pub channel_cooldown: u64,
+  // 受冷却限制的命令，不带斜杠；!ping 与 /ping 共用 "ping"
+  #[serde(default = "default_cooldown_commands")]
+  pub cooldown_commands: Vec<String>,
+  // 回复所有人可见的斜杠命令，不带斜杠，子命令跟随顶层命令（"queue" 即 /queue status）；
+  // 其余命令的回复仅调用者可见，!ping 总是公开回复
+  #[serde(default)]
+  pub public_commands: Vec<String>,
+}
+
+impl Default for CommandsConfig {
+  fn default() -> Self {
+    Self {
+      recent_capacity: default_recent_capacity(),
+      user_cooldown: default_user_cooldown(),
+      channel_cooldown: default_channel_cooldown(),
+      cooldown_commands: default_cooldown_commands(),
+      public_commands: Vec::new(),
+    }
+  }
+}
+
+impl CommandsConfig {
+  pub fn is_public(&self, command: &str) -> bool {
+    self.public_commands.iter().any(|c| c == command)
+  }
+}
+
+fn default_recent_capacity() -> usize {
+  20
+}
+
+fn default_user_cooldown() -> u64 {
+  10
+}
+
+fn default_channel_cooldown() -> u64 {
+  3
+}
+
+fn default_cooldown_commands() -> Vec<String> {
+  ["scoreboard", "challenges", "last", "stats", "ping"]
+    .map(String::from)
+    .to_vec()
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct QueueConfig {
+  // 运行期间保存待重试队列快照的间隔（秒），0 表示禁用
+  #[serde(default = "default_snapshot_interval")]
+  pub snapshot_interval: u64,
+  // 设置后用该环境变量中的口令加密持久化文件和快照
+  #[serde(default)]
+  pub encryption_key_env: Option<String>,
+  // 退出时等待发送、重试循环和写盘的总时长上限（秒），应小于 docker stop / systemd 的等待时间
+  #[serde(default = "default_shutdown_timeout")]
+  pub shutdown_timeout: u64,
+}
+
+impl Default for QueueConfig {
+  fn default() -> Self {
+    Self {
+      snapshot_interval: default_snapshot_interval(),
+      encryption_key_env: None,
+      shutdown_timeout: default_shutdown_timeout(),
+    }
+  }
+}
+
+fn default_snapshot_interval() -> u64 {
+  30
+}
+
+fn default_shutdown_timeout() -> u64 {
+  8
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AlertsConfig {
+  // 未确认告警的重新提醒间隔（秒）
+  #[serde(default = "default_reping_interval")]
+  pub reping_interval: u64,
+  #[serde(default = "default_poll_failure_minutes")]
+  pub poll_failure_minutes: u64,
+  #[serde(default = "default_queue_threshold")]
+  pub queue_threshold: usize,
+  #[serde(default = "default_gateway_disconnect_minutes")]
+  pub gateway_disconnect_minutes: u64,
+  // 告警未解除超过该时间（分钟）后私信 escalate_user_ids
+  #[serde(default = "default_escalate_after_minutes")]
+  pub escalate_after_minutes: u64,
+  #[serde(default)]
+  pub escalate_user_ids: Vec<u64>,
+}
+
+impl Default for AlertsConfig {
+  fn default() -> Self {
+    Self {
+      reping_interval: default_reping_interval(),
+      poll_failure_minutes: default_poll_failure_minutes(),
+      queue_threshold: default_queue_threshold(),
+      gateway_disconnect_minutes: default_gateway_disconnect_minutes(),
+      escalate_after_minutes: default_escalate_after_minutes(),
+      escalate_user_ids: Vec::new(),
+    }
+  }
+}
+
+fn default_reping_interval() -> u64 {
+  600
+}
+
+fn default_poll_failure_minutes() -> u64 {
+  5
+}
+
+fn default_queue_threshold() -> usize {
+  50
+}
+
+fn default_gateway_disconnect_minutes() -> u64 {
+  5
+}
+
+fn default_escalate_after_minutes() -> u64 {
+  30
+}
+
+// 在这些频道转发比赛公告后自动创建讨论子区，方便工作人员协调
+#[derive(Debug, Deserialize, Clone)]
+pub struct DiscussionConfig {
+  pub channel_ids: Vec<u64>,
+  // 无消息后自动归档的时间（分钟），取整到 Discord 支持的 60/1440/4320/10080
+  #[serde(default = "default_auto_archive_minutes")]
+  pub auto_archive_minutes: u16,
+  // 为新题目开子区，之后该题的提示和血榜也发到子区中
+  #[serde(default)]
+  pub challenge_threads: bool,
+}
+
+fn default_auto_archive_minutes() -> u16 {
+  1440
+}
+
+// 内置 HTTP 服务，提供只读状态页，需要 Bearer token 访问
+#[derive(Debug, Deserialize, Clone)]
+pub struct HttpConfig {
+  #[serde(default = "default_http_bind")]
+  pub bind: String,
+  pub token: String,
+}
+
+fn default_http_bind() -> String {
+  "127.0.0.1:8080".to_string()
+}
+
+// 比赛开始前的倒计时播报，offsets 形如 "24h" "1h" "10m"，"start" 表示开始时刻
+#[derive(Debug, Deserialize, Clone)]
+pub struct CountdownConfig {
+  // 默认发送到 discord.channel_id
+  #[serde(default)]
+  pub channel_id: Option<u64>,
+  #[serde(default = "default_countdown_offsets")]
+  pub offsets: Vec<String>,
+  // 倒计时消息的编辑间隔（秒）
+  #[serde(default = "default_countdown_update_interval")]
+  pub update_interval: u64,
+}
+
+fn default_countdown_offsets() -> Vec<String> {
+  ["24h", "1h", "10m", "start"]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+fn default_countdown_update_interval() -> u64 {
+  60
+}
+
+// 把容器启动和销毁事件转发到支持频道，需要 gzctf.session_cookie 有 Monitor 权限
+#[derive(Debug, Deserialize, Clone)]
+pub struct ContainersConfig {
+  pub channel_id: u64,
+  // 检查间隔（秒），最小 5
+  #[serde(default = "default_containers_interval")]
+  pub interval: u64,
+}
+
+fn default_containers_interval() -> u64 {
+  15
+}
+
+// 报名截止前的提醒，offsets 格式同 countdown.offsets
+#[derive(Debug, Deserialize, Clone)]
+pub struct RegistrationConfig {
+  // 默认发送到各比赛的播报频道
+  #[serde(default)]
+  pub channel_id: Option<u64>,
+  #[serde(default = "default_registration_offsets")]
+  pub offsets: Vec<String>,
+}
+
+fn default_registration_offsets() -> Vec<String> {
+  ["24h", "1h"].iter().map(|s| s.to_string()).collect()
+}
+
+// 比赛开始一段时间后播报仍然零解的题目
+#[derive(Debug, Deserialize, Clone)]
+pub struct SpotlightConfig {
+  // 默认发送到各比赛的播报频道
+  #[serde(default)]
+  pub channel_id: Option<u64>,
+  // 开始后多久首次播报，格式同 countdown.offsets
+  #[serde(default = "default_spotlight_after")]
+  pub after: String,
+  // 之后的重复间隔，留空表示只播报一次
+  #[serde(default)]
+  pub repeat: Option<String>,
+}
+
+fn default_spotlight_after() -> String {
+  "2h".to_string()
+}
+
+// 定期检查比赛是否从隐藏变为公开，以及标题和海报的变化
+#[derive(Debug, Deserialize, Clone)]
+pub struct VisibilityConfig {
+  // 默认发送到各比赛的播报频道
+  #[serde(default)]
+  pub channel_id: Option<u64>,
+  // 检查间隔（秒），最小 30
+  #[serde(default = "default_visibility_interval")]
+  pub interval: u64,
+}
+
+fn default_visibility_interval() -> u64 {
+  300
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranslationProvider {
+  Deepl,
+  Libretranslate,
+}
+
+// 比赛公告的翻译后端，译文与原文一起显示在 embed 中
+#[derive(Debug, Deserialize, Clone)]
+pub struct TranslationConfig {
+  pub provider: TranslationProvider,
+  pub endpoint: String,
+  #[serde(default)]
+  pub api_key: Option<String>,
+  // 留空由后端自动识别
+  #[serde(default)]
+  pub source_lang: Option<String>,
+  #[serde(default = "default_target_lang")]
+  pub target_lang: String,
+}
+
+fn default_target_lang() -> String {
+  "EN".to_string()
+}
+
+// 把比赛公告以纯文本邮件抄送给组织者的邮件列表
+#[derive(Debug, Deserialize, Clone)]
+pub struct MailConfig {
+  pub host: String,
+  // 留空时按 security 取 465 / 587 / 25
+  #[serde(default)]
+  pub port: Option<u16>,
+  #[serde(default)]
+  pub security: MailSecurity,
+  #[serde(default)]
+  pub username: Option<String>,
+  #[serde(default)]
+  pub password: Option<String>,
+  pub from: String,
+  pub to: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailSecurity {
+  Tls,
+  #[default]
+  Starttls,
+  None,
+}
+
+// 通过 OneBot v11 协议（go-cqhttp 等）转发到 QQ 群
+#[derive(Debug, Deserialize, Clone)]
+pub struct OneBotConfig {
+  // http(s):// 使用 HTTP API，ws(s):// 使用正向 WebSocket
+  pub endpoint: String,
+  #[serde(default)]
+  pub access_token: Option<String>,
+  pub group_ids: Vec<u64>,
+  // 只转发这些类型，留空表示全部
+  #[serde(default)]
+  pub types: Vec<NoticeType>,
+}
+
+// 飞书 / Lark 或钉钉群机器人
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebhookConfig {
+  pub kind: WebhookKind,
+  pub url: String,
+  // 机器人开启了签名校验时填写
+  #[serde(default)]
+  pub secret: Option<String>,
+  // 只转发这些类型，留空表示全部
+  #[serde(default)]
+  pub types: Vec<NoticeType>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookKind {
+  #[serde(alias = "lark")]
+  Feishu,
+  Dingtalk,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RewardsConfig {
+  pub guild_id: u64,
+  // 一血队伍成员获得的身份组
+  pub first_blood_role_id: u64,
+  // 队伍名 -> Discord 用户 ID 列表
+  #[serde(default)]
+  pub teams: HashMap<String, Vec<u64>>,
+}
+
+// 已关联队伍拿到血时，在 embed 上方附带的祝贺文本模板
+// 可用变量: {mentions} {team} {challenge} {match}
+#[derive(Debug, Deserialize, Clone)]
+pub struct CongratsConfig {
+  #[serde(default = "default_congrats_first")]
+  pub first_blood: String,
+  #[serde(default = "default_congrats_second")]
+  pub second_blood: String,
+  #[serde(default = "default_congrats_third")]
+  pub third_blood: String,
+}
+
+impl CongratsConfig {
+  pub fn template_for(&self, notice_type: &NoticeType) -> Option<&str> {
+    match notice_type {
+      NoticeType::FirstBlood => Some(&self.first_blood),
+      NoticeType::SecondBlood => Some(&self.second_blood),
+      NoticeType::ThirdBlood => Some(&self.third_blood),
+      _ => None,
+    }
+  }
+}
+
+fn default_congrats_first() -> String {
+  "🎉 恭喜 {mentions}（{team}）拿下 **{challenge}** 一血！".to_string()
+}
+
+fn default_congrats_second() -> String {
+  "恭喜 {mentions}（{team}）拿下 **{challenge}** 二血！".to_string()
+}
+
+fn default_congrats_third() -> String {
+  "恭喜 {mentions}（{team}）拿下 **{challenge}** 三血！".to_string()
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
   pub discord: DiscordConfig,
   pub gzctf: GzctfConfig,
+  #[serde(default)]
+  pub queue: QueueConfig,
+  #[serde(default)]
+  pub alerts: AlertsConfig,
+  #[serde(default)]
+  pub rewards: Option<RewardsConfig>,
+  #[serde(default)]
+  pub congrats: Option<CongratsConfig>,
+  #[serde(default)]
+  pub guilds: Vec<GuildConfig>,
+  #[serde(default)]
+  pub destinations: Vec<DestinationConfig>,
+  #[serde(default)]
+  pub quiet_hours: Option<QuietHoursConfig>,
+  #[serde(default)]
+  pub commands: CommandsConfig,
+  #[serde(default)]
+  pub log: LogConfig,
+  #[serde(default)]
+  pub storage: StorageConfig,
+  #[serde(default)]
+  pub translation: Option<TranslationConfig>,
+  #[serde(default)]
+  pub countdown: Option<CountdownConfig>,
+  #[serde(default)]
+  pub registration: Option<RegistrationConfig>,
+  #[serde(default)]
+  pub spotlight: Option<SpotlightConfig>,
+  #[serde(default)]
+  pub visibility: Option<VisibilityConfig>,
+  #[serde(default)]
+  pub containers: Option<ContainersConfig>,
+  #[serde(default)]
+  pub http: Option<HttpConfig>,
+  #[serde(default)]
+  pub discussion: Option<DiscussionConfig>,
+  #[serde(default)]
+  pub mail: Option<MailConfig>,
+  #[serde(default)]
+  pub webhooks: Vec<WebhookConfig>,
+  #[serde(default)]
+  pub onebot: Option<OneBotConfig>,
 }
 
+// include 嵌套的最大深度，防止循环引用
+const MAX_INCLUDE_DEPTH: usize = 8;
+
 impl Config {
-  pub fn from_file(path: &str) -> anyhow::Result<Self> {
-    let config_str = std::fs::read_to_string(path)?;
-    let config: Config = toml::from_str(&config_str)?;
+  pub fn from_file(path: &str, profile: Option<&str>) -> anyhow::Result<Self> {
+    let mut value = load_with_includes(Path::new(path), 0)?;
+
+    let profiles = value.as_table_mut().and_then(|t| t.remove("profiles"));
+    if let Some(name) = profile {
+      let overlay = profiles
+        .as_ref()
+        .and_then(|p| p.get(name))
+        .cloned()
+        .ok_or_else(|| anyhow!("profile '{}' not found in [profiles]", name))?;
+      merge_values(&mut value, overlay);
+    }
+
+    let config: Config = value.try_into()?;
+    config.validate()?;
     Ok(config)
   }
 
+  // 类型无法表达的约束，加载（包括 SIGHUP 重新加载）时直接拒绝
+  fn validate(&self) -> anyhow::Result<()> {
+    if let Some(http) = &self.http
+      && http.token.trim().is_empty()
+    {
+      return Err(anyhow!(
+        "http.token must not be empty; the status page would accept any `Bearer ` header"
+      ));
+    }
+    if let Some(quiet) = &self.quiet_hours
+      && !(-12..=14).contains(&quiet.utc_offset)
+    {
+      return Err(anyhow!(
+        "quiet_hours.utc_offset must be between -12 and 14 hours, got {}",
+        quiet.utc_offset
+      ));
+    }
+    Ok(())
+  }
+
+  // 比赛公告的主播报频道，未配置 [[guilds]] 时只有 discord.channel_id
+  pub fn broadcast_channels(&self, match_id: u32) -> Vec<u64> {
+    if self.guilds.is_empty() {
+      return vec![self.discord.channel_id];
+    }
+    self
+      .guilds
+      .iter()
+      .filter(|g| g.wants_match(match_id))
+      .map(|g| g.channel_id)
+      .collect()
+  }
+
+  // 服务器频道优先使用自己的模板和前缀，discord.channel_id 使用 discord 下的配置
+  pub fn broadcast_prefix(&self, channel_id: u64, notice_type: &NoticeType) -> Option<&str> {
+    self
+      .guilds
+      .iter()
+      .find(|g| g.channel_id == channel_id)
+      .and_then(|g| g.prefix_for(notice_type))
+      .or_else(|| {
+        (channel_id == self.discord.channel_id)
+          .then(|| self.discord.prefix_for(notice_type))
+          .flatten()
+      })
+  }
+
+  // 与 broadcast_prefix 相同的查找顺序
+  pub fn broadcast_footer(&self, channel_id: u64, notice_type: &NoticeType) -> Option<&str> {
+    self
+      .guilds
+      .iter()
+      .find(|g| g.channel_id == channel_id)
+      .and_then(|g| g.footer_for(notice_type))
+      .or_else(|| {
+        (channel_id == self.discord.channel_id)
+          .then(|| self.discord.footer_for(notice_type))
+          .flatten()
+      })
+  }
+
+  // 配置为 format = "text" 的频道
+  pub fn text_channels(&self) -> Vec<u64> {
+    let main = (self.discord.format == MessageFormat::Text).then_some(self.discord.channel_id);
+    let guilds = self
+      .guilds
+      .iter()
+      .filter(|g| g.format == MessageFormat::Text)
+      .map(|g| g.channel_id);
+    let destinations = self
+      .destinations
+      .iter()
+      .filter(|d| d.format == MessageFormat::Text)
+      .map(|d| d.channel_id);
+    main.into_iter().chain(guilds).chain(destinations).collect()
+  }
+
+  // matches 非空时忽略 match_id；同一比赛 ID 出现多次时只保留第一项，避免重复轮询和重复播报
   pub fn get_matches(&self) -> Vec<MatchConfig> {
     if !self.gzctf.matches.is_empty() {
-      self.gzctf.matches.clone()
+      let mut seen = HashSet::new();
+      self
+        .gzctf
+        .matches
+        .iter()
+        .filter(|m| seen.insert(m.id))
+        .cloned()
+        .collect()
     } else if let Some(match_id) = self.gzctf.match_id {
       vec![MatchConfig {
         id: match_id,
         name: None,
+        bloods: BloodsMode::default(),
+        mask_challenges_below: None,
+        announce_categories: Vec::new(),
+        enrich_bloods: false,
+        group_bloods: false,
+        branding: Branding::default(),
+        score_milestones: Vec::new(),
+        announce_lead_change: false,
+        lead_change_cooldown: default_lead_change_cooldown(),
+        registration_deadline: None,
+        submission_burst: None,
+        burst_window: default_burst_window(),
+        on_start: OnStart::default(),
       }]
     } else {
       Vec::new()
     }
   }
+
+  // 启动时提示被去重或忽略的比赛配置
+  pub fn match_warnings(&self) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut counts: Vec<(u32, usize)> = Vec::new();
+    for m in &self.gzctf.matches {
+      match counts.iter_mut().find(|(id, _)| *id == m.id) {
+        Some((_, count)) => *count += 1,
+        None => counts.push((m.id, 1)),
+      }
+    }
+    for (id, count) in counts.into_iter().filter(|(_, count)| *count > 1) {
+      warnings.push(format!(
+        "Match {} appears {} times in gzctf.matches, only the first entry is used",
+        id, count
+      ));
+    }
+
+    if let Some(match_id) = self.gzctf.match_id
+      && !self.gzctf.matches.is_empty()
+    {
+      warnings.push(if self.gzctf.matches.iter().any(|m| m.id == match_id) {
+        format!(
+          "gzctf.match_id = {} duplicates an entry in gzctf.matches and is ignored",
+          match_id
+        )
+      } else {
+        format!(
+          "gzctf.match_id = {} is ignored because gzctf.matches is set; add it to gzctf.matches to monitor it",
+          match_id
+        )
+      });
+    }
+
+    for m in self.get_matches() {
+      if let Some(deadline) = &m.registration_deadline
+        && chrono::DateTime::parse_from_rfc3339(deadline).is_err()
+      {
+        warnings.push(format!(
+          "Match {} registration_deadline '{}' is not an RFC 3339 time, no reminders will be posted",
+          m.id, deadline
+        ));
+      }
+      for medal in m
+        .branding
+        .medals
+        .iter()
+        .filter(|medal| !emoji::is_valid(medal))
+      {
+        warnings.push(format!(
+          "Match {} medal '{}' is not an emoji or <:name:id>, the default medal is used instead",
+          m.id, medal
+        ));
+      }
+    }
+    warnings
+  }
+}
+
+// 先按顺序合并 include 的文件，再用当前文件覆盖
+fn load_with_includes(path: &Path, depth: usize) -> anyhow::Result<Value> {
+  if depth > MAX_INCLUDE_DEPTH {
+    return Err(anyhow!(
+      "config includes nested too deeply at {}",
+      path.display()
+    ));
+  }
+
+  let config_str =
+    std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+  let mut value: Value =
+    toml::from_str(&config_str).with_context(|| format!("failed to parse {}", path.display()))?;
+
+  let includes = value.as_table_mut().and_then(|t| t.remove("include"));
+  let Some(includes) = includes else {
+    return Ok(value);
+  };
+
+  let base_dir = path.parent().unwrap_or(Path::new("."));
+  let mut merged = Value::Table(Default::default());
+  for include in includes
+    .as_array()
+    .ok_or_else(|| anyhow!("'include' must be an array of paths"))?
+  {
+    let include = include
+      .as_str()
+      .ok_or_else(|| anyhow!("'include' entries must be strings"))?;
+    merge_values(
+      &mut merged,
+      load_with_includes(&base_dir.join(include), depth + 1)?,
+    );
+  }
+  merge_values(&mut merged, value);
+
+  Ok(merged)
+}
+
+// 表按键递归合并，其余类型（包括数组）整体覆盖
+fn merge_values(base: &mut Value, overlay: Value) {
+  match (base, overlay) {
+    (Value::Table(base), Value::Table(overlay)) => {
+      for (key, value) in overlay {
+        match base.get_mut(&key) {
+          Some(existing) => merge_values(existing, value),
+          None => {
+            base.insert(key, value);
+          }
+        }
+      }
+    }
+    (base, overlay) => *base = overlay,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(gzctf: &str) -> Config {
+    let text = format!(
+      "[discord]\ntoken = \"t\"\nchannel_id = 1\n\n[gzctf]\nurl = \"https://example.com\"\npoll_interval = 3\n{}",
+      gzctf
+    );
+    toml::from_str(&text).unwrap()
+  }
+
+  fn ids(config: &Config) -> Vec<u32> {
+    config.get_matches().iter().map(|m| m.id).collect()
+  }
+
+  #[test]
+  fn duplicate_matches_keep_first_entry() {
+    let config = parse(
+      r#"
+[[gzctf.matches]]
+id = 3
+name = "first"
+
+[[gzctf.matches]]
+id = 1
+
+[[gzctf.matches]]
+id = 3
+name = "second"
+"#,
+    );
+
+    assert_eq!(ids(&config), vec![3, 1]);
+    assert_eq!(config.get_matches()[0].name.as_deref(), Some("first"));
+    assert_eq!(config.match_warnings().len(), 1);
+  }
+
+  #[test]
+  fn matches_take_precedence_over_match_id() {
+    let duplicate = parse("match_id = 1\n[[gzctf.matches]]\nid = 1\n");
+    assert_eq!(ids(&duplicate), vec![1]);
+    assert!(duplicate.match_warnings()[0].contains("duplicates"));
+
+    let ignored = parse("match_id = 2\n[[gzctf.matches]]\nid = 1\n");
+    assert_eq!(ids(&ignored), vec![1]);
+    assert!(ignored.match_warnings()[0].contains("ignored"));
+  }
+
+  #[test]
+  fn match_id_alone_is_used() {
+    let config = parse("match_id = 5\n");
+    assert_eq!(ids(&config), vec![5]);
+    assert!(config.match_warnings().is_empty());
+
+    assert!(parse("").get_matches().is_empty());
+  }
+
+  #[test]
+  fn invalid_medals_fall_back_to_defaults() {
+    let config = parse(
+      r#"
+[[gzctf.matches]]
+id = 1
+medals = ["<:gold:123>", ":second_place:"]
+"#,
+    );
+    let branding = &config.get_matches()[0].branding;
+
+    assert_eq!(branding.medal(0), Some("<:gold:123>"));
+    assert_eq!(branding.medal(1), Some("🥈"));
+    assert_eq!(branding.medal(2), Some("🥉"));
+    assert_eq!(config.match_warnings().len(), 1);
+    assert_eq!(Branding::default().medal(0), None);
+  }
+
+  #[test]
+  fn templates_override_destination_settings_per_type() {
+    let config = parse(
+      r#"
+[[destinations]]
+channel_id = 2
+content_prefix = "{match}"
+footer = "{time}"
+
+[[destinations.templates]]
+types = ["FirstBlood"]
+content = "{team} took {challenge}"
+
+[[destinations.templates]]
+footer = "terse"
+"#,
+    );
+    let destination = &config.destinations[0];
+
+    assert_eq!(
+      destination.prefix_for(&NoticeType::FirstBlood),
+      Some("{team} took {challenge}")
+    );
+    assert_eq!(destination.prefix_for(&NoticeType::Normal), Some("{match}"));
+    assert_eq!(
+      destination.footer_for(&NoticeType::FirstBlood),
+      Some("terse")
+    );
+  }
+
+  #[test]
+  fn on_start_parses_replay_modes() {
+    let parse_on_start = |value: &str| OnStart::try_from(value.to_string());
+    assert_eq!(parse_on_start("skip_existing"), Ok(OnStart::SkipExisting));
+    assert_eq!(parse_on_start("replay_last_5"), Ok(OnStart::ReplayLast(5)));
+    assert_eq!(
+      parse_on_start("replay_since 1970-01-01T00:00:01Z"),
+      Ok(OnStart::ReplaySince(1000))
+    );
+    assert!(parse_on_start("replay_last_x").is_err());
+    assert!(parse_on_start("replay_everything").is_err());
+
+    let notices: Vec<Notice> = (1..=4)
+      .map(|id| Notice {
+        id,
+        notice_type: "Normal".to_string(),
+        values: Vec::new(),
+        time: id * 1000,
+      })
+      .collect();
+    let refs: Vec<&Notice> = notices.iter().collect();
+    assert_eq!(
+      OnStart::ReplayLast(2).replayed(&refs),
+      HashSet::from([3, 4])
+    );
+    assert_eq!(
+      OnStart::ReplaySince(2000).replayed(&refs),
+      HashSet::from([2, 3, 4])
+    );
+    assert!(OnStart::SkipExisting.replayed(&refs).is_empty());
+  }
+
+  #[test]
+  fn empty_http_token_is_rejected() {
+    let http = |token: &str| parse(&format!("\n[http]\ntoken = \"{}\"\n", token));
+    assert!(http("").validate().is_err());
+    assert!(http("  ").validate().is_err());
+    assert!(http("secret").validate().is_ok());
+    assert!(parse("").validate().is_ok());
+  }
+
+  #[test]
+  fn cooldowns_cover_public_commands_by_default() {
+    let defaults = parse("").commands.cooldown_commands;
+    for command in ["scoreboard", "challenges", "last"] {
+      assert!(defaults.iter().any(|c| c == command), "{}", command);
+    }
+
+    let custom = parse("\n[commands]\ncooldown_commands = [\"scoreboard\"]\n");
+    assert_eq!(custom.commands.cooldown_commands, vec!["scoreboard"]);
+  }
+
+  #[test]
+  fn only_listed_commands_reply_publicly() {
+    let config = parse("\n[commands]\npublic_commands = [\"scoreboard\"]\n");
+    assert!(config.commands.is_public("scoreboard"));
+    assert!(!config.commands.is_public("queue"));
+    assert!(!parse("").commands.is_public("scoreboard"));
+  }
+
+  #[test]
+  fn out_of_range_utc_offset_is_rejected() {
+    let quiet = |offset: i32| {
+      parse(&format!(
+        "\n[quiet_hours]\nstart = \"01:00\"\nend = \"07:00\"\nutc_offset = {}\n",
+        offset
+      ))
+    };
+    assert!(quiet(-12).validate().is_ok());
+    assert!(quiet(14).validate().is_ok());
+    assert!(quiet(15).validate().is_err());
+    assert!(quiet(-13).validate().is_err());
+    assert!(quiet(i32::MAX).validate().is_err());
+  }
 }
@@ -0,0 +1,167 @@
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+use serenity::model::colour::Colour;
+use serenity::prelude::Context;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::time::{Duration, sleep};
+
+use crate::config::{Config, MatchConfig, PlatformKind};
+use crate::discord::{DeliveryState, DiscordMessenger};
+use crate::gzctf::{GzctfClient, format_time, trunc_text, trunc_width};
+use crate::limiter::RequestLimiter;
+use crate::log;
+use crate::models::GameEvent;
+use crate::scheduler::Scheduler;
+use crate::tasks;
+
+// 每次拉取的事件数，两次检查之间的容器事件超过这个数时只转发最新的部分
+const EVENT_PAGE: u32 = 100;
+// 一条消息中最多列出的事件数
+const MAX_LINES: usize = 25;
+
+// 把各队伍的容器启动和销毁事件转发到支持频道；到期回收的容器同样以销毁事件出现。
+// 启动后第一次拉到的事件只作为起点，不转发
+pub fn start(
+  ctx: Arc<Context>,
+  config: Arc<Config>,
+  scheduler: Arc<Scheduler>,
+  delivery: Arc<DeliveryState>,
+  limiter: Arc<RequestLimiter>,
+) {
+  let Some(containers) = config.containers.clone() else {
+    return;
+  };
+  if config.gzctf.platform != PlatformKind::Gzctf {
+    log::warn("Container event relaying only supports GZCTF, skipping");
+    return;
+  }
+  if config.gzctf.session_cookie.is_none() {
+    log::warn("Container event relaying needs gzctf.session_cookie of a Monitor account");
+  }
+
+  let interval = Duration::from_secs(containers.interval.max(5));
+  let task = async move {
+    let client = match GzctfClient::new(&config.gzctf, limiter) {
+      Ok(client) => client,
+      Err(e) => {
+        log::error(format!(
+          "Failed to create client for container events: {}",
+          e
+        ));
+        return;
+      }
+    };
+    let messenger = DiscordMessenger::new(config.discord.channel_id, delivery);
+    let mut latest: HashMap<u32, u64> = HashMap::new();
+    // 同一比赛连续失败时只记录一次
+    let mut failing = HashSet::new();
+
+    loop {
+      for match_config in config.get_matches() {
+        let events = match client.fetch_events(match_config.id, EVENT_PAGE, true).await {
+          Ok(events) => {
+            failing.remove(&match_config.id);
+            events
+          }
+          Err(e) => {
+            if failing.insert(match_config.id) {
+              log::error(format!(
+                "Failed to fetch events for match {}: {}",
+                match_config.id, e
+              ));
+            }
+            continue;
+          }
+        };
+
+        let newest = events.iter().map(|e| e.time).max().unwrap_or(0);
+        let previous = latest.get(&match_config.id).copied();
+        latest.insert(match_config.id, newest.max(previous.unwrap_or(0)));
+        let Some(since) = previous else {
+          continue;
+        };
+        let mut fresh: Vec<&GameEvent> = events
+          .iter()
+          .filter(|e| e.time > since && describe(e).is_some())
+          .collect();
+        if fresh.is_empty() {
+          continue;
+        }
+        fresh.sort_by_key(|e| e.time);
+
+        let embed = events_embed(&match_config, &fresh);
+        if let Err(e) = messenger
+          .send_embed_to(&ctx, containers.channel_id, None, embed)
+          .await
+        {
+          log::error(format!(
+            "Failed to relay {} container event(s) for match {}: {}",
+            fresh.len(),
+            match_config.id,
+            e
+          ));
+        }
+      }
+      tasks::touch("scheduler:containers");
+      sleep(interval).await;
+    }
+  };
+  if scheduler.schedule_at("containers", scheduler.now(), task) {
+    log::info(format!(
+      "Relaying container events to channel {} every {}s",
+      containers.channel_id,
+      interval.as_secs()
+    ));
+  }
+}
+
+fn describe(event: &GameEvent) -> Option<&'static str> {
+  match event.event_type.as_str() {
+    "ContainerStart" => Some("🟢 启动"),
+    "ContainerDestroy" => Some("🔴 销毁"),
+    _ => None,
+  }
+}
+
+fn events_embed(match_config: &MatchConfig, events: &[&GameEvent]) -> CreateEmbed {
+  let mut lines: Vec<String> = events
+    .iter()
+    .rev()
+    .take(MAX_LINES)
+    .rev()
+    .map(|event| {
+      let who = match (&event.team, &event.user) {
+        (Some(team), Some(user)) => format!("{} ({})", team, user),
+        (Some(name), None) | (None, Some(name)) => name.clone(),
+        (None, None) => "未知队伍".to_string(),
+      };
+      format!(
+        "`{}` {} **{}** · {}",
+        format_time(event.time),
+        describe(event).unwrap_or_default(),
+        trunc_text(event.values.first().map(String::as_str).unwrap_or("?"), 40),
+        trunc_width(&who, 40)
+      )
+    })
+    .collect();
+  if events.len() > MAX_LINES {
+    lines.insert(
+      0,
+      format!("…另有 {} 条较早的事件", events.len() - MAX_LINES),
+    );
+  }
+
+  let match_name = match_config
+    .name
+    .clone()
+    .unwrap_or_else(|| format!("比赛 {}", match_config.id));
+  CreateEmbed::new()
+    .title("**容器事件**")
+    .color(Colour::from_rgb(100, 116, 139))
+    .description(trunc_text(&lines.join("\n"), 4000))
+    .footer(CreateEmbedFooter::new(format!(
+      "{} · {} 条事件",
+      match_name,
+      events.len()
+    )))
+}
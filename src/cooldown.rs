@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::CommandsConfig;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Scope {
+  User(u64),
+  Channel(u64),
+}
+
+// 公开命令的冷却：同一用户、同一频道在窗口内只能各触发一次
+pub struct Cooldowns {
+  user: Duration,
+  channel: Duration,
+  commands: Vec<String>,
+  last_used: Mutex<HashMap<(String, Scope), Instant>>,
+}
+
+impl Cooldowns {
+  pub fn new(config: &CommandsConfig) -> Self {
+    Self {
+      user: Duration::from_secs(config.user_cooldown),
+      channel: Duration::from_secs(config.channel_cooldown),
+      commands: config.cooldown_commands.clone(),
+      last_used: Mutex::new(HashMap::new()),
+    }
+  }
+
+  // 未在冷却中时记下本次使用；否则返回还需等待的时间，且不刷新冷却
+  pub fn check(&self, command: &str, user_id: u64, channel_id: u64) -> Result<(), Duration> {
+    if !self.commands.iter().any(|c| c == command) {
+      return Ok(());
+    }
+
+    let now = Instant::now();
+    let mut last_used = self.last_used.lock().unwrap();
+    let windows = [
+      (Scope::User(user_id), self.user),
+      (Scope::Channel(channel_id), self.channel),
+    ];
+    let wait = windows
+      .iter()
+      .filter_map(|(scope, window)| {
+        let used = last_used.get(&(command.to_string(), scope.clone()))?;
+        window.checked_sub(now.duration_since(*used))
+      })
+      .filter(|wait| !wait.is_zero())
+      .max();
+    if let Some(wait) = wait {
+      return Err(wait);
+    }
+
+    let longest = self.user.max(self.channel);
+    last_used.retain(|_, used| now.duration_since(*used) < longest);
+    for (scope, _) in windows {
+      last_used.insert((command.to_string(), scope), now);
+    }
+    Ok(())
+  }
+}
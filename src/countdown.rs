@@ -0,0 +1,215 @@
+use chrono::{DateTime, Utc};
+use serenity::builder::{CreateEmbed, CreateEmbedFooter, CreateMessage, EditMessage};
+use serenity::model::colour::Colour;
+use serenity::model::id::ChannelId;
+use serenity::prelude::Context;
+use std::sync::Arc;
+use tokio::time::{Duration, sleep};
+
+use crate::clock::Clock;
+use crate::config::Config;
+use crate::discord::DeliveryState;
+use crate::gzctf::{format_time, game_url};
+use crate::limiter::RequestLimiter;
+use crate::log;
+use crate::platform;
+use crate::scheduler::{self, Scheduler};
+
+struct Countdown {
+  channel: ChannelId,
+  match_name: String,
+  game_url: String,
+  start: DateTime<Utc>,
+  // 到这个时间点（下一条倒计时或开始时刻）为止持续编辑消息
+  until: DateTime<Utc>,
+  update_interval: Duration,
+  clock: Arc<dyn Clock>,
+  delivery: Arc<DeliveryState>,
+}
+
+// 拉取各比赛的开始时间，为每个尚未到达的偏移安排一次倒计时播报
+pub fn start(
+  ctx: Arc<Context>,
+  config: Arc<Config>,
+  scheduler: Arc<Scheduler>,
+  delivery: Arc<DeliveryState>,
+  limiter: Arc<RequestLimiter>,
+) {
+  let Some(countdown) = config.countdown.clone() else {
+    return;
+  };
+
+  let mut offsets: Vec<Duration> = countdown
+    .offsets
+    .iter()
+    .filter_map(|raw| {
+      let offset = scheduler::parse_offset(raw);
+      if offset.is_none() {
+        log::error(format!("Ignoring invalid countdown offset '{}'", raw));
+      }
+      offset
+    })
+    .collect();
+  offsets.sort_unstable_by(|a, b| b.cmp(a));
+  offsets.dedup();
+
+  tokio::spawn(async move {
+    let client = match platform::connect(&config.gzctf, &limiter) {
+      Ok(client) => client,
+      Err(e) => {
+        log::error(format!("Failed to create client for countdowns: {}", e));
+        return;
+      }
+    };
+    for match_config in config.get_matches() {
+      let game = match client.fetch_game(match_config.id).await {
+        Ok(game) => game,
+        Err(e) => {
+          log::error(format!(
+            "Failed to fetch game info for match {}: {}",
+            match_config.id, e
+          ));
+          continue;
+        }
+      };
+      let Some(start) = DateTime::from_timestamp_millis(game.start as i64) else {
+        continue;
+      };
+
+      let channels = match countdown.channel_id {
+        Some(channel_id) => vec![channel_id],
+        None => config.broadcast_channels(match_config.id),
+      };
+
+      let mut scheduled = 0;
+      for (i, offset) in offsets.iter().enumerate() {
+        let at = start - *offset;
+        if at <= scheduler.now() {
+          continue;
+        }
+
+        let until = offsets.get(i + 1).map_or(start, |next| start - *next);
+        for channel_id in &channels {
+          let countdown = Countdown {
+            channel: ChannelId::new(*channel_id),
+            match_name: match_config.name.clone().unwrap_or(game.title.clone()),
+            game_url: game_url(&config.gzctf.url, match_config.id, config.gzctf.platform),
+            start,
+            until,
+            update_interval: Duration::from_secs(countdown.update_interval.max(1)),
+            clock: scheduler.clock(),
+            delivery: Arc::clone(&delivery),
+          };
+          let key = format!(
+            "countdown:{}:{}@{}",
+            match_config.id,
+            offset.as_secs(),
+            channel_id
+          );
+          if scheduler.schedule_at(key, at, run(Arc::clone(&ctx), countdown)) {
+            scheduled += 1;
+          }
+        }
+      }
+
+      if scheduled > 0 {
+        log::info(format!(
+          "Scheduled {} countdown post(s) for match {} starting at {}",
+          scheduled,
+          match_config.id,
+          format_time(game.start)
+        ));
+      }
+    }
+  });
+}
+
+async fn run(ctx: Arc<Context>, countdown: Countdown) {
+  let embed = countdown_embed(&countdown);
+  let message = match countdown
+    .channel
+    .send_message(&ctx.http, CreateMessage::new().embed(embed.clone()))
+    .await
+  {
+    Ok(message) => {
+      countdown
+        .delivery
+        .audit(&ctx, countdown.channel.get(), None, &embed);
+      message
+    }
+    Err(e) => {
+      log::error(format!("Failed to post countdown: {}", e));
+      return;
+    }
+  };
+  log::success(format!(
+    "Posted countdown for {} to channel {}",
+    countdown.match_name, countdown.channel
+  ));
+
+  while countdown.clock.utc() < countdown.until {
+    let remaining = (countdown.until - countdown.clock.utc())
+      .to_std()
+      .unwrap_or_default();
+    sleep(remaining.min(countdown.update_interval)).await;
+
+    let edit = EditMessage::new().embed(countdown_embed(&countdown));
+    if let Err(e) = countdown
+      .channel
+      .edit_message(&ctx.http, message.id, edit)
+      .await
+    {
+      log::error(format!("Failed to update countdown message: {}", e));
+    }
+  }
+}
+
+fn countdown_embed(countdown: &Countdown) -> CreateEmbed {
+  let remaining = countdown.start - countdown.clock.utc();
+  let (title, color, status) = if remaining <= chrono::Duration::zero() {
+    (
+      "**比赛开始**",
+      Colour::from_rgb(34, 197, 94),
+      "已开始".to_string(),
+    )
+  } else {
+    (
+      "**比赛倒计时**",
+      Colour::from_rgb(59, 130, 246),
+      format_remaining(remaining),
+    )
+  };
+
+  CreateEmbed::new()
+    .title(title)
+    .color(color)
+    .description(format!(
+      "**赛事:** [{}]({})",
+      countdown.match_name, countdown.game_url
+    ))
+    .field("距离开始", status, false)
+    .footer(CreateEmbedFooter::new(format!(
+      "开始时间 {}",
+      format_time(countdown.start.timestamp_millis() as u64)
+    )))
+}
+
+pub fn format_remaining(remaining: chrono::Duration) -> String {
+  let minutes = remaining.num_minutes();
+  if minutes < 1 {
+    return "不到 1 分钟".to_string();
+  }
+
+  let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+  let mut parts = Vec::new();
+  if days > 0 {
+    parts.push(format!("{} 天", days));
+  }
+  if hours > 0 {
+    parts.push(format!("{} 小时", hours));
+  }
+  if minutes > 0 {
+    parts.push(format!("{} 分钟", minutes));
+  }
+  parts.join(" ")
+}
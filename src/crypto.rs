@@ -0,0 +1,82 @@
+use anyhow::{Result, anyhow};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use sha2::{Digest, Sha256};
+
+// 加密文件格式: MAGIC || 24 字节 nonce || 密文
+const MAGIC: &[u8] = b"DCBOTENC1";
+const NONCE_LEN: usize = 24;
+
+// 持久化文件的对称加密，密钥由环境变量中的口令经 SHA-256 派生
+pub struct Cipher {
+  aead: XChaCha20Poly1305,
+}
+
+impl Cipher {
+  pub fn from_env(var: &str) -> Result<Self> {
+    let passphrase =
+      std::env::var(var).map_err(|_| anyhow!("environment variable {} is not set", var))?;
+    if passphrase.is_empty() {
+      return Err(anyhow!("environment variable {} is empty", var));
+    }
+    Ok(Self::from_passphrase(&passphrase))
+  }
+
+  pub fn from_passphrase(passphrase: &str) -> Self {
+    let key = Sha256::digest(passphrase.as_bytes());
+    Self {
+      aead: XChaCha20Poly1305::new(&key),
+    }
+  }
+
+  pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = self
+      .aead
+      .encrypt(&nonce, plaintext)
+      .map_err(|_| anyhow!("failed to encrypt data"))?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+  }
+
+  pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+    let body = data
+      .strip_prefix(MAGIC)
+      .ok_or_else(|| anyhow!("data is not encrypted"))?;
+    if body.len() < NONCE_LEN {
+      return Err(anyhow!("encrypted data is truncated"));
+    }
+
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into()?;
+    self
+      .aead
+      .decrypt(&XNonce::from(nonce), ciphertext)
+      .map_err(|_| anyhow!("failed to decrypt data, wrong key?"))
+  }
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+  data.starts_with(MAGIC)
+}
+
+// 配置了密钥时加密，否则原样返回
+pub fn seal(cipher: Option<&Cipher>, plaintext: Vec<u8>) -> Result<Vec<u8>> {
+  match cipher {
+    Some(cipher) => cipher.encrypt(&plaintext),
+    None => Ok(plaintext),
+  }
+}
+
+// 兼容未加密的旧文件；遇到加密文件但没有密钥时报错而不是当作空文件
+pub fn open(cipher: Option<&Cipher>, data: Vec<u8>) -> Result<Vec<u8>> {
+  match (is_encrypted(&data), cipher) {
+    (false, _) => Ok(data),
+    (true, Some(cipher)) => cipher.decrypt(&data),
+    (true, None) => Err(anyhow!("file is encrypted but no key is configured")),
+  }
+}
@@ -0,0 +1,173 @@
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serenity::async_trait;
+use std::collections::HashMap;
+
+use crate::compat;
+use crate::config::GzctfConfig;
+use crate::gzctf::{build_http_client, join_url};
+use crate::models::{ChallengeInfo, GameInfo, Notice, NoticeType, Scoreboard, ScoreboardItem};
+use crate::platform::CtfPlatform;
+
+// CTFd 的 API 响应都包在 { success, data } 里
+#[derive(Deserialize)]
+struct Envelope<T> {
+  #[serde(default)]
+  success: bool,
+  data: Option<T>,
+  #[serde(default)]
+  errors: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct Notification {
+  #[serde(deserialize_with = "compat::id")]
+  id: u64,
+  #[serde(default)]
+  title: String,
+  #[serde(default)]
+  content: String,
+  #[serde(default, deserialize_with = "compat::timestamp")]
+  date: u64,
+}
+
+#[derive(Deserialize)]
+struct Standing {
+  #[serde(default)]
+  pos: u32,
+  #[serde(default)]
+  name: String,
+  #[serde(default)]
+  score: u64,
+}
+
+#[derive(Deserialize)]
+struct Challenge {
+  #[serde(default)]
+  id: u32,
+  name: String,
+  #[serde(default)]
+  category: String,
+  #[serde(default)]
+  value: u32,
+  // 隐藏解题数时为 null
+  #[serde(default)]
+  solves: Option<u32>,
+}
+
+// CTFd 一个实例就是一场比赛，配置中的比赛 ID 只用来区分进度，不参与请求
+pub struct CtfdClient {
+  api_base: String,
+  client: reqwest::Client,
+  token: Option<String>,
+}
+
+impl CtfdClient {
+  pub fn new(config: &GzctfConfig) -> Result<Self> {
+    Ok(Self {
+      api_base: join_url(&config.url, &[&config.api_prefix, "v1"]),
+      client: build_http_client(&config.http)?,
+      token: config.api_token.clone(),
+    })
+  }
+
+  async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+    let mut request = self
+      .client
+      .get(join_url(&self.api_base, &[path]))
+      .header(reqwest::header::CONTENT_TYPE, "application/json");
+    // 访问令牌需要配合 JSON Content-Type 才会被 CTFd 接受
+    if let Some(token) = &self.token {
+      request = request.header(reqwest::header::AUTHORIZATION, format!("Token {}", token));
+    }
+
+    let envelope: Envelope<T> = request.send().await?.error_for_status()?.json().await?;
+    match envelope.data {
+      Some(data) if envelope.success => Ok(data),
+      _ => Err(anyhow!(
+        "CTFd returned an error for {}: {}",
+        path,
+        envelope.errors.unwrap_or_default()
+      )),
+    }
+  }
+
+  async fn notifications(&self, path: &str) -> Result<Vec<Notice>> {
+    let notifications: Vec<Notification> = self.get(path).await?;
+    Ok(
+      notifications
+        .into_iter()
+        .map(|n| {
+          let text = match n.title.trim() {
+            "" => n.content,
+            title => format!("**{}**\n{}", title, n.content),
+          };
+          Notice {
+            id: n.id,
+            notice_type: format!("{:?}", NoticeType::Normal),
+            values: vec![text],
+            time: n.date,
+          }
+        })
+        .collect(),
+    )
+  }
+}
+
+#[async_trait]
+impl CtfPlatform for CtfdClient {
+  async fn fetch_notices(&self, _match_id: u32) -> Result<Vec<Notice>> {
+    self.notifications("notifications").await
+  }
+
+  async fn fetch_notices_since(
+    &self,
+    _match_id: u32,
+    last_id: u64,
+    _page_size: u32,
+  ) -> Result<Vec<Notice>> {
+    self
+      .notifications(&format!("notifications?since_id={}", last_id))
+      .await
+  }
+
+  async fn fetch_scoreboard(&self, _match_id: u32) -> Result<Scoreboard> {
+    let standings: Vec<Standing> = self.get("scoreboard").await?;
+    let listed: Vec<Challenge> = self.get("challenges").await?;
+
+    let mut challenges: HashMap<String, Vec<ChallengeInfo>> = HashMap::new();
+    for c in listed {
+      challenges
+        .entry(c.category.clone())
+        .or_default()
+        .push(ChallengeInfo {
+          id: c.id,
+          title: c.name,
+          category: c.category,
+          score: c.value,
+          solved: c.solves.unwrap_or(0),
+          challenge_type: None,
+        });
+    }
+
+    Ok(Scoreboard {
+      items: standings
+        .into_iter()
+        .map(|s| ScoreboardItem {
+          name: s.name,
+          score: s.score,
+          rank: s.pos,
+        })
+        .collect(),
+      challenges,
+    })
+  }
+
+  async fn fetch_game(&self, _match_id: u32) -> Result<GameInfo> {
+    // 开始和结束时间只有管理员接口能读到，倒计时和零解播报不支持 CTFd
+    Err(anyhow!(
+      "CTFd does not expose the event start and end times"
+    ))
+  }
+}
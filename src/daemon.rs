@@ -0,0 +1,85 @@
+use anyhow::Result;
+use std::fs::OpenOptions;
+
+use crate::log;
+
+pub struct DaemonOptions<'a> {
+  pub pid_file: &'a str,
+  pub log_file: &'a str,
+}
+
+// 必须在创建 tokio 运行时之前调用
+#[cfg(unix)]
+pub fn daemonize(options: &DaemonOptions) -> Result<()> {
+  let stdout = OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(options.log_file)?;
+  let stderr = stdout.try_clone()?;
+
+  log::info(format!(
+    "Detaching into background, logging to {} (pid file: {})",
+    options.log_file, options.pid_file
+  ));
+
+  daemonize::Daemonize::new()
+    .pid_file(options.pid_file)
+    .working_directory(std::env::current_dir()?)
+    .stdout(stdout)
+    .stderr(stderr)
+    .start()?;
+
+  colored::control::set_override(false);
+  Ok(())
+}
+
+// Windows 没有 fork：以分离模式重新启动自身，由子进程继续运行
+#[cfg(windows)]
+pub fn daemonize(options: &DaemonOptions) -> Result<()> {
+  use std::os::windows::process::CommandExt;
+  use std::process::{Command, Stdio};
+
+  const DETACHED_PROCESS: u32 = 0x0000_0008;
+  const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+
+  let stdout = OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(options.log_file)?;
+  let stderr = stdout.try_clone()?;
+
+  let mut args: Vec<String> = std::env::args()
+    .skip(1)
+    .filter(|arg| arg != "--daemon")
+    .collect();
+  if !args.iter().any(|arg| arg == "--pid-file") {
+    args.extend(["--pid-file".to_string(), options.pid_file.to_string()]);
+  }
+
+  let child = Command::new(std::env::current_exe()?)
+    .args(args)
+    .stdin(Stdio::null())
+    .stdout(stdout)
+    .stderr(stderr)
+    .creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP)
+    .spawn()?;
+  std::fs::write(options.pid_file, child.id().to_string())?;
+
+  log::info(format!(
+    "Started background process {}, logging to {}",
+    child.id(),
+    options.log_file
+  ));
+  std::process::exit(0);
+}
+
+// 仅当 PID 文件属于当前进程时才删除，避免误删其他实例的文件
+pub fn remove_pid_file(pid_file: &str) {
+  let is_ours = std::fs::read_to_string(pid_file)
+    .map(|content| content.trim() == std::process::id().to_string())
+    .unwrap_or(false);
+
+  if is_ours && let Err(e) = std::fs::remove_file(pid_file) {
+    log::error(format!("Failed to remove pid file {}: {}", pid_file, e));
+  }
+}
@@ -0,0 +1,236 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+use serenity::model::colour::Colour;
+use serenity::prelude::Context;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::fs;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+use crate::crypto::{self, Cipher};
+use crate::discord::DiscordMessenger;
+use crate::gzctf::format_time;
+use crate::log;
+use crate::models::{Notice, NoticeType};
+
+// Discord embed description 上限为 4096 字符，留出余量
+const MAX_DESCRIPTION_LEN: usize = 3800;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestEntry {
+  pub match_name: String,
+  pub notice_type: NoticeType,
+  pub notice: Notice,
+}
+
+#[derive(Debug)]
+struct Pending {
+  entries: Vec<DigestEntry>,
+  window_start: Instant,
+}
+
+// 按目标频道缓存公告，窗口结束时合并为一条汇总 embed 发送
+pub struct DigestBuffer {
+  pending: Mutex<HashMap<u64, Pending>>,
+  // 每次变化后整体写盘，重启或重新加载配置后恢复；None 时只保存在内存中
+  persist_path: Option<String>,
+  cipher: Option<Arc<Cipher>>,
+}
+
+impl DigestBuffer {
+  // 恢复上次运行留下的条目，窗口从现在重新计时。文件无法读取时改名保留，从空缓冲开始
+  pub fn load(persist_path: String, cipher: Option<Arc<Cipher>>) -> Self {
+    let pending = match Self::read(&persist_path, cipher.as_deref()) {
+      Ok(saved) => {
+        let count: usize = saved.values().map(Vec::len).sum();
+        if count > 0 {
+          log::success(format!(
+            "Loaded {} buffered notice(s) from {}.",
+            count, persist_path
+          ));
+        }
+        saved
+          .into_iter()
+          .map(|(channel_id, entries)| {
+            (
+              channel_id,
+              Pending {
+                entries,
+                window_start: Instant::now(),
+              },
+            )
+          })
+          .collect()
+      }
+      Err(e) => {
+        let backup = format!("{}.bak", persist_path);
+        log::error(format!(
+          "Failed to load buffered notices from {}: {}. Moved it to {}.",
+          persist_path, e, backup
+        ));
+        let _ = std::fs::rename(&persist_path, backup);
+        HashMap::new()
+      }
+    };
+
+    Self {
+      pending: Mutex::new(pending),
+      persist_path: Some(persist_path),
+      cipher,
+    }
+  }
+
+  fn read(path: &str, cipher: Option<&Cipher>) -> Result<HashMap<u64, Vec<DigestEntry>>> {
+    if !Path::new(path).exists() {
+      return Ok(HashMap::new());
+    }
+    let content = crypto::open(cipher, std::fs::read(path)?)?;
+    Ok(serde_json::from_slice(&content)?)
+  }
+
+  async fn save(&self, pending: &HashMap<u64, Pending>) -> Result<()> {
+    let Some(persist_path) = &self.persist_path else {
+      return Ok(());
+    };
+    if pending.is_empty() {
+      if fs::try_exists(persist_path).await.unwrap_or(false) {
+        fs::remove_file(persist_path).await?;
+      }
+      return Ok(());
+    }
+
+    let saved: HashMap<u64, &Vec<DigestEntry>> = pending
+      .iter()
+      .map(|(channel_id, p)| (*channel_id, &p.entries))
+      .collect();
+    let data = crypto::seal(self.cipher.as_deref(), serde_json::to_vec(&saved)?)?;
+    let tmp_path = format!("{}.tmp", persist_path);
+    fs::write(&tmp_path, data).await?;
+    fs::rename(&tmp_path, persist_path).await?;
+    Ok(())
+  }
+
+  // 写盘后才算暂存成功；写盘失败时撤销这一条并返回错误，调用方据此不记录公告
+  pub async fn push(&self, channel_id: u64, entry: DigestEntry) -> Result<()> {
+    let mut pending = self.pending.lock().await;
+    pending
+      .entry(channel_id)
+      .or_insert_with(|| Pending {
+        entries: Vec::new(),
+        window_start: Instant::now(),
+      })
+      .entries
+      .push(entry);
+
+    if let Err(e) = self.save(&pending).await {
+      if let Some(slot) = pending.get_mut(&channel_id) {
+        slot.entries.pop();
+        if slot.entries.is_empty() {
+          pending.remove(&channel_id);
+        }
+      }
+      return Err(e);
+    }
+    Ok(())
+  }
+
+  // 发送 is_due(频道, 窗口已持续时间) 为真的频道汇总；发送失败的保留到下次
+  pub async fn flush_due(
+    &self,
+    ctx: &Context,
+    messenger: &DiscordMessenger,
+    is_due: impl Fn(u64, Duration) -> bool,
+  ) {
+    let due: Vec<(u64, Vec<DigestEntry>)> = {
+      let mut pending = self.pending.lock().await;
+      let channels: Vec<u64> = pending
+        .iter()
+        .filter(|(channel_id, p)| is_due(**channel_id, p.window_start.elapsed()))
+        .map(|(channel_id, _)| *channel_id)
+        .collect();
+      channels
+        .into_iter()
+        .filter_map(|channel_id| pending.remove(&channel_id).map(|p| (channel_id, p.entries)))
+        .collect()
+    };
+
+    if due.is_empty() {
+      return;
+    }
+    for (channel_id, entries) in due {
+      self.send(ctx, messenger, channel_id, entries).await;
+    }
+    // 发送完才写盘，发送途中退出时条目仍在文件里，下次启动重新发送
+    let pending = self.pending.lock().await;
+    if let Err(e) = self.save(&pending).await {
+      log::error(format!("Failed to save buffered notices: {}", e));
+    }
+  }
+
+  async fn send(
+    &self,
+    ctx: &Context,
+    messenger: &DiscordMessenger,
+    channel_id: u64,
+    entries: Vec<DigestEntry>,
+  ) {
+    if entries.is_empty() {
+      return;
+    }
+
+    let embed = build_digest_embed(&entries);
+    match messenger.send_embed_to(ctx, channel_id, None, embed).await {
+      Ok(_) => log::success(format!(
+        "Sent digest of {} notice(s) to channel {}",
+        entries.len(),
+        channel_id
+      )),
+      Err(e) => {
+        log::error(format!(
+          "Failed to send digest to channel {}: {}. Keeping entries for next window.",
+          channel_id, e
+        ));
+        let mut pending = self.pending.lock().await;
+        let slot = pending.entry(channel_id).or_insert_with(|| Pending {
+          entries: Vec::new(),
+          window_start: Instant::now(),
+        });
+        let newer = std::mem::replace(&mut slot.entries, entries);
+        slot.entries.extend(newer);
+      }
+    }
+  }
+}
+
+pub fn build_digest_embed(entries: &[DigestEntry]) -> CreateEmbed {
+  let mut description = String::new();
+  let mut omitted = 0;
+
+  for entry in entries {
+    let line = format!(
+      "`{}` [{}] {} {}\n",
+      format_time(entry.notice.time),
+      entry.match_name,
+      entry.notice_type.get_title(),
+      entry.notice.values.join(" / ")
+    );
+    if description.chars().count() + line.chars().count() > MAX_DESCRIPTION_LEN {
+      omitted += 1;
+    } else {
+      description.push_str(&line);
+    }
+  }
+
+  if omitted > 0 {
+    description.push_str(&format!("…以及另外 {} 条", omitted));
+  }
+
+  CreateEmbed::new()
+    .title("**公告汇总**")
+    .description(description)
+    .color(Colour::from_rgb(100, 116, 139))
+    .footer(CreateEmbedFooter::new(format!("共 {} 条", entries.len())))
+}
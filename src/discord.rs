@@ -1,43 +1,436 @@
 use anyhow::Result;
-use serenity::builder::{CreateEmbed, CreateMessage};
-use serenity::model::id::ChannelId;
+use serde_json::{Value, json};
+use serenity::async_trait;
+use serenity::builder::{
+  CreateAllowedMentions, CreateEmbed, CreateEmbedFooter, CreateMessage, EditThread,
+};
+use serenity::http::{HttpError, StatusCode};
+use serenity::model::channel::{AutoArchiveDuration, Message};
+use serenity::model::id::{ChannelId, UserId};
 use serenity::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
 use tokio::time::{Duration, timeout};
 
+use crate::audit;
+use crate::clock::{Clock, SystemClock};
+use crate::config::Config;
+use crate::gzctf::format_time;
 use crate::log;
+use crate::maintenance::Maintenance;
+use crate::text;
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(120);
+// Missing Access / Missing Permissions
+const PERMISSION_ERROR_CODES: [isize; 2] = [50001, 50013];
+
+struct Degraded {
+  since: Instant,
+  reason: String,
+  next_probe: Instant,
+}
+
+fn permission_error(error: &serenity::Error) -> Option<&str> {
+  match error {
+    serenity::Error::Http(HttpError::UnsuccessfulRequest(response))
+      if PERMISSION_ERROR_CODES.contains(&response.error.code) =>
+    {
+      Some(&response.error.message)
+    }
+    _ => None,
+  }
+}
+
+// 一次运行内所有发送共用的状态：由 run() 按配置创建，随 DiscordMessenger 传给各模块，
+// 重新加载配置时随 run() 一起重建
+#[derive(Default)]
+pub struct DeliveryState {
+  maintenance: Arc<Maintenance>,
+  // 所有发出的 embed 额外抄送到审计频道
+  audit_channel: Option<u64>,
+  // 以 Markdown 文本代替 embed 发送的频道
+  text_channels: HashSet<u64>,
+  // 缺少权限的频道不再逐条尝试发送，只在到期时用下一条消息重新探测
+  degraded: std::sync::Mutex<HashMap<u64, Degraded>>,
+}
+
+impl DeliveryState {
+  pub fn new(config: &Config, maintenance: Arc<Maintenance>) -> Self {
+    Self {
+      maintenance,
+      audit_channel: config.discord.audit_channel_id,
+      text_channels: config.text_channels().into_iter().collect(),
+      degraded: std::sync::Mutex::new(HashMap::new()),
+    }
+  }
+
+  pub fn maintenance(&self) -> &Maintenance {
+    &self.maintenance
+  }
+
+  pub fn is_text_channel(&self, channel_id: u64) -> bool {
+    self.text_channels.contains(&channel_id)
+  }
+
+  // 频道是否处于降级状态且还未到重新探测的时间
+  pub fn is_degraded(&self, channel_id: u64) -> bool {
+    self
+      .degraded
+      .lock()
+      .unwrap()
+      .get(&channel_id)
+      .is_some_and(|d| d.next_probe > Instant::now())
+  }
+
+  // 降级中的频道：(频道, 持续时间, 原因)，供告警监控使用
+  pub fn degraded_channels(&self) -> Vec<(u64, Duration, String)> {
+    self
+      .degraded
+      .lock()
+      .unwrap()
+      .iter()
+      .map(|(channel_id, d)| (*channel_id, d.since.elapsed(), d.reason.clone()))
+      .collect()
+  }
+
+  fn mark_degraded(&self, channel_id: u64, reason: &str) {
+    let next_probe = Instant::now() + PROBE_INTERVAL;
+    let mut degraded = self.degraded.lock().unwrap();
+    match degraded.get_mut(&channel_id) {
+      Some(d) => d.next_probe = next_probe,
+      None => {
+        log::warn(format!(
+          "Channel {} is missing permissions ({}), pausing sends and re-probing every {}s",
+          channel_id,
+          reason,
+          PROBE_INTERVAL.as_secs()
+        ));
+        degraded.insert(
+          channel_id,
+          Degraded {
+            since: Instant::now(),
+            reason: reason.to_string(),
+            next_probe,
+          },
+        );
+      }
+    }
+  }
+
+  fn mark_healthy(&self, channel_id: u64) {
+    if self.degraded.lock().unwrap().remove(&channel_id).is_some() {
+      log::success(format!(
+        "Channel {} is reachable again, resuming sends",
+        channel_id
+      ));
+    }
+  }
+
+  // 抄送在后台进行，失败只记录日志，不影响原消息的投递和重试
+  pub fn audit(&self, ctx: &Context, target: u64, content: Option<&str>, embed: &CreateEmbed) {
+    let Some(audit_channel) = self.audit_channel else {
+      return;
+    };
+    if audit_channel == target {
+      return;
+    }
+
+    let original_footer = serde_json::to_value(embed).ok().and_then(|v| {
+      v.pointer("/footer/text")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+    });
+    let mut metadata = format!(
+      "→ 频道 {} · 发送于 {}",
+      target,
+      format_time(SystemClock.now_millis())
+    );
+    if let Some(footer) = original_footer {
+      metadata = format!("{} · {}", metadata, footer);
+    }
+
+    let mut message = CreateMessage::new()
+      .embed(embed.clone().footer(CreateEmbedFooter::new(metadata)))
+      .allowed_mentions(CreateAllowedMentions::new());
+    if let Some(content) = content {
+      message = message.content(content);
+    }
+
+    let http = ctx.http.clone();
+    tokio::spawn(async move {
+      if let Err(e) = ChannelId::new(audit_channel)
+        .send_message(&http, message)
+        .await
+      {
+        log::error(format!("Failed to mirror message to audit channel: {}", e));
+      }
+    });
+  }
+
+  // 私信不抄送审计频道；对方关闭了服务器成员私信时返回错误
+  pub async fn send_dm(&self, ctx: &Context, user_id: u64, embed: CreateEmbed) -> Result<Message> {
+    if self.maintenance.is_active() {
+      return Err(anyhow::anyhow!("maintenance mode is on"));
+    }
+    let send_future =
+      UserId::new(user_id).direct_message(&ctx.http, CreateMessage::new().embed(embed));
+    match timeout(Duration::from_secs(10), send_future).await {
+      Ok(result) => {
+        let sent = result?;
+        audit::record("dm_sent", "bot", json!({ "user_id": user_id }));
+        Ok(sent)
+      }
+      Err(_) => Err(anyhow::anyhow!("DM send timeout after 10 seconds")),
+    }
+  }
+}
+
+// 重试队列使用的发送接口，测试中替换为可控制失败的实现
+#[async_trait]
+pub trait Messenger: Send + Sync {
+  // channel_id 为 None 时发到主播报频道；mentions 是正文中允许提及的用户
+  async fn send(
+    &self,
+    channel_id: Option<u64>,
+    content: Option<&str>,
+    mentions: &[u64],
+    embed: CreateEmbed,
+  ) -> Result<()>;
+
+  fn resolve_channel(&self, channel_id: Option<u64>) -> u64;
+}
+
+// 绑定了 Context 的 DiscordMessenger，在 ready 之后才能创建
+pub struct ContextMessenger {
+  ctx: Arc<Context>,
+  messenger: Arc<DiscordMessenger>,
+}
+
+impl ContextMessenger {
+  pub fn new(ctx: Arc<Context>, messenger: Arc<DiscordMessenger>) -> Self {
+    Self { ctx, messenger }
+  }
+}
+
+#[async_trait]
+impl Messenger for ContextMessenger {
+  async fn send(
+    &self,
+    channel_id: Option<u64>,
+    content: Option<&str>,
+    mentions: &[u64],
+    embed: CreateEmbed,
+  ) -> Result<()> {
+    let channel_id = self.messenger.resolve_channel(channel_id);
+    self
+      .messenger
+      .send_embed_mentioning(&self.ctx, channel_id, content, mentions, embed)
+      .await
+      .map(|_| ())
+  }
+
+  fn resolve_channel(&self, channel_id: Option<u64>) -> u64 {
+    self.messenger.resolve_channel(channel_id)
+  }
+}
 
 pub struct DiscordMessenger {
   channel_id: u64,
+  // 主题（例如某道题目）到子区的映射，后续相关消息发到同一子区
+  threads: Mutex<HashMap<String, ChannelId>>,
+  state: Arc<DeliveryState>,
 }
 
 impl DiscordMessenger {
-  pub fn new(channel_id: u64) -> Self {
-    Self { channel_id }
+  pub fn new(channel_id: u64, state: Arc<DeliveryState>) -> Self {
+    Self {
+      channel_id,
+      threads: Mutex::new(HashMap::new()),
+      state,
+    }
   }
 
-  pub async fn send_embed(&self, ctx: &Context, embed: CreateEmbed) -> Result<()> {
-    let send_future =
-      ChannelId::new(self.channel_id).send_message(&ctx.http, CreateMessage::new().embed(embed));
+  pub fn state(&self) -> &Arc<DeliveryState> {
+    &self.state
+  }
+
+  pub async fn remember_thread(&self, key: String, thread: ChannelId) {
+    self.threads.lock().await.insert(key, thread);
+  }
+
+  // 发到主题对应的子区；子区可能已自动归档，先取消归档并重置归档时间。
+  // 没有对应子区时返回 None
+  pub async fn send_to_thread(
+    &self,
+    ctx: &Context,
+    key: &str,
+    archive: AutoArchiveDuration,
+    embed: CreateEmbed,
+  ) -> Option<Result<Message>> {
+    let thread = self.threads.lock().await.get(key).copied()?;
+
+    let bump = EditThread::new()
+      .archived(false)
+      .auto_archive_duration(archive);
+    if let Err(e) = thread.edit_thread(&ctx.http, bump).await {
+      log::error(format!("Failed to unarchive thread {}: {}", thread, e));
+      // 子区已被删除时忘掉它，不再尝试
+      if let serenity::Error::Http(http) = &e
+        && http.status_code() == Some(StatusCode::NOT_FOUND)
+      {
+        self.threads.lock().await.remove(key);
+      }
+      return Some(Err(e.into()));
+    }
+
+    Some(self.send_embed_to(ctx, thread.get(), None, embed).await)
+  }
+
+  // None 表示主播报频道
+  pub fn resolve_channel(&self, channel_id: Option<u64>) -> u64 {
+    channel_id.unwrap_or(self.channel_id)
+  }
+
+  pub async fn send_embed_to(
+    &self,
+    ctx: &Context,
+    channel_id: u64,
+    content: Option<&str>,
+    embed: CreateEmbed,
+  ) -> Result<Message> {
+    self
+      .send_embed_mentioning(ctx, channel_id, content, &[], embed)
+      .await
+  }
+
+  // 正文里的队伍名、题目名来自参赛者，只允许提及 mentions 中的用户（关联的队员），
+  // 其余 @everyone、身份组和用户提及一律不生效
+  pub async fn send_embed_mentioning(
+    &self,
+    ctx: &Context,
+    channel_id: u64,
+    content: Option<&str>,
+    mentions: &[u64],
+    embed: CreateEmbed,
+  ) -> Result<Message> {
+    // 兜底：维护期间其他功能（倒计时等）的发送直接失败；
+    // 告警直接调用 ChannelId::send_message，有意绕过维护和降级检查，保证运维始终能收到
+    if self.state.maintenance.is_active() {
+      return Err(anyhow::anyhow!(
+        "maintenance mode is on, not sending to channel {}",
+        channel_id
+      ));
+    }
+    if self.state.is_degraded(channel_id) {
+      return Err(anyhow::anyhow!(
+        "channel {} is missing permissions, waiting for the next probe",
+        channel_id
+      ));
+    }
+    let message = build_message(
+      self.state.is_text_channel(channel_id),
+      content,
+      mentions,
+      &embed,
+    );
+    let send_future = ChannelId::new(channel_id).send_message(&ctx.http, message);
 
     match timeout(Duration::from_secs(10), send_future).await {
-      Ok(Ok(_)) => {
-        log::success(format!("Sent embed message to channel {}", self.channel_id));
-        Ok(())
+      Ok(Ok(sent)) => {
+        self.state.mark_healthy(channel_id);
+        log::success(format!("Sent embed message to channel {}", channel_id));
+        self.state.audit(ctx, channel_id, content, &embed);
+        audit::record(
+          "message_sent",
+          "bot",
+          json!({
+            "channel_id": channel_id,
+            "message_id": sent.id.get(),
+            "content": content,
+            "title": serde_json::to_value(&embed).ok().and_then(|v| v.get("title").cloned()),
+          }),
+        );
+        Ok(sent)
       }
       Ok(Err(e)) => {
         log::error(format!(
           "Failed to send message to channel {}: {}",
-          self.channel_id, e
+          channel_id, e
         ));
+        if let Some(reason) = permission_error(&e) {
+          self.state.mark_degraded(channel_id, reason);
+        }
         Err(e.into())
       }
       Err(_) => {
         log::error(format!(
           "Timeout (10s) while sending message to channel {}",
-          self.channel_id
+          channel_id
         ));
         Err(anyhow::anyhow!("Message send timeout after 10 seconds"))
       }
     }
   }
 }
+
+// 正文可能来自祝贺模板或频道前缀模板，其中的 {team} {challenge} 同样来自参赛者，提及范围在这里统一限制；
+// 文本格式的频道连公告内容和队伍名也在正文里，同样只放开 mentions
+pub fn build_message(
+  text_format: bool,
+  content: Option<&str>,
+  mentions: &[u64],
+  embed: &CreateEmbed,
+) -> CreateMessage {
+  let allowed = CreateAllowedMentions::new().users(mentions.iter().copied());
+  if text_format {
+    return CreateMessage::new()
+      .content(text::render(content, embed))
+      .allowed_mentions(allowed);
+  }
+  let message = CreateMessage::new()
+    .embed(embed.clone())
+    .allowed_mentions(allowed);
+  match content {
+    Some(content) => message.content(content),
+    None => message,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn allowed_mentions(message: &CreateMessage) -> Value {
+    serde_json::to_value(message).unwrap()["allowed_mentions"].clone()
+  }
+
+  #[test]
+  fn prefixed_content_only_mentions_listed_users() {
+    let embed = CreateEmbed::new().title("一血");
+    let prefix = "📢 @everyone <@&42> <@7> 拿下 pwn1";
+
+    let message = build_message(false, Some(prefix), &[], &embed);
+    assert_eq!(
+      allowed_mentions(&message),
+      json!({ "parse": [], "users": [], "roles": [] })
+    );
+
+    let message = build_message(false, Some(prefix), &[7], &embed);
+    assert_eq!(allowed_mentions(&message)["users"], json!(["7"]));
+    assert_eq!(allowed_mentions(&message)["parse"], json!([]));
+  }
+
+  #[test]
+  fn text_format_keeps_the_allow_list() {
+    let embed = CreateEmbed::new()
+      .title("一血")
+      .field("队伍", "@everyone", true);
+
+    let message = build_message(true, None, &[], &embed);
+    assert_eq!(
+      allowed_mentions(&message),
+      json!({ "parse": [], "users": [], "roles": [] })
+    );
+  }
+}
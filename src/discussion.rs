@@ -0,0 +1,106 @@
+use serenity::builder::CreateThread;
+use serenity::model::channel::{AutoArchiveDuration, Message};
+use serenity::model::id::ChannelId;
+use serenity::prelude::Context;
+
+use crate::config::DiscussionConfig;
+use crate::log;
+use crate::models::Notice;
+
+// Discord 子区名最长 100 字符
+const MAX_THREAD_NAME: usize = 100;
+
+// Discord 只接受这几档归档时间，其他值向上取到最近的一档
+pub fn archive_duration(minutes: u16) -> AutoArchiveDuration {
+  match minutes {
+    0..=60 => AutoArchiveDuration::OneHour,
+    61..=1440 => AutoArchiveDuration::OneDay,
+    1441..=4320 => AutoArchiveDuration::ThreeDays,
+    _ => AutoArchiveDuration::OneWeek,
+  }
+}
+
+// 在已发送的公告消息下创建讨论子区，失败不影响公告本身
+pub async fn open_thread(
+  ctx: &Context,
+  config: &DiscussionConfig,
+  message: &Message,
+  notice: &Notice,
+) -> Option<ChannelId> {
+  let summary = notice
+    .values
+    .first()
+    .map(String::as_str)
+    .unwrap_or_default();
+  create(
+    ctx,
+    config,
+    message,
+    format!("Discussion: {}", summary),
+    notice,
+  )
+  .await
+}
+
+// 新题目公告下的题目子区，之后同一题目的提示和血榜会发到这里
+pub async fn open_challenge_thread(
+  ctx: &Context,
+  config: &DiscussionConfig,
+  message: &Message,
+  notice: &Notice,
+) -> Option<ChannelId> {
+  let title = notice
+    .values
+    .first()
+    .map(String::as_str)
+    .unwrap_or_default();
+  create(
+    ctx,
+    config,
+    message,
+    format!("Challenge: {}", title),
+    notice,
+  )
+  .await
+}
+
+async fn create(
+  ctx: &Context,
+  config: &DiscussionConfig,
+  message: &Message,
+  name: String,
+  notice: &Notice,
+) -> Option<ChannelId> {
+  if !config.channel_ids.contains(&message.channel_id.get()) {
+    return None;
+  }
+
+  let name: String = name
+    .replace('\n', " ")
+    .chars()
+    .take(MAX_THREAD_NAME)
+    .collect();
+  let builder =
+    CreateThread::new(name).auto_archive_duration(archive_duration(config.auto_archive_minutes));
+
+  match message
+    .channel_id
+    .create_thread_from_message(&ctx.http, message.id, builder)
+    .await
+  {
+    Ok(thread) => {
+      log::info(format!(
+        "Opened discussion thread {} for notice {}",
+        thread.id, notice.id
+      ));
+      Some(thread.id)
+    }
+    Err(e) => {
+      log::error(format!(
+        "Failed to open discussion thread for notice {}: {}",
+        notice.id, e
+      ));
+      None
+    }
+  }
+}
@@ -0,0 +1,67 @@
+use serenity::model::guild::UnavailableGuild;
+use serenity::prelude::Context;
+use std::collections::HashSet;
+use std::sync::{LazyLock, RwLock};
+
+use crate::config::Config;
+use crate::log;
+
+// Bot 所在服务器里都找不到的自定义表情，发送时退回默认标记
+static UNAVAILABLE: LazyLock<RwLock<HashSet<u64>>> = LazyLock::new(Default::default);
+
+// 自定义表情 "<:name:id>" 或 "<a:name:id>" 的 ID
+fn custom_id(emoji: &str) -> Option<u64> {
+  let inner = emoji.strip_prefix('<')?.strip_suffix('>')?;
+  let mut parts = inner.split(':');
+  match (parts.next(), parts.next(), parts.next(), parts.next()) {
+    (Some("" | "a"), Some(name), Some(id), None) if !name.is_empty() => id.parse().ok(),
+    _ => None,
+  }
+}
+
+// Unicode 表情或格式正确的自定义表情；":medal:" 这类短代码在 embed 中不会渲染
+pub fn is_valid(emoji: &str) -> bool {
+  custom_id(emoji).is_some() || (!emoji.trim().is_empty() && !emoji.is_ascii())
+}
+
+pub fn is_usable(emoji: &str) -> bool {
+  is_valid(emoji) && custom_id(emoji).is_none_or(|id| !UNAVAILABLE.read().unwrap().contains(&id))
+}
+
+// 连接后检查配置的自定义表情是否在 Bot 所在的服务器中
+pub async fn check(ctx: &Context, config: &Config, guilds: &[UnavailableGuild]) {
+  let configured: Vec<(u64, String)> = config
+    .get_matches()
+    .into_iter()
+    .flat_map(|m| m.branding.medals)
+    .filter_map(|medal| custom_id(&medal).map(|id| (id, medal)))
+    .collect();
+  if configured.is_empty() {
+    return;
+  }
+
+  let mut available = HashSet::new();
+  for guild in guilds {
+    match guild.id.emojis(&ctx.http).await {
+      Ok(emojis) => available.extend(emojis.into_iter().map(|e| e.id.get())),
+      Err(e) => {
+        log::warn(format!(
+          "Failed to list emojis of guild {}, skipping the emoji check: {}",
+          guild.id, e
+        ));
+        return;
+      }
+    }
+  }
+
+  let mut unavailable = UNAVAILABLE.write().unwrap();
+  unavailable.clear();
+  for (id, medal) in configured {
+    if !available.contains(&id) && unavailable.insert(id) {
+      log::warn(format!(
+        "Emoji {} is not in any guild the bot has joined, using the default medal instead",
+        medal
+      ));
+    }
+  }
+}
@@ -0,0 +1,171 @@
+use anyhow::{Context as _, Result, anyhow};
+use serde_json::Value;
+use serenity::async_trait;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::compat;
+use crate::config::{FeedConfig, GzctfConfig};
+use crate::gzctf::build_http_client;
+use crate::log;
+use crate::models::{GameInfo, Notice, NoticeType, Scoreboard};
+use crate::platform::CtfPlatform;
+
+// 通用 JSON 公告源：按配置的路径从任意接口中取出公告，只支持比赛公告类功能
+pub struct FeedClient {
+  config: FeedConfig,
+  client: reqwest::Client,
+  interval: Duration,
+  last: Mutex<Option<(Instant, Vec<Notice>)>>,
+  // 同一条解析错误只提示一次
+  warned: std::sync::Mutex<HashSet<String>>,
+}
+
+impl FeedClient {
+  pub fn new(config: &GzctfConfig) -> Result<Self> {
+    let feed = config
+      .feed
+      .clone()
+      .ok_or_else(|| anyhow!("platform = \"feed\" requires a [gzctf.feed] section"))?;
+    Ok(Self {
+      interval: Duration::from_secs(feed.poll_interval),
+      config: feed,
+      client: build_http_client(&config.http)?,
+      last: Mutex::new(None),
+      warned: std::sync::Mutex::new(HashSet::new()),
+    })
+  }
+
+  fn parse(&self, body: &Value) -> Result<Vec<Notice>> {
+    let items = select(body, &self.config.items)
+      .and_then(Value::as_array)
+      .ok_or_else(|| anyhow!("feed path '{}' is not an array", self.config.items))?;
+
+    let mut notices = Vec::with_capacity(items.len());
+    for item in items {
+      match self.parse_item(item) {
+        Ok(notice) => notices.push(notice),
+        Err(e) => {
+          let e = format!("{:#}", e);
+          if self.warned.lock().unwrap().insert(e.clone()) {
+            log::warn(format!("Skipping unreadable feed item: {}", e));
+          }
+        }
+      }
+    }
+    Ok(notices)
+  }
+
+  fn parse_item(&self, item: &Value) -> Result<Notice> {
+    let field = |path: &Option<String>| path.as_deref().and_then(|p| select(item, p));
+
+    let content = select(item, &self.config.content)
+      .map(text)
+      .ok_or_else(|| anyhow!("missing content at '{}'", self.config.content))?;
+    let content = match field(&self.config.title).map(text) {
+      Some(title) if !title.trim().is_empty() => format!("**{}**\n{}", title.trim(), content),
+      _ => content,
+    };
+    let id = match field(&self.config.id) {
+      Some(id) => compat::id(id.clone()).context("invalid id")?,
+      None => content_id(&content),
+    };
+    let time = match field(&self.config.time) {
+      Some(time) => compat::timestamp(time.clone()).context("invalid time")?,
+      None => 0,
+    };
+    let notice_type = field(&self.config.notice_type)
+      .map(text)
+      .unwrap_or_else(|| format!("{:?}", NoticeType::Normal));
+
+    Ok(Notice {
+      id,
+      notice_type,
+      values: vec![content],
+      time,
+    })
+  }
+}
+
+#[async_trait]
+impl CtfPlatform for FeedClient {
+  // 同一接口被多场比赛共用时，在间隔内复用上次的结果
+  async fn fetch_notices(&self, _match_id: u32) -> Result<Vec<Notice>> {
+    let mut last = self.last.lock().await;
+    if let Some((fetched_at, notices)) = last.as_ref()
+      && fetched_at.elapsed() < self.interval
+    {
+      return Ok(notices.clone());
+    }
+
+    let body: Value = self
+      .client
+      .get(&self.config.url)
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await?;
+    let notices = self.parse(&body)?;
+    *last = Some((Instant::now(), notices.clone()));
+    Ok(notices)
+  }
+
+  async fn fetch_notices_since(
+    &self,
+    match_id: u32,
+    _last_id: u64,
+    _page_size: u32,
+  ) -> Result<Vec<Notice>> {
+    self.fetch_notices(match_id).await
+  }
+
+  async fn fetch_scoreboard(&self, _match_id: u32) -> Result<Scoreboard> {
+    Err(anyhow!("JSON feeds do not provide a scoreboard"))
+  }
+
+  async fn fetch_game(&self, _match_id: u32) -> Result<GameInfo> {
+    Err(anyhow!("JSON feeds do not provide event times"))
+  }
+
+  fn has_scoreboard(&self) -> bool {
+    false
+  }
+}
+
+// 支持 "$.a.b"、"a[0].b"、"$" 这类简单路径
+fn select<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+  let path = path.trim().trim_start_matches('$');
+  let mut current = value;
+  for segment in path.split('.').filter(|s| !s.is_empty()) {
+    let (key, indexes) = match segment.find('[') {
+      Some(at) => (&segment[..at], &segment[at..]),
+      None => (segment, ""),
+    };
+    if !key.is_empty() {
+      current = current.get(key)?;
+    }
+    for index in indexes.split(['[', ']']).filter(|s| !s.is_empty()) {
+      current = current.get(index.parse::<usize>().ok()?)?;
+    }
+  }
+  Some(current)
+}
+
+fn text(value: &Value) -> String {
+  match value {
+    Value::String(s) => s.clone(),
+    Value::Null => String::new(),
+    other => other.to_string(),
+  }
+}
+
+// 没有 ID 字段时用内容哈希代替，保持在 JavaScript 安全整数范围内
+fn content_id(content: &str) -> u64 {
+  let digest = Sha256::digest(content.as_bytes());
+  let mut bytes = [0u8; 8];
+  bytes.copy_from_slice(&digest[..8]);
+  u64::from_be_bytes(bytes) >> 11
+}
@@ -1,44 +1,312 @@
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 use chrono::DateTime;
+use serenity::async_trait;
 use serenity::builder::{CreateEmbed, CreateEmbedFooter};
 use serenity::model::colour::Colour;
 
-use crate::models::{Notice, NoticeType};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::time::{Duration, sleep};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::clock::{Clock, SystemClock};
+use crate::compat::{self, ApiVersion};
+use crate::config::{Branding, GzctfConfig, HttpOptions, PlatformKind, SpoilerField};
+use crate::limiter::RequestLimiter;
+use crate::log;
+use crate::models::{ChallengeDetail, GameEvent, GameInfo, Notice, NoticeType, Scoreboard};
+use crate::platform::CtfPlatform;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+// 小于该值的时间戳按秒解释（按毫秒解释会落在 1973 年之前）
+const SECONDS_THRESHOLD: u64 = 100_000_000_000;
+const MAX_CLOCK_SKEW_MS: u64 = 5 * 60 * 1000;
 
 pub struct GzctfClient {
-  base_url: String,
+  // 已拼接 api_prefix 的 API 根地址
+  api_base: String,
   client: reqwest::Client,
+  max_retries: u32,
+  api_version: ApiVersion,
+  // 同一问题只提示一次，恢复后重新计
+  warned_seconds: AtomicBool,
+  warned_skew: Mutex<HashSet<u32>>,
+  warned_items: Mutex<HashSet<String>>,
+  limiter: Arc<RequestLimiter>,
+  session_cookie: Option<String>,
 }
 
 impl GzctfClient {
-  pub fn new(base_url: String) -> Result<Self> {
-    let client = reqwest::Client::builder()
-      .danger_accept_invalid_certs(true)
-      .build()?;
+  // 轮询、题目同步和命令各自创建客户端，共用 run() 创建的同一个限流器才能真正限制总请求量
+  pub fn new(config: &GzctfConfig, limiter: Arc<RequestLimiter>) -> Result<Self> {
+    let client = build_http_client(&config.http)?;
 
-    Ok(Self { base_url, client })
+    Ok(Self {
+      api_base: join_url(&config.url, &[&config.api_prefix]),
+      client,
+      max_retries: config.max_retries,
+      api_version: config.api_version,
+      warned_seconds: AtomicBool::new(false),
+      warned_skew: Mutex::new(HashSet::new()),
+      warned_items: Mutex::new(HashSet::new()),
+      limiter,
+      session_cookie: config.session_cookie.clone(),
+    })
   }
 
-  pub async fn fetch_notices(&self, match_id: u32) -> Result<Vec<Notice>> {
-    let api_url = format!("{}/api/game/{}/notices", self.base_url, match_id);
-
-    self
-      .client
-      .get(&api_url)
-      .send()
-      .await?
-      .error_for_status()?
-      .json()
-      .await
-      .map_err(Into::into)
+  // 逐条解析，格式不认识的公告跳过并提示一次，不影响其他公告
+  async fn get_notices(&self, match_id: u32, url: &str) -> Result<Vec<Notice>> {
+    let value: Value = self.get_json(url).await?;
+    let (notices, errors) = compat::items(value);
+    let mut warned = self.warned_items.lock().unwrap();
+    for error in errors {
+      if warned.insert(format!("{}:{}", match_id, error)) {
+        log::warn(format!(
+          "Skipping unreadable notice from match {}: {}",
+          match_id, error
+        ));
+      }
+    }
+    Ok(notices)
   }
 
-  pub fn filter_by_type(notices: &[Notice], notice_type: NoticeType) -> Vec<Notice> {
-    notices
+  // 统一为毫秒时间戳，并在公告时间明显超前于本机时钟时提示
+  fn normalize_times(&self, match_id: u32, notices: &mut [Notice]) {
+    let mut converted = false;
+    for notice in notices.iter_mut() {
+      if notice.time > 0 && notice.time < SECONDS_THRESHOLD {
+        notice.time *= 1000;
+        converted = true;
+      }
+    }
+    if converted && !self.warned_seconds.swap(true, Ordering::Relaxed) {
+      log::warn(format!(
+        "Match {} returned notice times in seconds, converting to milliseconds",
+        match_id
+      ));
+    }
+
+    let now = SystemClock.now_millis();
+    let ahead = notices
       .iter()
-      .filter(|n| NoticeType::from_str(&n.notice_type) == Some(notice_type.clone()))
-      .cloned()
-      .collect()
+      .map(|n| n.time.saturating_sub(now))
+      .max()
+      .unwrap_or(0);
+    let mut warned_skew = self.warned_skew.lock().unwrap();
+    if ahead > MAX_CLOCK_SKEW_MS {
+      if warned_skew.insert(match_id) {
+        log::warn(format!(
+          "Match {} has notices {}s in the future; GZCTF and local clocks are skewed",
+          match_id,
+          ahead / 1000
+        ));
+      }
+    } else {
+      warned_skew.remove(&match_id);
+    }
+  }
+
+  // 最新的 count 条事件，containers 为 false 时不含容器事件；GZCTF 专有接口，不在 CtfPlatform 中
+  pub async fn fetch_events(
+    &self,
+    match_id: u32,
+    count: u32,
+    containers: bool,
+  ) -> Result<Vec<GameEvent>> {
+    let api_url = format!(
+      "{}?hideContainer={}&count={}&skip=0",
+      self.api_url(&["game", &match_id.to_string(), "events"]),
+      !containers,
+      count
+    );
+    let value: Value = self.get_json(&api_url).await?;
+    let (events, _) = compat::items(value);
+    Ok(events)
+  }
+
+  // 题目详情，包括提示原文；GZCTF 专有接口，不在 CtfPlatform 中
+  pub async fn fetch_challenge(&self, match_id: u32, challenge_id: u32) -> Result<ChallengeDetail> {
+    let api_url = self.api_url(&[
+      "game",
+      &match_id.to_string(),
+      "challenges",
+      &challenge_id.to_string(),
+    ]);
+    self.get_json(&api_url).await
+  }
+
+  fn api_url(&self, segments: &[&str]) -> String {
+    join_url(&self.api_base, segments)
+  }
+
+  // 对瞬时错误（5xx、连接失败、超时）做带上限的指数退避重试
+  async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+    let mut attempt = 0;
+
+    loop {
+      match self.try_get_json(url).await {
+        Ok(value) => return Ok(value),
+        Err(e) if attempt < self.max_retries && is_transient(&e) => {
+          let delay = backoff_delay(attempt);
+          attempt += 1;
+          log::info(format!(
+            "Transient error fetching {}: {}. Retrying in {}ms ({}/{})",
+            url,
+            e,
+            delay.as_millis(),
+            attempt,
+            self.max_retries
+          ));
+          sleep(delay).await;
+        }
+        Err(e) => return Err(e.into()),
+      }
+    }
+  }
+
+  async fn try_get_json<T: DeserializeOwned>(&self, url: &str) -> reqwest::Result<T> {
+    let _permit = self.limiter.acquire().await;
+    let mut request = self.client.get(url);
+    if let Some(cookie) = &self.session_cookie {
+      request = request.header(reqwest::header::COOKIE, cookie);
+    }
+    request.send().await?.error_for_status()?.json().await
+  }
+}
+
+#[async_trait]
+impl CtfPlatform for GzctfClient {
+  async fn fetch_notices(&self, match_id: u32) -> Result<Vec<Notice>> {
+    let api_url = self.api_url(&["game", &match_id.to_string(), "notices"]);
+    let mut notices = self.get_notices(match_id, &api_url).await?;
+    self.normalize_times(match_id, &mut notices);
+    Ok(notices)
+  }
+
+  // 按页向前翻，直到翻到不晚于 last_id 的公告或最后一页；
+  // 不支持分页参数的旧版本会返回完整列表，此时不再继续翻页
+  async fn fetch_notices_since(
+    &self,
+    match_id: u32,
+    last_id: u64,
+    page_size: u32,
+  ) -> Result<Vec<Notice>> {
+    if !self.api_version.supports_paging() {
+      return self.fetch_notices(match_id).await;
+    }
+    let base = self.api_url(&["game", &match_id.to_string(), "notices"]);
+    let mut notices: Vec<Notice> = Vec::new();
+    let mut ids = HashSet::new();
+    let mut skip = 0;
+
+    loop {
+      let api_url = format!("{}?count={}&skip={}", base, page_size, skip);
+      let page = self.get_notices(match_id, &api_url).await?;
+      let page_len = page.len();
+      let reached = page.last().is_none_or(|n| n.id <= last_id);
+      let before = notices.len();
+      notices.extend(page.into_iter().filter(|n| ids.insert(n.id)));
+
+      if reached || page_len != page_size as usize || notices.len() == before {
+        break;
+      }
+      skip += page_size;
+    }
+
+    self.normalize_times(match_id, &mut notices);
+    Ok(notices)
+  }
+
+  async fn fetch_scoreboard(&self, match_id: u32) -> Result<Scoreboard> {
+    let api_url = self.api_url(&["game", &match_id.to_string(), "scoreboard"]);
+    self.get_json(&api_url).await
+  }
+
+  async fn fetch_game(&self, match_id: u32) -> Result<GameInfo> {
+    let api_url = self.api_url(&["game", &match_id.to_string()]);
+    self.get_json(&api_url).await
+  }
+}
+
+pub fn build_http_client(options: &HttpOptions) -> Result<reqwest::Client> {
+  let mut builder = reqwest::Client::builder()
+    .danger_accept_invalid_certs(options.insecure)
+    .timeout(REQUEST_TIMEOUT);
+  if let Some(path) = &options.ca_path {
+    let pem = std::fs::read(path).with_context(|| format!("failed to read CA file {}", path))?;
+    builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+  }
+  if let Some(proxy) = &options.proxy {
+    builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+  }
+  // 端口以 URL 为准，这里的端口会被忽略
+  for (host, ip) in &options.resolve {
+    builder = builder.resolve(host, SocketAddr::new(*ip, 0));
+  }
+  // 绑定 IPv4 本地地址后只会尝试解析结果中的 IPv4 地址
+  if options.prefer_ipv4 {
+    builder = builder.local_address(IpAddr::from(Ipv4Addr::UNSPECIFIED));
+  }
+  Ok(builder.build()?)
+}
+
+fn is_transient(e: &reqwest::Error) -> bool {
+  e.is_timeout()
+    || e.is_connect()
+    || e.is_request()
+    || e.status().is_some_and(|s| s.is_server_error())
+}
+
+// delay: 500ms * 2**attempt, capped at MAX_BACKOFF
+fn backoff_delay(attempt: u32) -> Duration {
+  Duration::from_millis(500u64 << attempt.min(8)).min(MAX_BACKOFF)
+}
+
+// 拼接 URL 路径段，忽略多余的斜杠和空段，保留 base 中的子路径
+pub fn join_url(base: &str, segments: &[&str]) -> String {
+  let mut url = base.trim_end_matches('/').to_string();
+  for segment in segments {
+    let segment = segment.trim_matches('/');
+    if !segment.is_empty() {
+      url.push('/');
+      url.push_str(segment);
+    }
+  }
+  url
+}
+
+// 赛事链接的格式取决于平台
+pub fn game_url(base_url: &str, match_id: u32, platform: PlatformKind) -> String {
+  match platform {
+    PlatformKind::Ctfd => join_url(base_url, &["challenges"]),
+    PlatformKind::Feed => base_url.trim_end_matches('/').to_string(),
+    PlatformKind::Gzctf => join_url(base_url, &["games", &match_id.to_string()]),
+  }
+}
+
+// GZCTF 返回的海报是站内路径
+pub fn poster_url(base_url: &str, poster: &str) -> String {
+  if poster.starts_with("http://") || poster.starts_with("https://") {
+    poster.to_string()
+  } else {
+    join_url(base_url, &[poster])
+  }
+}
+
+// GZCTF 的题目类型，未知类型原样显示
+pub fn deploy_label(challenge_type: &str) -> &str {
+  match challenge_type {
+    "StaticAttachment" => "静态附件",
+    "StaticContainer" => "静态容器",
+    "DynamicAttachment" => "动态附件",
+    "DynamicContainer" => "动态容器",
+    other => other,
   }
 }
 
@@ -56,22 +324,94 @@ pub fn format_time(timestamp_ms: u64) -> String {
 }
 
 // 截断文本以避免队伍名过长影响观感
-fn trunc_text(text: &str, max_len: usize) -> String {
-  let char_count = text.chars().count();
+pub fn trunc_text(text: &str, max_len: usize) -> String {
+  if text.chars().count() <= max_len {
+    return text.to_string();
+  }
+  // 按字素簇截断，不拆开国旗、ZWJ 组合 emoji 和带附加符号的字符；长度仍按字符计，与 Discord 的限制一致
+  trunc_graphemes(text, max_len.saturating_sub(1), |g| g.chars().count())
+}
+
+// 按显示宽度截断，中日韩文字和 emoji 占两格；用于队伍名这类混排的短文本
+pub fn trunc_width(text: &str, max_width: usize) -> String {
+  if text.width() <= max_width {
+    return text.to_string();
+  }
+  trunc_graphemes(text, max_width.saturating_sub(1), |g| g.width())
+}
+
+fn trunc_graphemes(text: &str, limit: usize, size: impl Fn(&str) -> usize) -> String {
+  let mut used = 0;
+  let mut truncated = String::new();
+  for grapheme in text.graphemes(true) {
+    used += size(grapheme);
+    if used > limit {
+      break;
+    }
+    truncated.push_str(grapheme);
+  }
+  truncated.push('…');
+  truncated
+}
+
+pub fn spoiler(text: &str) -> String {
+  format!("||{}||", text)
+}
 
-  (char_count > max_len)
-    .then(|| format!("{}…", text.chars().take(max_len - 1).collect::<String>()))
-    .unwrap_or_else(|| text.to_string())
+// 按目标频道的配置把队伍名和题目名包成 spoiler；只改渲染用的副本，查题目信息仍用原始公告
+pub fn with_spoilers(notice: &Notice, notice_type: &NoticeType, fields: &[SpoilerField]) -> Notice {
+  let (team, challenge) = match notice_type {
+    NoticeType::Normal => (None, None),
+    NoticeType::NewChallenge | NoticeType::NewHint => (None, Some(0)),
+    NoticeType::FirstBlood | NoticeType::SecondBlood | NoticeType::ThirdBlood => (Some(0), Some(1)),
+  };
+  let mut notice = notice.clone();
+  for (field, index) in [
+    (SpoilerField::Team, team),
+    (SpoilerField::Challenge, challenge),
+  ] {
+    if fields.contains(&field)
+      && let Some(value) = index.and_then(|i| notice.values.get_mut(i))
+    {
+      *value = spoiler(value);
+    }
+  }
+  notice
 }
 
+// 按比赛外观渲染：标题前缀、颜色以及血播报的名次标记
 pub fn create_embed(
+  notice: &Notice,
+  notice_type: &NoticeType,
+  match_name: Option<&str>,
+  match_id: u32,
+  base_url: &str,
+  platform: PlatformKind,
+  branding: &Branding,
+) -> CreateEmbed {
+  let medal = blood_index(notice_type).and_then(|rank| branding.medal(rank));
+  let embed = build_embed(
+    notice,
+    notice_type.clone(),
+    match_name,
+    match_id,
+    base_url,
+    platform,
+    medal,
+  );
+  apply_branding(embed, notice_type, branding, medal)
+}
+
+fn build_embed(
   notice: &Notice,
   notice_type: NoticeType,
   match_name: Option<&str>,
   match_id: u32,
   base_url: &str,
+  platform: PlatformKind,
+  medal: Option<&str>,
 ) -> CreateEmbed {
-  let game_url = format!("{}/games/{}", base_url, match_id);
+  let game_url = game_url(base_url, match_id, platform);
 
   let mut embed = CreateEmbed::new()
     .title(notice_type.get_title())
@@ -82,11 +422,107 @@ pub fn create_embed(
     embed = embed.description(format!("**赛事:** [{}]({})", name, game_url));
   }
 
-  embed = add_notice_fields(embed, &notice_type, &notice.values);
+  embed = add_notice_fields(embed, &notice_type, &notice.values, medal);
 
   embed
 }
 
+// 同一道题在一轮内的多个血合并成一条血榜，按名次列出队伍；题目名取第一条（可能已隐藏）
+pub fn create_blood_board(
+  bloods: &[(NoticeType, Notice)],
+  match_name: Option<&str>,
+  match_id: u32,
+  base_url: &str,
+  platform: PlatformKind,
+  branding: &Branding,
+) -> CreateEmbed {
+  let challenge = bloods
+    .first()
+    .and_then(|(_, notice)| notice.values.get(1))
+    .cloned()
+    .unwrap_or_default();
+  let latest = bloods.iter().map(|(_, n)| n.time).max().unwrap_or(0);
+  let title = match &branding.prefix_emoji {
+    Some(emoji) => format!("{} **血榜播报**", emoji),
+    None => "**血榜播报**".to_string(),
+  };
+
+  let mut embed = CreateEmbed::new()
+    .title(title)
+    .color(
+      branding
+        .colour()
+        .unwrap_or(get_notice_color(&NoticeType::FirstBlood)),
+    )
+    .footer(CreateEmbedFooter::new(format_time(latest)));
+  if let Some(name) = match_name {
+    embed = embed.description(format!(
+      "**赛事:** [{}]({})",
+      name,
+      game_url(base_url, match_id, platform)
+    ));
+  }
+  embed = embed.field("题目", challenge, false);
+  for (notice_type, notice) in bloods {
+    let rank = blood_index(notice_type).unwrap_or(0);
+    let label = with_medal(branding.medal(rank), BLOOD_LABELS[rank]);
+    embed = embed.field(label, blood_team(&notice.values, None), false);
+  }
+  embed
+}
+
+const BLOOD_LABELS: [&str; 3] = ["一血", "二血", "三血"];
+
+fn blood_index(notice_type: &NoticeType) -> Option<usize> {
+  match notice_type {
+    NoticeType::FirstBlood => Some(0),
+    NoticeType::SecondBlood => Some(1),
+    NoticeType::ThirdBlood => Some(2),
+    _ => None,
+  }
+}
+
+fn with_medal(medal: Option<&str>, text: &str) -> String {
+  match medal {
+    Some(medal) => format!("{} {}", medal, text),
+    None => text.to_string(),
+  }
+}
+
+// 队伍名，启用 enrich_bloods 时附上得分和排名；截断时保留 spoiler 标记
+fn blood_team(values: &[String], medal: Option<&str>) -> String {
+  let team = values.first().map(String::as_str).unwrap_or_default();
+  let team = match team.strip_prefix("||").and_then(|t| t.strip_suffix("||")) {
+    Some(hidden) => spoiler(&trunc_width(hidden, 40)),
+    None => trunc_width(team, 40),
+  };
+  let team = with_medal(medal, &team);
+  match values.get(2) {
+    Some(gain) => format!("{} ({})", team, gain),
+    None => team,
+  }
+}
+
+// 按比赛配置覆盖标题前缀和颜色，未配置的部分保持默认
+fn apply_branding(
+  embed: CreateEmbed,
+  notice_type: &NoticeType,
+  branding: &Branding,
+  medal: Option<&str>,
+) -> CreateEmbed {
+  let mut embed = embed;
+  let title = with_medal(medal, notice_type.get_title());
+  if let Some(emoji) = &branding.prefix_emoji {
+    embed = embed.title(format!("{} {}", emoji, title));
+  } else if medal.is_some() {
+    embed = embed.title(title);
+  }
+  if let Some(colour) = branding.colour() {
+    embed = embed.color(colour);
+  }
+  embed
+}
+
 fn get_notice_color(notice_type: &NoticeType) -> Colour {
   match notice_type {
     NoticeType::Normal => Colour::from_rgb(59, 130, 246), // Blue
@@ -102,18 +538,241 @@ fn add_notice_fields(
   embed: CreateEmbed,
   notice_type: &NoticeType,
   values: &[String],
+  medal: Option<&str>,
 ) -> CreateEmbed {
   match notice_type {
-    NoticeType::Normal => embed.field(
-      "公告内容",
-      values.first().cloned().unwrap_or_default(),
-      false,
-    ),
+    NoticeType::Normal => {
+      let content = values.first().map(String::as_str).unwrap_or_default();
+      match values.get(1) {
+        Some(translation) => {
+          let embed = long_field(embed, "公告内容", content, BODY_BUDGET / 2);
+          long_field(embed, "译文 / Translation", translation, BODY_BUDGET / 2)
+        }
+        None => long_field(embed, "公告内容", content, BODY_BUDGET),
+      }
+    }
     NoticeType::NewChallenge | NoticeType::NewHint => {
       embed.field("题目", values.first().cloned().unwrap_or_default(), false)
     }
     NoticeType::FirstBlood | NoticeType::SecondBlood | NoticeType::ThirdBlood => embed
-      .field("队伍", trunc_text(&values[0], 30), false)
+      .field("队伍", blood_team(values, medal), false)
       .field("题目", &values[1], false),
   }
 }
+
+// Discord 单个字段值最多 1024 字符，整个 embed 最多 6000 字符；
+// 标题、赛事链接和页脚之外留给正文的长度
+const FIELD_LIMIT: usize = 1024;
+const BODY_BUDGET: usize = 5000;
+
+// 超长正文拆成多个字段，后续字段标注“续”；超出 budget 的部分截断并提示到比赛页面查看，
+// 避免发送失败后在重试队列里反复失败
+fn long_field(embed: CreateEmbed, name: &str, text: &str, budget: usize) -> CreateEmbed {
+  let text = if text.chars().count() > budget {
+    format!(
+      "{}\n*（内容过长已截断，完整内容请查看比赛页面）*",
+      trunc_text(text, budget - 30)
+    )
+  } else {
+    text.to_string()
+  };
+
+  split_field(&text, FIELD_LIMIT)
+    .into_iter()
+    .enumerate()
+    .fold(embed, |embed, (i, chunk)| {
+      let name = if i == 0 {
+        name.to_string()
+      } else {
+        format!("{}（续 {}）", name, i)
+      };
+      embed.field(name, chunk, false)
+    })
+}
+
+// 按字符数切分，尽量在后半段的换行处断开
+fn split_field(text: &str, limit: usize) -> Vec<String> {
+  let mut chunks = Vec::new();
+  let mut rest: Vec<char> = text.chars().collect();
+  while rest.len() > limit {
+    let cut = rest[limit / 2..limit]
+      .iter()
+      .rposition(|c| *c == '\n')
+      .map(|i| limit / 2 + i + 1)
+      .unwrap_or(limit);
+    let tail = rest.split_off(cut);
+    chunks.push(rest.into_iter().collect::<String>().trim_end().to_string());
+    rest = tail;
+  }
+  if chunks.is_empty() || !rest.is_empty() {
+    chunks.push(rest.into_iter().collect());
+  }
+  chunks
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod tests {
+  use super::*;
+  use serde_json::json;
+  use wiremock::matchers::{method, path, query_param};
+  use wiremock::{Mock, MockServer, ResponseTemplate};
+
+  const NOTICES: &str = "/api/game/1/notices";
+
+  fn client(server: &MockServer, max_retries: u32) -> GzctfClient {
+    let config: GzctfConfig = toml::from_str(&format!(
+      "url = \"{}\"\npoll_interval = 3\nmax_retries = {}",
+      server.uri(),
+      max_retries
+    ))
+    .unwrap();
+    GzctfClient::new(&config, Arc::new(RequestLimiter::from_config(&config))).unwrap()
+  }
+
+  fn notice(id: u64) -> Value {
+    json!({ "id": id, "type": "Normal", "values": [format!("notice {}", id)], "time": 1_700_000_000_000u64 })
+  }
+
+  fn ids(notices: &[Notice]) -> Vec<u64> {
+    notices.iter().map(|n| n.id).collect()
+  }
+
+  #[tokio::test]
+  async fn fetch_notices_accepts_every_known_format() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path(NOTICES))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        "data": [
+          { "Id": "1", "Type": "NewHint", "Values": ["web"], "Time": "2024-01-01T00:00:00Z" },
+          { "id": 2, "type": "Normal", "content": "旧版公告", "time": 1_700_000_000u64 },
+          { "type": "Normal" }
+        ]
+      })))
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    let notices = client(&server, 0).fetch_notices(1).await.unwrap();
+
+    assert_eq!(ids(&notices), vec![1, 2]);
+    assert_eq!(notices[0].time, 1_704_067_200_000);
+    assert_eq!(notices[1].values, vec!["旧版公告"]);
+    assert_eq!(notices[1].time, 1_700_000_000_000);
+  }
+
+  #[tokio::test]
+  async fn unauthorized_is_not_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path(NOTICES))
+      .respond_with(ResponseTemplate::new(401))
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    let error = client(&server, 3).fetch_notices(1).await.unwrap_err();
+
+    assert!(error.to_string().contains("401"));
+  }
+
+  #[tokio::test]
+  async fn server_errors_are_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path(NOTICES))
+      .respond_with(ResponseTemplate::new(500))
+      .up_to_n_times(1)
+      .expect(1)
+      .mount(&server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path(NOTICES))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!([notice(1)])))
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    let notices = client(&server, 1).fetch_notices(1).await.unwrap();
+
+    assert_eq!(ids(&notices), vec![1]);
+  }
+
+  #[tokio::test]
+  async fn malformed_json_is_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path(NOTICES))
+      .respond_with(ResponseTemplate::new(200).set_body_string("<html>502 Bad Gateway</html>"))
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    assert!(client(&server, 3).fetch_notices(1).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn fetch_notices_since_pages_until_last_seen() {
+    let server = MockServer::start().await;
+    for (skip, page) in [("0", vec![6, 5]), ("2", vec![4, 3]), ("4", vec![2, 1])] {
+      let page: Vec<Value> = page.into_iter().map(notice).collect();
+      Mock::given(method("GET"))
+        .and(path(NOTICES))
+        .and(query_param("count", "2"))
+        .and(query_param("skip", skip))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page))
+        .mount(&server)
+        .await;
+    }
+
+    let notices = client(&server, 0)
+      .fetch_notices_since(1, 3, 2)
+      .await
+      .unwrap();
+
+    assert_eq!(ids(&notices), vec![6, 5, 4, 3]);
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+  }
+
+  #[test]
+  fn long_fields_are_split_within_discord_limits() {
+    let line = "x".repeat(99);
+    let text = vec![line.as_str(); 30].join("\n");
+    let chunks = split_field(&text, FIELD_LIMIT);
+    assert_eq!(chunks.len(), 3);
+    assert!(chunks.iter().all(|c| c.chars().count() <= FIELD_LIMIT));
+    // 在换行处断开，内容不丢失
+    assert_eq!(chunks.join("\n"), text);
+
+    assert_eq!(split_field("", FIELD_LIMIT), vec![String::new()]);
+    assert_eq!(split_field(&"y".repeat(2048), FIELD_LIMIT).len(), 2);
+  }
+
+  #[test]
+  fn spoilers_survive_team_truncation() {
+    let notice = Notice {
+      id: 1,
+      notice_type: "FirstBlood".to_string(),
+      values: vec!["x".repeat(60), "web".to_string()],
+      time: 0,
+    };
+    let hidden = with_spoilers(&notice, &NoticeType::FirstBlood, &[SpoilerField::Team]);
+    assert_eq!(hidden.values[1], "web");
+
+    let team = blood_team(&hidden.values, None);
+    assert!(team.starts_with("||") && team.ends_with("…||"));
+  }
+
+  #[test]
+  fn truncation_keeps_grapheme_clusters_whole() {
+    let family = "👨\u{200d}👩\u{200d}👧";
+    assert_eq!(trunc_text(&format!("ab{}cd", family), 6), "ab…");
+    assert_eq!(trunc_text("🇨🇳🇺🇸🇯🇵", 5), "🇨🇳🇺🇸…");
+    assert_eq!(trunc_text("short", 10), "short");
+
+    // 中文每字占两格
+    assert_eq!(trunc_width("中文队伍名称", 8), "中文队…");
+    assert_eq!(trunc_width("team", 8), "team");
+  }
+}
@@ -1,20 +1,144 @@
+use serde_json::json;
 use serenity::async_trait;
-use serenity::model::channel::Message;
+use serenity::gateway::{ConnectionStage, ShardStageUpdateEvent};
+use serenity::model::application::{CommandInteraction, Interaction};
+use serenity::model::channel::{Message, Reaction};
 use serenity::model::gateway::Ready;
+use serenity::model::guild::Guild;
 use serenity::prelude::*;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 use tokio::sync::RwLock;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
 
+use crate::alerts::AlertManager;
+use crate::audit;
+use crate::bursts;
+use crate::challenges::ChallengeStore;
+use crate::clock::Clock;
+use crate::commands;
 use crate::config::Config;
+use crate::containers;
+use crate::cooldown::Cooldowns;
+use crate::countdown;
+use crate::crypto::Cipher;
+use crate::discord::DeliveryState;
+use crate::emoji;
+use crate::limiter::RequestLimiter;
+use crate::links::TeamLinks;
 use crate::log;
+use crate::onboarding::{self, Onboarding};
+use crate::pages::Paginator;
+use crate::permissions;
 use crate::polling::PollingService;
+use crate::presence;
 use crate::queue::MessageQueue;
+use crate::recent::RecentNotices;
+use crate::registration;
+use crate::scheduler::Scheduler;
+use crate::shutdown::ShutdownBarrier;
+use crate::spotlight;
+use crate::subscriptions::Subscriptions;
+use crate::tasks;
 use crate::tracker::NoticeTracker;
+use crate::visibility;
+
+// 一次性模式退出前，等待已到期重试的最长时间
+const ONCE_RETRY_GRACE: Duration = Duration::from_secs(10);
 
 pub struct BotHandler {
   pub config: Arc<Config>,
   pub tracker: Arc<RwLock<NoticeTracker>>,
   pub message_queue: Arc<MessageQueue>,
+  pub alerts: Arc<AlertManager>,
+  pub links: Arc<TeamLinks>,
+  pub recent: Arc<RecentNotices>,
+  pub challenges: Arc<ChallengeStore>,
+  pub scheduler: Arc<Scheduler>,
+  // 一次性模式下轮询一轮后取消，通知 main 退出
+  pub once: Option<CancellationToken>,
+  // 网关重连会再次触发 ready，状态更新任务只启动一次
+  pub presence_started: AtomicBool,
+  // 同上，轮询服务只创建一次，避免每次 ready 多出一个轮询循环并重新初始化
+  pub polling_started: AtomicBool,
+  pub started_at: Instant,
+  pub cooldowns: Cooldowns,
+  pub shutdown: Arc<ShutdownBarrier>,
+  pub subscriptions: Arc<Subscriptions>,
+  pub clock: Arc<dyn Clock>,
+  pub pages: Paginator,
+  pub onboarding: Onboarding,
+  // 持久化文件的加密密钥，未配置时为 None
+  pub cipher: Option<Arc<Cipher>>,
+  // 维护模式、审计频道、文本频道和频道降级状态，所有发送共用
+  pub delivery: Arc<DeliveryState>,
+  // 所有 GZCTF 客户端共用，限制对平台的并发请求总数
+  pub limiter: Arc<RequestLimiter>,
+}
+
+impl BotHandler {
+  // 先延迟应答避免慢命令超过 3 秒的交互时限，commands.public_commands 中的命令公开回复；
+  // 冷却提示和失败原因始终以仅自己可见的消息告知调用者
+  async fn handle_command(&self, ctx: &Context, command: &CommandInteraction) {
+    log::info(format!(
+      "Received /{} from {}",
+      command.data.name, command.user.name
+    ));
+    audit::record(
+      "command",
+      &command.user.name,
+      json!({
+        "user_id": command.user.id.get(),
+        "channel_id": command.channel_id.get(),
+        "command": command.data.name,
+        "options": command.data.options,
+      }),
+    );
+
+    let cooldown = self.cooldowns.check(
+      &command.data.name,
+      command.user.id.get(),
+      command.channel_id.get(),
+    );
+    let public = cooldown.is_ok() && self.config.commands.is_public(&command.data.name);
+    let deferred = if public {
+      command.defer(&ctx.http).await
+    } else {
+      command.defer_ephemeral(&ctx.http).await
+    };
+    if let Err(e) = deferred {
+      log::error(format!(
+        "Failed to acknowledge /{}: {}",
+        command.data.name, e
+      ));
+      return;
+    }
+
+    let result = match cooldown {
+      Ok(()) => match commands::execute(ctx, command, self).await {
+        Ok(reply) => commands::respond(ctx, command, reply, &self.pages).await,
+        Err(e) => {
+          log::error(format!("Command /{} failed: {:#}", command.data.name, e));
+          let reply = commands::error_reply(&e);
+          commands::respond_privately(ctx, command, reply, public, &self.pages).await
+        }
+      },
+      Err(wait) => {
+        let reply =
+          commands::Reply::from(format!("操作太频繁，请 {} 秒后再试", wait.as_secs().max(1)));
+        commands::respond(ctx, command, reply, &self.pages).await
+      }
+    };
+
+    if let Err(e) = result {
+      log::error(format!(
+        "Failed to respond to /{}: {}",
+        command.data.name, e
+      ));
+    }
+  }
 }
 
 #[async_trait]
@@ -23,27 +147,180 @@ impl EventHandler for BotHandler {
     log::success(format!("{} is connected and ready!", ready.user.name));
 
     let config = Arc::clone(&self.config);
-    let tracker = Arc::clone(&self.tracker);
     let message_queue = Arc::clone(&self.message_queue);
+    let alerts = Arc::clone(&self.alerts);
     let ctx = Arc::new(ctx);
 
-    message_queue.retrying(Arc::clone(&ctx)).await;
-
-    tokio::spawn(async move {
-      match PollingService::new(config, tracker, message_queue).map(Arc::new) {
-        Ok(service) => {
-          if let Err(e) = service.start_polling(ctx).await {
-            log::error(format!("Polling service error: {}", e));
+    // 一次性模式下也要记住已有服务器，否则随后的 guild_create 会把它们当作新加入的服务器欢迎
+    self.onboarding.remember(&ready.guilds);
+    if let Some(done) = self.once.clone() {
+      message_queue
+        .retrying(Arc::clone(&ctx), Arc::clone(&alerts))
+        .await;
+      let service = PollingService::new(self);
+      tokio::spawn(async move {
+        match service {
+          Ok(service) => {
+            if let Err(e) = service.run_once(&ctx).await {
+              log::error(format!("Polling service error: {}", e));
+            }
           }
+          Err(e) => log::error(format!("Polling service error: {}", e)),
+        }
+        message_queue.wait_idle(ONCE_RETRY_GRACE).await;
+        done.cancel();
+      });
+      return;
+    }
+
+    self.alerts.set_bot_user(ready.user.id);
+    for guild in &config.guilds {
+      if !ready.guilds.iter().any(|g| g.id.get() == guild.guild_id) {
+        log::error(format!(
+          "Bot is not a member of configured guild {}, its notices will fail",
+          guild.guild_id
+        ));
+      }
+    }
+    permissions::self_test(&ctx, &config, &alerts, ready.user.id).await;
+    emoji::check(&ctx, &config, &ready.guilds).await;
+    commands::register(&ctx).await;
+    self.alerts.record_gateway(true).await;
+    Arc::clone(&self.alerts).start_monitor(Arc::clone(&ctx), Arc::clone(&message_queue));
+    if config.discord.presence && !self.presence_started.swap(true, Ordering::SeqCst) {
+      presence::start(
+        Arc::clone(&ctx),
+        Arc::clone(&alerts),
+        config.get_matches().len(),
+      );
+    }
+    message_queue
+      .retrying(Arc::clone(&ctx), Arc::clone(&alerts))
+      .await;
+    countdown::start(
+      Arc::clone(&ctx),
+      Arc::clone(&config),
+      Arc::clone(&self.scheduler),
+      Arc::clone(&self.delivery),
+      Arc::clone(&self.limiter),
+    );
+    spotlight::start(
+      Arc::clone(&ctx),
+      Arc::clone(&config),
+      Arc::clone(&self.scheduler),
+      Arc::clone(&self.delivery),
+      Arc::clone(&self.limiter),
+    );
+    registration::start(
+      Arc::clone(&ctx),
+      Arc::clone(&config),
+      Arc::clone(&self.scheduler),
+      Arc::clone(&self.delivery),
+      Arc::clone(&self.limiter),
+    );
+    visibility::start(
+      Arc::clone(&ctx),
+      Arc::clone(&config),
+      Arc::clone(&self.scheduler),
+      Arc::clone(&self.delivery),
+      Arc::clone(&self.limiter),
+    );
+    containers::start(
+      Arc::clone(&ctx),
+      Arc::clone(&config),
+      Arc::clone(&self.scheduler),
+      Arc::clone(&self.delivery),
+      Arc::clone(&self.limiter),
+    );
+    bursts::start(
+      Arc::clone(&ctx),
+      Arc::clone(&config),
+      Arc::clone(&self.scheduler),
+      Arc::clone(&alerts),
+      Arc::clone(&self.limiter),
+    );
+
+    if self.polling_started.swap(true, Ordering::SeqCst) {
+      return;
+    }
+    let service = match PollingService::new(self) {
+      Ok(service) => Arc::new(service),
+      Err(e) => {
+        log::error(format!("Polling service error: {}", e));
+        // 下次 ready 时再尝试创建
+        self.polling_started.store(false, Ordering::SeqCst);
+        return;
+      }
+    };
+    // 轮询任务 panic 后按退避间隔重启，并在管理频道告警
+    tasks::supervise("polling", Some((Arc::clone(&ctx), alerts)), move || {
+      let service = Arc::clone(&service);
+      let ctx = Arc::clone(&ctx);
+      async move {
+        if let Err(e) = service.start_polling(ctx).await {
+          log::error(format!("Polling service error: {}", e));
         }
-        Err(e) => log::error(format!("Polling service error: {}", e)),
       }
     });
   }
 
-  async fn message(&self, _ctx: Context, msg: Message) {
-    if msg.content == "!ping" {
-      log::info(format!("Received ping from {}", msg.author.name));
+  async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+    match interaction {
+      Interaction::Command(command) => self.handle_command(&ctx, &command).await,
+      Interaction::Component(component) => self.pages.turn(&ctx, &component).await,
+      _ => {}
+    }
+  }
+
+  async fn guild_create(&self, ctx: Context, guild: Guild, _is_new: Option<bool>) {
+    if self.config.discord.onboarding && self.onboarding.is_new(guild.id.get()) {
+      onboarding::welcome(
+        &ctx,
+        &guild,
+        self.config.discord.admin_channel_id,
+        &self.delivery,
+      )
+      .await;
+    }
+  }
+
+  async fn shard_stage_update(&self, _ctx: Context, event: ShardStageUpdateEvent) {
+    match event.new {
+      ConnectionStage::Connected => self.alerts.record_gateway(true).await,
+      ConnectionStage::Disconnected => self.alerts.record_gateway(false).await,
+      _ => {}
+    }
+  }
+
+  async fn reaction_add(&self, _ctx: Context, reaction: Reaction) {
+    self.alerts.handle_reaction(&reaction).await;
+  }
+
+  async fn message(&self, ctx: Context, msg: Message) {
+    if msg.author.bot || msg.content.trim() != "!ping" {
+      return;
+    }
+    log::info(format!("Received ping from {}", msg.author.name));
+    audit::record(
+      "command",
+      &msg.author.name,
+      json!({
+        "user_id": msg.author.id.get(),
+        "channel_id": msg.channel_id.get(),
+        "command": "!ping",
+      }),
+    );
+    // 文字命令的回复所有人可见，冷却中直接忽略，避免提示本身刷屏
+    if self
+      .cooldowns
+      .check("ping", msg.author.id.get(), msg.channel_id.get())
+      .is_err()
+    {
+      return;
+    }
+    let reply = commands::ping(&ctx, self).await;
+    if let Err(e) = commands::reply_to(&ctx, &msg, reply).await {
+      log::error(format!("Failed to answer ping: {}", e));
     }
   }
 }
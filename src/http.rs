@@ -0,0 +1,195 @@
+use axum::Router;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::net::TcpListener;
+
+use crate::alerts::AlertManager;
+use crate::config::Config;
+use crate::gzctf::format_time;
+use crate::log;
+use crate::queue::MessageQueue;
+use crate::recent::RecentNotices;
+
+const RECENT_ON_PAGE: usize = 20;
+
+struct HttpState {
+  config: Arc<Config>,
+  token: String,
+  message_queue: Arc<MessageQueue>,
+  recent: Arc<RecentNotices>,
+  alerts: Arc<AlertManager>,
+  started_at: Instant,
+}
+
+pub fn start(
+  config: Arc<Config>,
+  message_queue: Arc<MessageQueue>,
+  recent: Arc<RecentNotices>,
+  alerts: Arc<AlertManager>,
+) {
+  let Some(http) = config.http.clone() else {
+    return;
+  };
+  let state = Arc::new(HttpState {
+    config,
+    token: http.token,
+    message_queue,
+    recent,
+    alerts,
+    started_at: Instant::now(),
+  });
+
+  tokio::spawn(async move {
+    let listener = match TcpListener::bind(&http.bind).await {
+      Ok(listener) => listener,
+      Err(e) => {
+        log::error(format!(
+          "Failed to bind HTTP server on {}: {}",
+          http.bind, e
+        ));
+        return;
+      }
+    };
+
+    let app = Router::new()
+      .route("/", get(status_page))
+      .route("/status", get(status_page))
+      .with_state(state);
+
+    log::success(format!(
+      "Status page listening on http://{}/status",
+      http.bind
+    ));
+    if let Err(e) = axum::serve(listener, app).await {
+      log::error(format!("HTTP server error: {}", e));
+    }
+  });
+}
+
+async fn status_page(State(state): State<Arc<HttpState>>, headers: HeaderMap) -> Response {
+  if !authorized(&headers, &state.token) {
+    return (
+      StatusCode::UNAUTHORIZED,
+      [(header::WWW_AUTHENTICATE, "Bearer")],
+      "unauthorized",
+    )
+      .into_response();
+  }
+
+  Html(render_status(&state).await).into_response()
+}
+
+fn authorized(headers: &HeaderMap, token: &str) -> bool {
+  let Some(provided) = headers
+    .get(header::AUTHORIZATION)
+    .and_then(|v| v.to_str().ok())
+    .and_then(|v| v.strip_prefix("Bearer "))
+  else {
+    return false;
+  };
+
+  // 定长比较，避免通过响应时间猜测 token；空 token 在加载配置时已被拒绝，这里再兜底一次
+  !token.is_empty()
+    && provided.len() == token.len()
+    && provided
+      .bytes()
+      .zip(token.bytes())
+      .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+      == 0
+}
+
+async fn render_status(state: &HttpState) -> String {
+  let metrics = state.message_queue.metrics().await;
+  let recent = state.recent.latest(RECENT_ON_PAGE).await;
+  let mut html = String::new();
+
+  html.push_str(
+    "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>dc-bot status</title>\
+     <style>body{font-family:sans-serif;margin:2em}table{border-collapse:collapse}\
+     td,th{border:1px solid #ccc;padding:4px 8px;text-align:left}</style></head><body>",
+  );
+  let _ = write!(
+    html,
+    "<h1>dc-bot status</h1><p>Uptime: {}s · Poll interval: {}s</p>",
+    state.started_at.elapsed().as_secs(),
+    state.config.gzctf.poll_interval
+  );
+
+  html.push_str("<h2>Matches</h2><table><tr><th>ID</th><th>Name</th></tr>");
+  for match_config in state.config.get_matches() {
+    let _ = write!(
+      html,
+      "<tr><td>{}</td><td>{}</td></tr>",
+      match_config.id,
+      escape(match_config.name.as_deref().unwrap_or("-"))
+    );
+  }
+  html.push_str("</table>");
+
+  let _ = write!(
+    html,
+    "<h2>Queue</h2><table>\
+     <tr><th>Depth</th><td>{}</td></tr>\
+     <tr><th>Retries</th><td>{}</td></tr>\
+     <tr><th>Persisted</th><td>{}</td></tr>\
+     <tr><th>Oldest undelivered</th><td>{}</td></tr></table>",
+    metrics.depth,
+    metrics.retries,
+    metrics.persisted,
+    metrics
+      .oldest_age
+      .map(|age| format!("{}s ago", age.as_secs()))
+      .unwrap_or_else(|| "-".to_string())
+  );
+
+  let gateway = state.alerts.gateway_stats().await;
+  let _ = write!(
+    html,
+    "<h2>Gateway</h2><table>\
+     <tr><th>Status</th><td>{}</td></tr>\
+     <tr><th>Connects</th><td>{}</td></tr>\
+     <tr><th>Disconnects</th><td>{}</td></tr>\
+     <tr><th>Reconnects</th><td>{}</td></tr>\
+     <tr><th>Total downtime</th><td>{}s</td></tr></table>",
+    match (gateway.connected_since, gateway.down_since) {
+      (Some(since), _) => format!("connected for {}s", since.elapsed().as_secs()),
+      (None, Some(since)) => format!("disconnected for {}s", since.elapsed().as_secs()),
+      (None, None) => "not connected".to_string(),
+    },
+    gateway.connects,
+    gateway.disconnects,
+    gateway.reconnects(),
+    gateway.total_downtime().as_secs()
+  );
+
+  html.push_str(
+    "<h2>Recently relayed</h2><table>\
+     <tr><th>Time</th><th>Match</th><th>Type</th><th>Content</th></tr>",
+  );
+  for (match_id, entry) in recent {
+    let _ = write!(
+      html,
+      "<tr><td>{}</td><td>{}</td><td>{:?}</td><td>{}</td></tr>",
+      format_time(entry.notice.time),
+      escape(&entry.match_name.unwrap_or_else(|| match_id.to_string())),
+      entry.notice_type,
+      escape(&entry.notice.values.join(" / "))
+    );
+  }
+  html.push_str("</table></body></html>");
+
+  html
+}
+
+fn escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
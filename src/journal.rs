@@ -0,0 +1,349 @@
+use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use crate::crypto::{self, Cipher};
+use crate::log;
+use crate::models::Notice;
+use crate::queue::{MessageItem, MessageQueue};
+use crate::tracker::NoticeTracker;
+
+pub const JOURNAL_PATH: &str = "journal.log";
+// 后台 fsync 的间隔
+const SYNC_INTERVAL: Duration = Duration::from_millis(200);
+
+// 两次检查点之间的状态变化，每行一条 JSON（配置了密钥时为加密后的 base64）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+enum Event {
+  // 公告已处理完毕（送达或已入队），对应 tracker.record
+  Recorded {
+    match_id: u32,
+    notice_type: String,
+    notice: Notice,
+  },
+  // 消息进入重试队列
+  Scheduled {
+    item: Box<MessageItem>,
+  },
+  // 重试队列中的消息已送达
+  Delivered {
+    id: String,
+  },
+}
+
+// 只追加的预写日志：每条事件写入文件后才继续，崩溃后启动时重放到 tracker 和队列上，
+// 重放完成并保存快照（检查点）后清空。
+// 写入只进内核页缓存，进程崩溃不会丢失；fsync 由后台线程批量完成，不阻塞 tokio 工作线程，
+// 系统断电时最多丢失最近 SYNC_INTERVAL 内的事件
+pub struct Journal {
+  path: String,
+  file: Mutex<File>,
+  cipher: Option<Arc<Cipher>>,
+  // 有尚未 fsync 的写入；后台线程只持有弱引用，Journal 释放后做最后一次 fsync 并退出
+  dirty: Arc<AtomicBool>,
+}
+
+impl fmt::Debug for Journal {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Journal").field("path", &self.path).finish()
+  }
+}
+
+impl Journal {
+  pub fn open(path: &str, cipher: Option<Arc<Cipher>>) -> Result<Self> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let dirty = Arc::new(AtomicBool::new(false));
+    Self::spawn_syncer(path, file.try_clone()?, Arc::downgrade(&dirty))?;
+    Ok(Self {
+      path: path.to_string(),
+      file: Mutex::new(file),
+      cipher,
+      dirty,
+    })
+  }
+
+  // 使用复制的文件句柄，fsync 期间不持有写入锁
+  fn spawn_syncer(path: &str, file: File, dirty: Weak<AtomicBool>) -> Result<()> {
+    let path = path.to_string();
+    std::thread::Builder::new()
+      .name("journal-sync".to_string())
+      .spawn(move || {
+        loop {
+          std::thread::sleep(SYNC_INTERVAL);
+          let Some(dirty) = dirty.upgrade() else {
+            let _ = file.sync_data();
+            return;
+          };
+          if dirty.swap(false, Ordering::AcqRel)
+            && let Err(e) = file.sync_data()
+          {
+            dirty.store(true, Ordering::Release);
+            log::error(format!("Failed to sync journal {}: {}", path, e));
+          }
+        }
+      })?;
+    Ok(())
+  }
+
+  pub fn recorded(&self, match_id: u32, notice_type: &str, notice: &Notice) {
+    self.append(&Event::Recorded {
+      match_id,
+      notice_type: notice_type.to_string(),
+      notice: notice.clone(),
+    });
+  }
+
+  pub fn scheduled(&self, item: &MessageItem) {
+    self.append(&Event::Scheduled {
+      item: Box::new(item.clone()),
+    });
+  }
+
+  pub fn delivered(&self, id: &str) {
+    self.append(&Event::Delivered { id: id.to_string() });
+  }
+
+  // 写日志失败不阻塞播报，只是崩溃后可能重复或漏掉这一条
+  fn append(&self, event: &Event) {
+    if let Err(e) = self.try_append(event) {
+      log::error(format!("Failed to write journal {}: {}", self.path, e));
+    }
+  }
+
+  fn try_append(&self, event: &Event) -> Result<()> {
+    let json = serde_json::to_vec(event)?;
+    let mut line = match self.cipher.as_deref() {
+      Some(cipher) => BASE64.encode(cipher.encrypt(&json)?).into_bytes(),
+      None => json,
+    };
+    line.push(b'\n');
+
+    self.file.lock().unwrap().write_all(&line)?;
+    self.dirty.store(true, Ordering::Release);
+    Ok(())
+  }
+
+  // 按顺序重放上次运行留下的事件；最后一行可能因崩溃而不完整，无法解析的行跳过。
+  // 已送达的消息即使还留在旧快照里也会被移除
+  pub async fn replay(&self, tracker: &mut NoticeTracker, queue: &MessageQueue) -> Result<usize> {
+    let events = self.events()?;
+    let applied = events.len();
+    let mut scheduled: Vec<MessageItem> = Vec::new();
+    let mut delivered = HashSet::new();
+
+    for event in events {
+      match event {
+        Event::Recorded {
+          match_id,
+          notice_type,
+          notice,
+        } => tracker.record(match_id, &notice_type, &notice),
+        Event::Scheduled { item } => {
+          delivered.remove(&item.id);
+          scheduled.push(*item);
+        }
+        Event::Delivered { id } => {
+          scheduled.retain(|item| item.id != id);
+          delivered.insert(id);
+        }
+      }
+    }
+
+    for item in scheduled {
+      queue.restore(item).await;
+    }
+    queue.forget(&delivered).await;
+    Ok(applied)
+  }
+
+  // 只把已处理的公告重放到 tracker 上，供不需要队列的离线命令（state export）使用
+  pub fn replay_records(&self, tracker: &mut NoticeTracker) -> Result<usize> {
+    let mut applied = 0;
+    for event in self.events()? {
+      if let Event::Recorded {
+        match_id,
+        notice_type,
+        notice,
+      } = event
+      {
+        tracker.record(match_id, &notice_type, &notice);
+        applied += 1;
+      }
+    }
+    Ok(applied)
+  }
+
+  fn events(&self) -> Result<Vec<Event>> {
+    let content = fs::read_to_string(&self.path)?;
+    let mut events = Vec::new();
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+      match self.decode(line) {
+        Ok(event) => events.push(event),
+        Err(e) => log::warn(format!("Skipping unreadable journal entry: {}", e)),
+      }
+    }
+    Ok(events)
+  }
+
+  fn decode(&self, line: &str) -> Result<Event> {
+    let data = match self.cipher.as_deref() {
+      Some(_) if !line.starts_with('{') => BASE64.decode(line.trim())?,
+      _ => line.as_bytes().to_vec(),
+    };
+    let json = crypto::open(self.cipher.as_deref(), data)?;
+    Ok(serde_json::from_slice(&json)?)
+  }
+
+  // 检查点：调用方已把 tracker 和队列完整保存到快照后清空日志
+  pub fn truncate(&self) -> Result<()> {
+    let file = self.file.lock().unwrap();
+    file.set_len(0)?;
+    file.sync_data()?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::discord::DiscordMessenger;
+  use crate::models::NoticeType;
+
+  // 每个测试使用独立的日志文件，结束时连同队列的持久化文件一起清理
+  struct TempJournal(String);
+
+  impl TempJournal {
+    fn new(name: &str) -> Self {
+      let path = std::env::temp_dir().join(format!("dc-bot-{}-{}.log", name, std::process::id()));
+      let temp = Self(path.to_string_lossy().into_owned());
+      temp.cleanup();
+      temp
+    }
+
+    fn journal(&self, cipher: Option<Arc<Cipher>>) -> Journal {
+      Journal::open(&self.0, cipher).unwrap()
+    }
+
+    fn queue(&self) -> MessageQueue {
+      MessageQueue::new(
+        format!("{}.queue", self.0),
+        0,
+        Arc::new(DiscordMessenger::new(0, Arc::default())),
+      )
+    }
+
+    fn cleanup(&self) {
+      for path in [self.0.clone(), format!("{}.queue", self.0)] {
+        let _ = std::fs::remove_file(path);
+      }
+    }
+  }
+
+  impl Drop for TempJournal {
+    fn drop(&mut self) {
+      self.cleanup();
+    }
+  }
+
+  fn test_item(id: &str) -> MessageItem {
+    let notice = Notice {
+      id: 1,
+      notice_type: "Normal".to_string(),
+      values: vec!["hello".to_string()],
+      time: 1_700_000_000_000,
+    };
+    MessageItem::new(
+      id.to_string(),
+      notice,
+      NoticeType::Normal,
+      None,
+      1,
+      "https://example.com".to_string(),
+      0,
+    )
+  }
+
+  #[tokio::test]
+  async fn replay_skips_truncated_last_line() {
+    let temp = TempJournal::new("journal-truncated");
+    let journal = temp.journal(None);
+    let item = test_item("1:1:1");
+    journal.recorded(1, "Normal", &item.notice);
+    journal.scheduled(&item);
+    // 崩溃时只写了一半的最后一行
+    let mut file = std::fs::OpenOptions::new()
+      .append(true)
+      .open(temp.0.clone())
+      .unwrap();
+    std::io::Write::write_all(&mut file, br#"{"event":"delivered","id":"1:1"#).unwrap();
+
+    let mut tracker = NoticeTracker::new();
+    let queue = temp.queue();
+    assert_eq!(journal.replay(&mut tracker, &queue).await.unwrap(), 2);
+    assert!(!tracker.is_new(1, "Normal", &item.notice));
+    assert!(queue.contains("1:1:1").await);
+  }
+
+  #[tokio::test]
+  async fn replay_drops_delivered_items() {
+    let temp = TempJournal::new("journal-delivered");
+    let journal = temp.journal(None);
+    journal.scheduled(&test_item("1:1:1"));
+    journal.scheduled(&test_item("1:2:2"));
+    journal.delivered("1:1:1");
+    // 旧快照里还留着、但检查点之后已送达的消息
+    journal.delivered("1:3:3");
+
+    let queue = temp.queue();
+    queue.restore(test_item("1:3:3")).await;
+    journal
+      .replay(&mut NoticeTracker::new(), &queue)
+      .await
+      .unwrap();
+
+    assert!(!queue.contains("1:1:1").await);
+    assert!(queue.contains("1:2:2").await);
+    assert!(!queue.contains("1:3:3").await);
+  }
+
+  #[tokio::test]
+  async fn replay_reads_encrypted_lines() {
+    let temp = TempJournal::new("journal-encrypted");
+    let cipher = Some(Arc::new(Cipher::from_passphrase("secret")));
+    let journal = temp.journal(cipher.clone());
+    journal.scheduled(&test_item("1:1:1"));
+    assert!(
+      !std::fs::read_to_string(temp.0.clone())
+        .unwrap()
+        .contains("hello")
+    );
+
+    let queue = temp.queue();
+    journal
+      .replay(&mut NoticeTracker::new(), &queue)
+      .await
+      .unwrap();
+    assert!(queue.contains("1:1:1").await);
+
+    // 没有密钥时读不出加密的行，全部跳过
+    let plain = temp.journal(None);
+    let queue = temp.queue();
+    assert_eq!(
+      plain
+        .replay(&mut NoticeTracker::new(), &queue)
+        .await
+        .unwrap(),
+      0
+    );
+    assert_eq!(queue.len().await, 0);
+  }
+}
@@ -0,0 +1,44 @@
+use tokio::sync::{Mutex, Semaphore, SemaphorePermit};
+use tokio::time::{Duration, Instant, sleep_until};
+
+use crate::config::GzctfConfig;
+
+// 限制同时进行的请求数和相邻两次请求的最小间隔，permit 在请求结束前一直持有
+pub struct RequestLimiter {
+  permits: Semaphore,
+  spacing: Duration,
+  next_slot: Mutex<Instant>,
+}
+
+impl RequestLimiter {
+  // max_in_flight 为 0 表示不限制并发
+  pub fn new(max_in_flight: usize, spacing: Duration) -> Self {
+    let permits = match max_in_flight {
+      0 => Semaphore::MAX_PERMITS,
+      n => n,
+    };
+    Self {
+      permits: Semaphore::new(permits),
+      spacing,
+      next_slot: Mutex::new(Instant::now()),
+    }
+  }
+
+  pub fn from_config(config: &GzctfConfig) -> Self {
+    Self::new(
+      config.max_in_flight,
+      Duration::from_millis(config.min_request_interval_ms),
+    )
+  }
+
+  pub async fn acquire(&self) -> SemaphorePermit<'_> {
+    // 信号量从不关闭
+    let permit = self.permits.acquire().await.unwrap();
+    if !self.spacing.is_zero() {
+      let mut next_slot = self.next_slot.lock().await;
+      sleep_until(*next_slot).await;
+      *next_slot = Instant::now() + self.spacing;
+    }
+    permit
+  }
+}
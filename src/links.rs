@@ -0,0 +1,163 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use tokio::fs;
+use tokio::sync::RwLock;
+
+use crate::audit;
+use crate::log;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LinkData {
+  // Discord 用户 ID -> GZCTF 队伍名，每个用户只能关联一个队伍；管理员确认后才生效
+  #[serde(default)]
+  approved: HashMap<u64, String>,
+  // 用户通过 /linkteam 提交、等待管理员确认的关联；旧版本未经确认的关联也归入这里
+  #[serde(default, alias = "users")]
+  pending: HashMap<u64, String>,
+}
+
+pub struct TeamLinks {
+  persist_path: String,
+  data: RwLock<LinkData>,
+  // 配置文件中静态指定的队伍成员
+  static_links: HashMap<String, Vec<u64>>,
+}
+
+impl TeamLinks {
+  pub async fn load_from_disk(persist_path: &str) -> Result<Self> {
+    let data = if fs::try_exists(persist_path).await.unwrap_or(false) {
+      let content = fs::read_to_string(persist_path).await?;
+      let data: LinkData = serde_json::from_str(&content)?;
+      log::success(format!(
+        "Loaded {} team links ({} awaiting approval) from disk.",
+        data.approved.len(),
+        data.pending.len()
+      ));
+      data
+    } else {
+      LinkData::default()
+    };
+
+    Ok(Self {
+      persist_path: persist_path.to_string(),
+      data: RwLock::new(data),
+      static_links: HashMap::new(),
+    })
+  }
+
+  pub fn empty(persist_path: &str) -> Self {
+    Self {
+      persist_path: persist_path.to_string(),
+      data: RwLock::new(LinkData::default()),
+      static_links: HashMap::new(),
+    }
+  }
+
+  pub fn with_static_links(mut self, static_links: HashMap<String, Vec<u64>>) -> Self {
+    self.static_links = static_links;
+    self
+  }
+
+  // 提交关联申请，返回当前已生效的关联
+  pub async fn request(&self, user_id: u64, team_name: &str) -> Result<Option<String>> {
+    let current = {
+      let mut data = self.data.write().await;
+      data.pending.insert(user_id, team_name.to_string());
+      data.approved.get(&user_id).cloned()
+    };
+    self.save_to_disk().await?;
+    audit::record(
+      "team_link_requested",
+      &user_id.to_string(),
+      json!({ "team": team_name, "current": current }),
+    );
+    Ok(current)
+  }
+
+  // 确认用户的关联申请，返回生效的队伍名；没有待确认的申请时返回 None
+  pub async fn approve(&self, user_id: u64, approver: &str) -> Result<Option<String>> {
+    let team = {
+      let mut data = self.data.write().await;
+      let Some(team) = data.pending.remove(&user_id) else {
+        return Ok(None);
+      };
+      data.approved.insert(user_id, team.clone());
+      team
+    };
+    self.save_to_disk().await?;
+    audit::record(
+      "team_linked",
+      approver,
+      json!({ "user_id": user_id, "team": team }),
+    );
+    Ok(Some(team))
+  }
+
+  // 待确认的申请，按用户 ID 排序
+  pub async fn pending(&self) -> Vec<(u64, String)> {
+    let mut pending: Vec<(u64, String)> = self
+      .data
+      .read()
+      .await
+      .pending
+      .iter()
+      .map(|(user_id, team)| (*user_id, team.clone()))
+      .collect();
+    pending.sort_unstable();
+    pending
+  }
+
+  // 同时撤回已生效的关联和待确认的申请，返回被撤回的队伍名
+  pub async fn unlink(&self, user_id: u64) -> Result<Option<String>> {
+    let (previous, requested) = {
+      let mut data = self.data.write().await;
+      (
+        data.approved.remove(&user_id),
+        data.pending.remove(&user_id),
+      )
+    };
+    if previous.is_some() || requested.is_some() {
+      self.save_to_disk().await?;
+      audit::record(
+        "team_unlinked",
+        &user_id.to_string(),
+        json!({ "team": previous, "requested": requested }),
+      );
+    }
+    Ok(previous.or(requested))
+  }
+
+  // 配置文件中的映射与管理员确认过的 /linkteam 关联合并，待确认的申请不算
+  pub async fn members_of(&self, team_name: &str) -> Vec<u64> {
+    let mut members = self
+      .static_links
+      .get(team_name)
+      .cloned()
+      .unwrap_or_default();
+    members.extend(
+      self
+        .data
+        .read()
+        .await
+        .approved
+        .iter()
+        .filter(|(_, team)| team.as_str() == team_name)
+        .map(|(user_id, _)| *user_id),
+    );
+    members.sort_unstable();
+    members.dedup();
+    members
+  }
+
+  async fn save_to_disk(&self) -> Result<()> {
+    let json = serde_json::to_string_pretty(&*self.data.read().await)?;
+
+    let tmp_path = format!("{}.tmp", self.persist_path);
+    fs::write(&tmp_path, &json).await?;
+    fs::rename(&tmp_path, &self.persist_path).await?;
+
+    Ok(())
+  }
+}
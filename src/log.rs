@@ -1,13 +1,171 @@
+use chrono::{Local, NaiveDate};
 use colored::*;
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+use crate::config::LogConfig;
+
+static FILE_SINK: Mutex<Option<RotatingFile>> = Mutex::new(None);
+static CORRELATION_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+tokio::task_local! {
+  // 当前轮询周期 / 消息的关联 ID，嵌套时以 "/" 连接
+  static CORRELATION: String;
+}
 
 pub fn success(msg: impl std::fmt::Display) {
-  println!("{}", format!("[+] {}", msg).green());
+  let line = format!("[+] {}{}", correlation_tag(), msg);
+  write_file(&line);
+  println!("{}", line.green());
 }
 
 pub fn info(msg: impl std::fmt::Display) {
-  println!("{}", format!("[*] {}", msg).blue());
+  let line = format!("[*] {}{}", correlation_tag(), msg);
+  write_file(&line);
+  println!("{}", line.blue());
+}
+
+pub fn warn(msg: impl std::fmt::Display) {
+  let line = format!("[!] {}{}", correlation_tag(), msg);
+  write_file(&line);
+  eprintln!("{}", line.yellow());
 }
 
 pub fn error(msg: impl std::fmt::Display) {
-  eprintln!("{}", format!("[-] {}", msg).red());
+  let line = format!("[-] {}{}", correlation_tag(), msg);
+  write_file(&line);
+  eprintln!("{}", line.red());
+}
+
+// 秒级时间戳 + 进程内计数，重启后也不易重复
+pub fn new_correlation_id(prefix: &str) -> String {
+  let counter = CORRELATION_COUNTER.fetch_add(1, Ordering::Relaxed);
+  format!(
+    "{}-{:08x}{:04x}",
+    prefix,
+    Local::now().timestamp() as u32,
+    counter & 0xffff
+  )
+}
+
+pub fn current_correlation() -> Option<String> {
+  CORRELATION.try_with(Clone::clone).ok()
+}
+
+// 在给定关联 ID 下执行 future，其中输出的日志都会带上该 ID
+pub async fn correlate<F: Future>(id: &str, future: F) -> F::Output {
+  CORRELATION.scope(nested(id), future).await
+}
+
+pub fn correlate_sync<R>(id: &str, f: impl FnOnce() -> R) -> R {
+  CORRELATION.sync_scope(nested(id), f)
+}
+
+fn nested(id: &str) -> String {
+  match current_correlation() {
+    Some(parent) => format!("{}/{}", parent, id),
+    None => id.to_string(),
+  }
+}
+
+fn correlation_tag() -> String {
+  current_correlation()
+    .map(|id| format!("[{}] ", id))
+    .unwrap_or_default()
+}
+
+// 在控制台输出之外同时写入日志文件；启动和重新加载配置时重新打开，未配置时关闭
+pub fn init_file(config: &LogConfig) -> std::io::Result<()> {
+  let sink = match &config.file {
+    Some(path) => Some(RotatingFile::open(
+      path.clone(),
+      config.max_size_mb * 1024 * 1024,
+      config.daily,
+      config.keep,
+    )?),
+    None => None,
+  };
+  *FILE_SINK.lock().unwrap() = sink;
+  Ok(())
+}
+
+fn write_file(line: &str) {
+  let Ok(mut sink) = FILE_SINK.lock() else {
+    return;
+  };
+  let Some(sink) = sink.as_mut() else {
+    return;
+  };
+  let stamped = format!("{} {}\n", Local::now().format("%Y-%m-%d %H:%M:%S"), line);
+  if let Err(e) = sink.write(stamped.as_bytes()) {
+    eprintln!("{}", format!("[-] Failed to write log file: {}", e).red());
+  }
+}
+
+struct RotatingFile {
+  path: String,
+  file: File,
+  size: u64,
+  // 0 表示不按大小轮转
+  max_bytes: u64,
+  daily: bool,
+  opened_on: NaiveDate,
+  keep: usize,
+}
+
+impl RotatingFile {
+  fn open(path: String, max_bytes: u64, daily: bool, keep: usize) -> std::io::Result<Self> {
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let size = file.metadata()?.len();
+
+    Ok(Self {
+      path,
+      file,
+      size,
+      max_bytes,
+      daily,
+      opened_on: Local::now().date_naive(),
+      keep,
+    })
+  }
+
+  fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+    let size_exceeded = self.max_bytes > 0 && self.size + bytes.len() as u64 > self.max_bytes;
+    let day_changed = self.daily && Local::now().date_naive() != self.opened_on;
+    if self.size > 0 && (size_exceeded || day_changed) {
+      self.rotate()?;
+    }
+
+    self.file.write_all(bytes)?;
+    self.size += bytes.len() as u64;
+    Ok(())
+  }
+
+  // bot.log -> bot.log.1 -> bot.log.2 ... 超出 keep 的最旧文件被删除
+  fn rotate(&mut self) -> std::io::Result<()> {
+    self.file.flush()?;
+
+    if self.keep == 0 {
+      std::fs::remove_file(&self.path)?;
+    } else {
+      let oldest = format!("{}.{}", self.path, self.keep);
+      if Path::new(&oldest).exists() {
+        std::fs::remove_file(&oldest)?;
+      }
+      for index in (1..self.keep).rev() {
+        let from = format!("{}.{}", self.path, index);
+        if Path::new(&from).exists() {
+          std::fs::rename(&from, format!("{}.{}", self.path, index + 1))?;
+        }
+      }
+      std::fs::rename(&self.path, format!("{}.1", self.path))?;
+    }
+
+    *self = Self::open(self.path.clone(), self.max_bytes, self.daily, self.keep)?;
+    Ok(())
+  }
 }
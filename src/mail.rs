@@ -0,0 +1,71 @@
+use anyhow::Result;
+use lettre::message::Mailbox;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serenity::async_trait;
+
+use crate::config::{MailConfig, MailSecurity};
+use crate::models::NoticeType;
+use crate::relays::{PlainNotice, Relay};
+
+pub struct MailRelay {
+  transport: AsyncSmtpTransport<Tokio1Executor>,
+  from: Mailbox,
+  to: Vec<Mailbox>,
+}
+
+impl MailRelay {
+  // 地址在启动时解析，配置错误直接报出而不是等到第一条公告
+  pub fn new(config: MailConfig) -> Result<Self> {
+    let mut builder = match config.security {
+      MailSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+      MailSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+      MailSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+    };
+    if let Some(port) = config.port {
+      builder = builder.port(port);
+    }
+    if let (Some(username), Some(password)) = (config.username, config.password) {
+      builder = builder.credentials(Credentials::new(username, password));
+    }
+
+    Ok(Self {
+      transport: builder.build(),
+      from: config.from.parse()?,
+      to: config
+        .to
+        .iter()
+        .map(|address| address.parse())
+        .collect::<Result<_, _>>()?,
+    })
+  }
+}
+
+#[async_trait]
+impl Relay for MailRelay {
+  fn name(&self) -> &str {
+    "mail"
+  }
+
+  // 邮件只用于比赛公告这类关键消息
+  fn wants(&self, notice_type: &NoticeType) -> bool {
+    *notice_type == NoticeType::Normal
+  }
+
+  async fn send(&self, notice: &PlainNotice) -> Result<()> {
+    let mut builder = Message::builder()
+      .from(self.from.clone())
+      .subject(&notice.title)
+      .header(ContentType::TEXT_PLAIN);
+    for recipient in &self.to {
+      builder = builder.to(recipient.clone());
+    }
+
+    self
+      .transport
+      .send(builder.body(notice.body.clone())?)
+      .await?;
+    Ok(())
+  }
+}
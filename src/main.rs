@@ -1,24 +1,95 @@
+mod alerts;
+mod audit;
+mod bursts;
+mod challenges;
+mod clock;
+mod commands;
+mod compat;
 mod config;
+mod containers;
+mod cooldown;
+mod countdown;
+mod crypto;
+mod ctfd;
+mod daemon;
+mod digest;
 mod discord;
+mod discussion;
+mod emoji;
+mod feed;
 mod gzctf;
 mod handler;
+mod http;
+mod journal;
+mod limiter;
+mod links;
 mod log;
+mod mail;
+mod maintenance;
 mod models;
+mod onboarding;
+mod onebot;
+mod pages;
+mod permissions;
+mod platform;
 mod polling;
+mod presence;
+mod preview;
 mod queue;
+mod recent;
+mod registration;
+mod relays;
+mod rewards;
+mod scheduler;
+mod scores;
+#[cfg(windows)]
+mod service;
+mod shutdown;
+mod signals;
+mod spotlight;
+mod state;
+mod subscriptions;
+mod tasks;
+mod template;
+mod text;
 mod tracker;
+mod translate;
+mod update;
+mod visibility;
+mod webhook;
 
-use anyhow::Result;
-use clap::Parser;
-use config::Config;
-use discord::DiscordMessenger;
+use alerts::AlertManager;
+use anyhow::{Context as _, Result, bail};
+use challenges::ChallengeStore;
+use clap::{Parser, Subcommand};
+use clock::{Clock, SystemClock};
+use config::{Config, StorageConfig};
+use cooldown::Cooldowns;
+use discord::{DeliveryState, DiscordMessenger};
 use handler::BotHandler;
+use journal::{JOURNAL_PATH, Journal};
+use limiter::RequestLimiter;
+use links::TeamLinks;
+use maintenance::Maintenance;
+use onboarding::Onboarding;
+use pages::Paginator;
 use queue::MessageQueue;
+use recent::RecentNotices;
+use scheduler::Scheduler;
+use serde_json::json;
 use serenity::prelude::*;
+use shutdown::ShutdownBarrier;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+use subscriptions::Subscriptions;
 use tokio::sync::RwLock;
 use tokio::time::{Duration, timeout};
-use tracker::NoticeTracker;
+use tokio_util::sync::CancellationToken;
+use tracker::{NoticeTracker, TRACKER_PATH};
+
+// 退出时为写盘保留的时间，其余时间用于等待正在进行的发送和重试循环
+const PERSIST_RESERVE: Duration = Duration::from_secs(2);
 
 #[derive(Parser, Debug)]
 #[command(name = "dc-bot")]
@@ -26,96 +97,478 @@ use tracker::NoticeTracker;
 struct Cli {
   #[arg(short, long, default_value = "config.toml")]
   config: String,
+  // 选择 [profiles.<name>] 覆盖基础配置
+  #[arg(short, long)]
+  profile: Option<String>,
+  // 后台运行，输出写入 --log-file
+  #[arg(long)]
+  daemon: bool,
+  #[arg(long)]
+  pid_file: Option<String>,
+  #[arg(long, default_value = "dc-bot.log")]
+  log_file: String,
+  // 拉取一次公告并打印尚未播报过的新公告预览，不连接 Discord
+  #[arg(long)]
+  dry_run: bool,
+  // 由 Windows 服务控制管理器启动时使用，服务用 `service install` 注册
+  #[cfg(windows)]
+  #[arg(long, hide = true)]
+  service: bool,
+  #[command(subcommand)]
+  command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+  /// Check GitHub releases for a newer version
+  Update {
+    /// Only report whether an update is available (default)
+    #[arg(long)]
+    check: bool,
+    /// Download the release binary and replace the current executable
+    #[arg(long, conflicts_with = "check")]
+    install: bool,
+  },
+  /// Poll and relay new notices once, then exit (for cron or systemd timers)
+  Once,
+  /// Export or import the seen-notice history for moving the bot between hosts
+  State {
+    #[command(subcommand)]
+    action: StateAction,
+  },
+  /// Send a sample notice of every type and print their previews
+  TestSend {
+    /// Target channel (defaults to discord.channel_id)
+    #[arg(long)]
+    channel: Option<u64>,
+  },
+  /// Register or remove the Windows service that runs the bot unattended
+  #[cfg(windows)]
+  Service {
+    #[command(subcommand)]
+    action: ServiceAction,
+  },
+}
+
+#[cfg(windows)]
+#[derive(Subcommand, Debug)]
+enum ServiceAction {
+  /// Register the service with the current --config and --profile (requires administrator)
+  Install,
+  /// Stop and remove the service
+  Uninstall,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+#[derive(Subcommand, Debug)]
+enum StateAction {
+  /// Write the tracker state to a file
+  Export { path: String },
+  /// Replace the tracker state with a previously exported file
+  Import {
+    path: String,
+    /// Overwrite an existing tracker.json
+    #[arg(long)]
+    force: bool,
+  },
+}
+
+fn main() -> Result<()> {
   let cli = Cli::parse();
 
-  let config = Config::from_file(&cli.config).unwrap_or_else(|e| {
-    log::error(format!(
-      "Failed to read config file '{}': {}",
-      cli.config, e
-    ));
-    std::process::exit(1);
-  });
+  if let Some(Command::Update { install, .. }) = cli.command {
+    let runtime = tokio::runtime::Runtime::new()?;
+    return runtime.block_on(update::run(install));
+  }
+  if let Some(Command::State { action }) = &cli.command {
+    let (storage, cipher) = match Config::from_file(&cli.config, cli.profile.as_deref()) {
+      Ok(config) => {
+        let cipher = match &config.queue.encryption_key_env {
+          Some(var) => Some(Arc::new(
+            crypto::Cipher::from_env(var).context("Failed to load persist encryption key")?,
+          )),
+          None => None,
+        };
+        (config.storage, cipher)
+      }
+      Err(e) => {
+        log::warn(format!(
+          "Failed to read config file '{}': {}. Using {} in the current directory.",
+          cli.config, e, TRACKER_PATH
+        ));
+        (StorageConfig::default(), None)
+      }
+    };
+    let runtime = tokio::runtime::Runtime::new()?;
+    return runtime.block_on(async {
+      match action {
+        StateAction::Export { path } => state::export(path, &storage, cipher).await,
+        StateAction::Import { path, force } => state::import(path, &storage, *force).await,
+      }
+    });
+  }
+
+  #[cfg(windows)]
+  if let Some(Command::Service { action }) = &cli.command {
+    return match action {
+      ServiceAction::Install => service::install(&cli.config, cli.profile.as_deref()),
+      ServiceAction::Uninstall => service::uninstall(),
+    };
+  }
+
+  if cli.daemon {
+    let options = daemon::DaemonOptions {
+      pid_file: cli.pid_file.as_deref().unwrap_or("dc-bot.pid"),
+      log_file: &cli.log_file,
+    };
+    if let Err(e) = daemon::daemonize(&options) {
+      log::error(format!("Failed to start in background: {}", e));
+      std::process::exit(1);
+    }
+  }
+
+  // 在 127.0.0.1:6669 上供 tokio-console 连接，需要以 --cfg tokio_unstable 编译
+  #[cfg(feature = "console")]
+  console_subscriber::init();
+
+  // SCM 以 System32 为工作目录启动服务，相对路径的数据文件改为放在配置文件旁边
+  #[cfg(windows)]
+  if cli.service {
+    let mut cli = cli;
+    let config = std::fs::canonicalize(&cli.config)
+      .with_context(|| format!("Failed to resolve config file '{}'", cli.config))?;
+    if let Some(dir) = config.parent() {
+      std::env::set_current_dir(dir)?;
+    }
+    cli.config = config.to_string_lossy().into_owned();
+    return service::start(move || serve(&cli));
+  }
+
+  serve(&cli)
+}
+
+// 每次运行使用新的 runtime，重新加载时旧 runtime 上的后台任务随之结束；
+// 维护模式在这里创建，重新加载配置后保持原状态
+fn serve(cli: &Cli) -> Result<()> {
+  let maintenance = Arc::new(Maintenance::new());
+  let result = loop {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+      .enable_all()
+      .build()?;
+    match runtime.block_on(run(cli, &maintenance)) {
+      Ok(Exit::Reload) => continue,
+      result => break result.map(|_| ()),
+    }
+  };
+
+  if let Some(pid_file) = cli
+    .pid_file
+    .as_deref()
+    .or(cli.daemon.then_some("dc-bot.pid"))
+  {
+    daemon::remove_pid_file(pid_file);
+  }
+
+  result
+}
+
+// run 结束的原因：SIGHUP 时保存状态后按新配置重新启动
+enum Exit {
+  Stop,
+  Reload,
+}
+
+async fn run(cli: &Cli, maintenance: &Arc<Maintenance>) -> Result<Exit> {
+  // 错误一律返回给 main，由它清理 pid 文件后退出
+  let config = Config::from_file(&cli.config, cli.profile.as_deref())
+    .with_context(|| format!("Failed to read config file '{}'", cli.config))?;
+
+  if let Err(e) = log::init_file(&config.log) {
+    log::error(format!("Failed to open log file: {}", e));
+  }
+  if let Err(e) = audit::init(config.log.audit_file.as_deref()) {
+    log::error(format!("Failed to open audit log: {}", e));
+  }
 
   print_config_info(&config);
+  for warning in config.match_warnings() {
+    log::warn(warning);
+  }
+
+  if cli.dry_run {
+    return preview::dry_run(&config).await.map(|_| Exit::Stop);
+  }
+  if let Some(Command::TestSend { channel }) = cli.command {
+    return preview::test_send(&config, channel)
+      .await
+      .map(|_| Exit::Stop);
+  }
 
+  tasks::reset();
   let config = Arc::new(config);
-  let tracker = match NoticeTracker::load_from_disk("tracker.json").await {
+  let delivery = Arc::new(DeliveryState::new(&config, Arc::clone(maintenance)));
+  let limiter = Arc::new(RequestLimiter::from_config(&config.gzctf));
+  let storage = &config.storage;
+  std::fs::create_dir_all(&storage.dir)
+    .with_context(|| format!("Failed to create storage directory '{}'", storage.dir))?;
+  let tracker_path = storage.path(TRACKER_PATH);
+  let tracker = match NoticeTracker::load_from_disk(&tracker_path).await {
     Ok(t) => Arc::new(RwLock::new(t)),
     Err(e) => {
       log::error(format!("Failed to load tracker: {}", e));
-      Arc::new(RwLock::new(NoticeTracker::with_persist_path(
-        "tracker.json".to_string(),
-      )))
+      Arc::new(RwLock::new(NoticeTracker::with_persist_path(tracker_path)))
     }
   };
 
-  let messenger = Arc::new(DiscordMessenger::new(config.discord.channel_id));
-  let persist_path = "failed_messages.json".to_string();
-  let message_queue = Arc::new(MessageQueue::new(persist_path, messenger));
+  let links_path = storage.path("team_links.json");
+  let links = TeamLinks::load_from_disk(&links_path)
+    .await
+    .unwrap_or_else(|e| {
+      log::error(format!("Failed to load team links: {}", e));
+      TeamLinks::empty(&links_path)
+    });
+  let static_links = config
+    .rewards
+    .as_ref()
+    .map(|rewards| rewards.teams.clone())
+    .unwrap_or_default();
+  let links = Arc::new(links.with_static_links(static_links));
+
+  let subscriptions_path = storage.path("subscriptions.json");
+  let subscriptions = Subscriptions::load_from_disk(&subscriptions_path)
+    .await
+    .unwrap_or_else(|e| {
+      log::error(format!("Failed to load subscriptions: {}", e));
+      Subscriptions::empty(&subscriptions_path)
+    });
+
+  let alerts = Arc::new(AlertManager::new(
+    config.discord.admin_channel_id,
+    &config.alerts,
+    Arc::clone(&delivery),
+  ));
+  let messenger = Arc::new(DiscordMessenger::new(
+    config.discord.channel_id,
+    Arc::clone(&delivery),
+  ));
+  let persist_path = storage.path("failed_messages.json");
+  let cipher = match &config.queue.encryption_key_env {
+    Some(var) => Some(Arc::new(
+      crypto::Cipher::from_env(var).context("Failed to load persist encryption key")?,
+    )),
+    None => None,
+  };
+  let journal = match Journal::open(&storage.path(JOURNAL_PATH), cipher.clone()) {
+    Ok(journal) => Some(Arc::new(journal)),
+    Err(e) => {
+      log::error(format!(
+        "Failed to open journal, crash recovery disabled: {}",
+        e
+      ));
+      None
+    }
+  };
+  let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+  let message_queue = Arc::new(
+    MessageQueue::new(persist_path, config.queue.snapshot_interval, messenger)
+      .with_clock(Arc::clone(&clock))
+      .with_cipher(cipher.clone())
+      .with_journal(journal.clone()),
+  );
 
   if let Err(e) = message_queue.load_from_disk().await {
     log::error(format!("Failed to load persisted messages: {}", e));
   }
+  if let Some(journal) = &journal {
+    recover(journal, &tracker, &message_queue).await;
+  }
+
+  let recent = Arc::new(RecentNotices::new(config.commands.recent_capacity));
+  let once = matches!(cli.command, Some(Command::Once)).then(CancellationToken::new);
+  if once.is_none() {
+    http::start(
+      Arc::clone(&config),
+      Arc::clone(&message_queue),
+      Arc::clone(&recent),
+      Arc::clone(&alerts),
+    );
+    maintenance.watch_signal();
+  }
 
-  let intents = GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT;
+  let intents = GatewayIntents::GUILDS
+    | GatewayIntents::GUILD_MESSAGES
+    | GatewayIntents::MESSAGE_CONTENT
+    | GatewayIntents::GUILD_MESSAGE_REACTIONS;
 
+  let shutdown = Arc::new(ShutdownBarrier::new());
   let handler = BotHandler {
     config: Arc::clone(&config),
     tracker: Arc::clone(&tracker),
     message_queue: Arc::clone(&message_queue),
+    alerts: Arc::clone(&alerts),
+    links: Arc::clone(&links),
+    recent,
+    challenges: Arc::new(ChallengeStore::new(config.gzctf.challenge_cache_ttl)),
+    scheduler: Arc::new(Scheduler::new(Arc::clone(&clock))),
+    once: once.clone(),
+    presence_started: AtomicBool::new(false),
+    polling_started: AtomicBool::new(false),
+    started_at: Instant::now(),
+    cooldowns: Cooldowns::new(&config.commands),
+    shutdown: Arc::clone(&shutdown),
+    subscriptions: Arc::new(subscriptions),
+    clock,
+    pages: Paginator::new(),
+    onboarding: Onboarding::new(),
+    cipher,
+    delivery,
+    limiter,
   };
+  // 常驻模式下这个 token 永远不会被取消
+  let once_done = once.unwrap_or_default();
 
-  let mut client = timeout(
-    Duration::from_secs(10),
-    Client::builder(&config.discord.token, intents).event_handler(handler),
-  )
-  .await
-  .unwrap_or_else(|_| {
-    log::error("Timed out creating Discord client");
-    std::process::exit(1);
-  })
-  .unwrap_or_else(|e| {
-    log::error(format!("Failed to create Discord client: {}", e));
-    std::process::exit(1);
-  });
+  let client_builder = Client::builder(&config.discord.token, intents).event_handler(handler);
+  let mut client = match timeout(Duration::from_secs(10), client_builder).await {
+    Ok(Ok(client)) => client,
+    Ok(Err(e)) => return Err(e).context("Failed to create Discord client"),
+    Err(_) => bail!("Timed out creating Discord client"),
+  };
+
+  client
+    .data
+    .write()
+    .await
+    .insert::<commands::ShardManagerKey>(Arc::clone(&client.shard_manager));
 
   log::success("Starting Discord bot...\n");
 
-  let client_task = tokio::spawn(async move {
+  let shard_manager = Arc::clone(&client.shard_manager);
+  let mut client_task = tokio::spawn(async move {
     if let Err(why) = client.start().await {
       log::error(format!("Client error: {:?}", why));
     }
   });
 
-  tokio::select! {
-    _ = tokio::signal::ctrl_c() => {
-      log::info("\nReceived Ctrl+C, shutting down...");
+  let exit = loop {
+    tokio::select! {
+      _ = tokio::signal::ctrl_c() => {
+        log::info("\nReceived Ctrl+C, shutting down...");
+        break Exit::Stop;
+      }
+      _ = signals::terminate() => {
+        log::info("Received SIGTERM or a service stop request, shutting down...");
+        break Exit::Stop;
+      }
+      // 新配置无法解析时保持当前配置继续运行
+      _ = signals::hangup() => match Config::from_file(&cli.config, cli.profile.as_deref()) {
+        Ok(_) => {
+          log::info("Received SIGHUP, reloading configuration...");
+          audit::record("config_reload", "signal", json!({ "path": cli.config }));
+          break Exit::Reload;
+        }
+        Err(e) => log::error(format!(
+          "Received SIGHUP but '{}' is invalid, keeping the current configuration: {}",
+          cli.config, e
+        )),
+      },
+      _ = &mut client_task => {
+        log::info("Client task finished.");
+        break Exit::Stop;
+      }
+      _ = once_done.cancelled() => {
+        log::info("Single pass finished, exiting...");
+        break Exit::Stop;
+      }
     }
-    _ = client_task => {
-      log::info("Client task finished.");
+  };
+
+  // 两阶段退出：先等待发送和重试循环结束，超时后中止它们并强制保存剩余状态；
+  // 写盘同样有时限，保存失败或超时时保留日志，下次启动重放
+  let deadline = Instant::now() + Duration::from_secs(config.queue.shutdown_timeout);
+  let drain_deadline = deadline.checked_sub(PERSIST_RESERVE).unwrap_or(deadline);
+  let left = |until: Instant| until.saturating_duration_since(Instant::now());
+
+  if !shutdown.close(left(drain_deadline)).await {
+    log::warn("Timed out waiting for in-flight notices; they will be picked up on the next start");
+  }
+
+  let mut saved = true;
+  match timeout(left(deadline), message_queue.shutdown(left(drain_deadline))).await {
+    Ok(Ok(())) => {}
+    Ok(Err(e)) => {
+      log::error(format!("Failed to save messages on shutdown: {}", e));
+      saved = false;
+    }
+    Err(_) => {
+      log::error("Timed out saving messages on shutdown");
+      saved = false;
+    }
+  }
+
+  let save_tracker = async { tracker.read().await.save_to_disk().await };
+  match timeout(left(deadline), save_tracker).await {
+    Ok(Ok(())) => {}
+    Ok(Err(e)) => {
+      log::error(format!("Failed to save tracker on shutdown: {}", e));
+      saved = false;
+    }
+    Err(_) => {
+      log::error("Timed out saving tracker on shutdown");
+      saved = false;
     }
   }
 
-  if let Err(e) = message_queue.shutdown().await {
-    log::error(format!("Failed to save messages on shutdown: {}", e));
+  // 状态都已保存，日志不再需要；否则留给下次启动重放
+  if saved
+    && let Some(journal) = &journal
+    && let Err(e) = journal.truncate()
+  {
+    log::error(format!("Failed to clear journal on shutdown: {}", e));
   }
 
-  if let Err(e) = tracker.read().await.save_to_disk().await {
-    log::error(format!("Failed to save tracker on shutdown: {}", e));
+  if matches!(exit, Exit::Reload) {
+    shard_manager.shutdown_all().await;
+  }
+  Ok(exit)
+}
+
+// 重放上次运行未清空的日志，保存为新的检查点后清空，之后的变化再写入日志
+async fn recover(journal: &Arc<Journal>, tracker: &RwLock<NoticeTracker>, queue: &MessageQueue) {
+  let mut tracker = tracker.write().await;
+  match journal.replay(&mut tracker, queue).await {
+    Ok(0) => {}
+    Ok(applied) => log::success(format!(
+      "Replayed {} journal entries from the previous run.",
+      applied
+    )),
+    Err(e) => log::error(format!("Failed to replay journal: {}", e)),
   }
 
-  Ok(())
+  let checkpoint = match tracker.save_to_disk().await {
+    Ok(()) => queue.checkpoint().await,
+    Err(e) => Err(e),
+  };
+  // 检查点失败时保留日志继续追加，下次启动时整体重放
+  if let Err(e) = checkpoint.and_then(|_| journal.truncate()) {
+    log::error(format!(
+      "Failed to checkpoint state, journal left in place: {}",
+      e
+    ));
+  }
+  tracker.set_journal(Arc::clone(journal));
 }
 
 fn print_config_info(config: &Config) {
   log::info("Configuration loaded:");
   log::info(format!("   GZCTF URL: {}", config.gzctf.url));
   log::info(format!("   Channel ID: {}", config.discord.channel_id));
+  config.guilds.iter().for_each(|guild| {
+    log::info(format!(
+      "   Guild {} -> channel {}",
+      guild.guild_id, guild.channel_id
+    ));
+  });
   log::info(format!("   Poll interval: {}s", config.gzctf.poll_interval));
+  log::info(format!("   Storage directory: {}", config.storage.dir));
 
   let matches = config.get_matches();
   log::info(format!("   Matches to monitor: {}", matches.len()));
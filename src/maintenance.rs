@@ -0,0 +1,49 @@
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::audit;
+use crate::log;
+use crate::signals;
+
+// 维护模式：暂停所有发往 Discord 的消息，轮询照常进行，公告暂存到结束后以汇总形式发出。
+// 由 main 创建并传给每次 run，重新加载配置后保持原状态
+#[derive(Default)]
+pub struct Maintenance {
+  active: AtomicBool,
+}
+
+impl Maintenance {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn is_active(&self) -> bool {
+    self.active.load(Ordering::SeqCst)
+  }
+
+  // 返回状态是否发生变化
+  pub fn set(&self, active: bool, actor: &str) -> bool {
+    let changed = self.active.swap(active, Ordering::SeqCst) != active;
+    if changed {
+      audit::record("maintenance", actor, json!({ "active": active }));
+    }
+    if changed && active {
+      log::warn("Maintenance mode on: outgoing Discord messages are paused");
+    } else if changed {
+      log::info("Maintenance mode off: releasing held notices");
+    }
+    changed
+  }
+
+  // 收到 SIGUSR1 时切换维护模式，便于在服务器上直接操作（SIGHUP 用于重新加载配置）
+  pub fn watch_signal(self: &Arc<Self>) {
+    let maintenance = Arc::clone(self);
+    tokio::spawn(async move {
+      loop {
+        signals::user_defined().await;
+        maintenance.set(!maintenance.is_active(), "signal");
+      }
+    });
+  }
+}
@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::compat;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(from = "RawNotice")]
 pub struct Notice {
   pub id: u64,
   #[serde(rename = "type")]
@@ -9,6 +14,83 @@ pub struct Notice {
   pub time: u64,
 }
 
+// 兼容各版本的公告格式：字段大小写不同、旧版本用 content 而不是 values、时间为字符串
+#[derive(Deserialize)]
+struct RawNotice {
+  #[serde(alias = "Id", deserialize_with = "compat::id")]
+  id: u64,
+  #[serde(rename = "type", alias = "Type", alias = "noticeType", default)]
+  notice_type: String,
+  #[serde(alias = "Values", default, deserialize_with = "compat::strings")]
+  values: Vec<String>,
+  #[serde(alias = "Content", default)]
+  content: Option<String>,
+  #[serde(
+    alias = "Time",
+    alias = "publishTimeUtc",
+    default,
+    deserialize_with = "compat::timestamp"
+  )]
+  time: u64,
+}
+
+impl From<RawNotice> for Notice {
+  fn from(raw: RawNotice) -> Self {
+    let values = match raw.content {
+      Some(content) if raw.values.is_empty() => vec![content],
+      _ => raw.values,
+    };
+    Self {
+      id: raw.id,
+      notice_type: raw.notice_type,
+      values,
+      time: raw.time,
+    }
+  }
+}
+
+impl Notice {
+  // ID 可能在删除重建后复用，加上内容哈希区分真正的新公告
+  pub fn fingerprint(&self) -> String {
+    let mut hasher = Sha256::new();
+    for value in &self.values {
+      hasher.update(value.as_bytes());
+      hasher.update([0u8]);
+    }
+    let digest = hasher.finalize();
+    let hash: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}:{}", self.id, hash)
+  }
+}
+
+// 按类型分好的公告，一次遍历完成，只借用不克隆；类型不认识的公告直接忽略
+pub struct NoticesByType<'a>([Vec<&'a Notice>; NOTICE_TYPES.len()]);
+
+impl<'a> NoticesByType<'a> {
+  pub fn new(notices: &'a [Notice]) -> Self {
+    let mut buckets: [Vec<&Notice>; NOTICE_TYPES.len()] = Default::default();
+    for notice in notices {
+      if let Some(notice_type) = NoticeType::from_str(&notice.notice_type) {
+        buckets[notice_type.priority() as usize].push(notice);
+      }
+    }
+    Self(buckets)
+  }
+
+  pub fn get(&self, notice_type: &NoticeType) -> &[&'a Notice] {
+    &self.0[notice_type.priority() as usize]
+  }
+}
+
+const NOTICE_TYPES: [NoticeType; 6] = [
+  NoticeType::Normal,
+  NoticeType::NewChallenge,
+  NoticeType::NewHint,
+  NoticeType::FirstBlood,
+  NoticeType::SecondBlood,
+  NoticeType::ThirdBlood,
+];
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum NoticeType {
   Normal,
@@ -20,15 +102,23 @@ pub enum NoticeType {
 }
 
 impl NoticeType {
+  // 不区分大小写，未知类型返回 None 并由调用方忽略
   pub fn from_str(s: &str) -> Option<Self> {
-    match s {
-      "Normal" => Some(NoticeType::Normal),
-      "NewChallenge" => Some(NoticeType::NewChallenge),
-      "NewHint" => Some(NoticeType::NewHint),
-      "FirstBlood" => Some(NoticeType::FirstBlood),
-      "SecondBlood" => Some(NoticeType::SecondBlood),
-      "ThirdBlood" => Some(NoticeType::ThirdBlood),
-      _ => None,
+    let s = s.trim();
+    NOTICE_TYPES
+      .iter()
+      .find(|t| t.name().eq_ignore_ascii_case(s))
+      .cloned()
+  }
+
+  fn name(&self) -> &'static str {
+    match self {
+      NoticeType::Normal => "Normal",
+      NoticeType::NewChallenge => "NewChallenge",
+      NoticeType::NewHint => "NewHint",
+      NoticeType::FirstBlood => "FirstBlood",
+      NoticeType::SecondBlood => "SecondBlood",
+      NoticeType::ThirdBlood => "ThirdBlood",
     }
   }
 
@@ -43,14 +133,115 @@ impl NoticeType {
     }
   }
 
+  // 数值越小越优先，与 all() 的顺序一致，同时是 NoticesByType 的下标
+  pub fn priority(&self) -> u8 {
+    match self {
+      NoticeType::Normal => 0,
+      NoticeType::NewChallenge => 1,
+      NoticeType::NewHint => 2,
+      NoticeType::FirstBlood => 3,
+      NoticeType::SecondBlood => 4,
+      NoticeType::ThirdBlood => 5,
+    }
+  }
+
   pub fn all() -> Vec<NoticeType> {
-    vec![
-      NoticeType::Normal,
-      NoticeType::NewChallenge,
-      NoticeType::NewHint,
-      NoticeType::FirstBlood,
-      NoticeType::SecondBlood,
-      NoticeType::ThirdBlood,
-    ]
+    NOTICE_TYPES.to_vec()
+  }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChallengeInfo {
+  #[serde(default)]
+  pub id: u32,
+  pub title: String,
+  #[serde(default)]
+  pub category: String,
+  #[serde(default)]
+  pub score: u32,
+  #[serde(default)]
+  pub solved: u32,
+  // 题目类型，如 StaticAttachment、DynamicContainer，旧版本不返回
+  #[serde(rename = "type", alias = "Type", default)]
+  pub challenge_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScoreboardItem {
+  #[serde(default)]
+  pub name: String,
+  #[serde(default)]
+  pub score: u64,
+  #[serde(default)]
+  pub rank: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Scoreboard {
+  // 已报名参赛的队伍
+  #[serde(default)]
+  pub items: Vec<ScoreboardItem>,
+  // 分类 -> 题目列表
+  #[serde(default)]
+  pub challenges: HashMap<String, Vec<ChallengeInfo>>,
+}
+
+impl Scoreboard {
+  pub fn find_challenge(&self, title: &str) -> Option<&ChallengeInfo> {
+    self
+      .challenges
+      .values()
+      .flatten()
+      .find(|c| c.title == title)
+  }
+
+  // 旧版本不返回 rank 时按列表顺序计算
+  pub fn rank_of(&self, team: &str) -> Option<u32> {
+    let (index, item) = self
+      .items
+      .iter()
+      .enumerate()
+      .find(|(_, t)| t.name == team)?;
+    Some(if item.rank > 0 {
+      item.rank
+    } else {
+      index as u32 + 1
+    })
   }
 }
+
+// /api/game/{id}/challenges/{challengeId} 返回的题目详情，需要已参赛队伍成员的登录状态
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChallengeDetail {
+  #[serde(alias = "Hints", default)]
+  pub hints: Vec<String>,
+}
+
+// /api/game/{id}/events 返回的比赛事件（容器启停、提交等），需要 Monitor 权限
+#[derive(Debug, Clone, Deserialize)]
+pub struct GameEvent {
+  #[serde(rename = "type", alias = "Type", default)]
+  pub event_type: String,
+  #[serde(alias = "Values", default, deserialize_with = "compat::strings")]
+  pub values: Vec<String>,
+  #[serde(alias = "Time", default, deserialize_with = "compat::timestamp")]
+  pub time: u64,
+  #[serde(alias = "User", default)]
+  pub user: Option<String>,
+  #[serde(alias = "Team", default)]
+  pub team: Option<String>,
+}
+
+// /api/game/{id} 返回的比赛信息，时间为毫秒时间戳
+#[derive(Debug, Clone, Deserialize)]
+pub struct GameInfo {
+  #[serde(alias = "Title", default)]
+  pub title: String,
+  #[serde(alias = "Start", deserialize_with = "compat::timestamp")]
+  pub start: u64,
+  #[serde(alias = "End", deserialize_with = "compat::timestamp")]
+  pub end: u64,
+  #[serde(alias = "Poster", default)]
+  pub poster: Option<String>,
+}
@@ -0,0 +1,168 @@
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+use serenity::model::channel::{ChannelType, GuildChannel};
+use serenity::model::colour::Colour;
+use serenity::model::guild::audit_log::{Action, MemberAction};
+use serenity::model::guild::{Guild, UnavailableGuild};
+use serenity::prelude::Context;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::discord::{DeliveryState, DiscordMessenger};
+use crate::gzctf::trunc_text;
+use crate::log;
+
+// 配置片段中最多列出的文字频道数
+const MAX_LISTED_CHANNELS: usize = 20;
+
+// 区分新加入的服务器和启动、重连时网关补发的已有服务器：ready 中列出的都是已有的
+pub struct Onboarding {
+  known: Mutex<HashSet<u64>>,
+}
+
+impl Onboarding {
+  pub fn new() -> Self {
+    Self {
+      known: Mutex::new(HashSet::new()),
+    }
+  }
+
+  pub fn remember(&self, guilds: &[UnavailableGuild]) {
+    let mut known = self.known.lock().unwrap();
+    known.extend(guilds.iter().map(|g| g.id.get()));
+  }
+
+  // 第一次见到该服务器时返回 true，之后同一服务器的 guild_create 不再重复欢迎
+  pub fn is_new(&self, guild_id: u64) -> bool {
+    self.known.lock().unwrap().insert(guild_id)
+  }
+}
+
+// 私信邀请者一段上手说明和填好 ID 的配置片段；查不到邀请者（缺少查看审计日志权限）
+// 或私信失败时发到机器人自己的管理频道。片段列出了服务器的全部文字频道（包括仅管理员可见的），
+// 不能发到新服务器的公开频道
+pub async fn welcome(
+  ctx: &Context,
+  guild: &Guild,
+  admin_channel_id: Option<u64>,
+  delivery: &Arc<DeliveryState>,
+) {
+  log::info(format!("Joined guild {} ({})", guild.name, guild.id));
+  if delivery.maintenance().is_active() {
+    log::info(format!(
+      "Maintenance: not sending onboarding message for guild {}",
+      guild.id
+    ));
+    return;
+  }
+  let embed = welcome_embed(guild);
+
+  if let Some(inviter) = inviter(ctx, guild).await {
+    match delivery.send_dm(ctx, inviter, embed.clone()).await {
+      Ok(_) => {
+        log::success(format!(
+          "Sent onboarding message for guild {} to inviter {}",
+          guild.id, inviter
+        ));
+        return;
+      }
+      Err(e) => log::error(format!(
+        "Failed to DM onboarding message to {}: {}",
+        inviter, e
+      )),
+    }
+  }
+
+  let Some(admin_channel_id) = admin_channel_id else {
+    log::warn(format!(
+      "No discord.admin_channel_id configured, onboarding message for guild {} was not delivered",
+      guild.id
+    ));
+    return;
+  };
+  let embed = embed.footer(CreateEmbedFooter::new(format!(
+    "{} ({})",
+    guild.name, guild.id
+  )));
+  if let Err(e) = DiscordMessenger::new(admin_channel_id, Arc::clone(delivery))
+    .send_embed_to(ctx, admin_channel_id, None, embed)
+    .await
+  {
+    log::error(format!(
+      "Failed to post onboarding message for guild {} in admin channel: {}",
+      guild.id, e
+    ));
+  }
+}
+
+// 审计日志中最近一条添加机器人的记录
+async fn inviter(ctx: &Context, guild: &Guild) -> Option<u64> {
+  let logs = guild
+    .id
+    .audit_logs(
+      &ctx.http,
+      Some(Action::Member(MemberAction::BotAdd)),
+      None,
+      None,
+      Some(1),
+    )
+    .await;
+  match logs {
+    Ok(logs) => logs.entries.first().map(|entry| entry.user_id.get()),
+    Err(e) => {
+      log::info(format!(
+        "Cannot read audit log of guild {} to find the inviter: {}",
+        guild.id, e
+      ));
+      None
+    }
+  }
+}
+
+fn welcome_embed(guild: &Guild) -> CreateEmbed {
+  let snippet = config_snippet(guild);
+  CreateEmbed::new()
+    .title("**感谢邀请 GZCTF 播报机器人**")
+    .color(Colour::from_rgb(59, 130, 246))
+    .description(trunc_text(
+      &format!(
+        "把下面的片段加入配置文件，按需修改播报频道和比赛，重启后生效。\n\
+         配置完成后可在服务器中运行 `/setup check` 检查权限。\n```toml\n{}```",
+        snippet
+      ),
+      4000,
+    ))
+}
+
+// 播报频道默认选系统频道，没有时选排在最前面的文字频道
+fn config_snippet(guild: &Guild) -> String {
+  let mut channels: Vec<&GuildChannel> = guild
+    .channels
+    .values()
+    .filter(|c| c.kind == ChannelType::Text || c.kind == ChannelType::News)
+    .collect();
+  channels.sort_by_key(|c| (c.position, c.id));
+
+  let broadcast = guild
+    .system_channel_id
+    .and_then(|id| channels.iter().find(|c| c.id == id))
+    .or(channels.first())
+    .copied();
+
+  let mut snippet = format!("[[guilds]]\nguild_id = {}\n", guild.id);
+  match broadcast {
+    Some(channel) => snippet.push_str(&format!(
+      "channel_id = {}  # #{}\n",
+      channel.id, channel.name
+    )),
+    None => snippet.push_str("channel_id = 0  # 填入播报频道 ID\n"),
+  }
+  snippet.push_str("matches = []  # 留空表示全部比赛\n");
+
+  if !channels.is_empty() {
+    snippet.push_str("\n# 本服务器的文字频道:\n");
+    for channel in channels.iter().take(MAX_LISTED_CHANNELS) {
+      snippet.push_str(&format!("# {}  #{}\n", channel.id, channel.name));
+    }
+  }
+  snippet
+}
@@ -0,0 +1,113 @@
+use anyhow::{Result, anyhow};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::AUTHORIZATION;
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::OneBotConfig;
+use crate::models::NoticeType;
+use crate::relays::{PlainNotice, Relay};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// OneBot v11（go-cqhttp 等）实现，按 endpoint 的协议选择 HTTP API 或正向 WebSocket
+pub struct OneBotRelay {
+  config: OneBotConfig,
+  client: reqwest::Client,
+}
+
+impl OneBotRelay {
+  pub fn new(config: OneBotConfig) -> Result<Self> {
+    let client = reqwest::Client::builder()
+      .timeout(REQUEST_TIMEOUT)
+      .build()?;
+    Ok(Self { config, client })
+  }
+
+  fn is_websocket(&self) -> bool {
+    self.config.endpoint.starts_with("ws://") || self.config.endpoint.starts_with("wss://")
+  }
+
+  async fn send_http(&self, params: &Value) -> Result<Value> {
+    let url = format!(
+      "{}/send_group_msg",
+      self.config.endpoint.trim_end_matches('/')
+    );
+    let mut request = self.client.post(url).json(params);
+    if let Some(token) = &self.config.access_token {
+      request = request.bearer_auth(token);
+    }
+    Ok(request.send().await?.error_for_status()?.json().await?)
+  }
+
+  // 每次发送建立一条短连接，等到 echo 对应的响应后关闭
+  async fn send_websocket(&self, params: &Value, echo: &str) -> Result<Value> {
+    let mut request = self.config.endpoint.as_str().into_client_request()?;
+    if let Some(token) = &self.config.access_token {
+      request
+        .headers_mut()
+        .insert(AUTHORIZATION, format!("Bearer {}", token).parse()?);
+    }
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await?;
+
+    let action = json!({ "action": "send_group_msg", "params": params, "echo": echo });
+    socket.send(Message::Text(action.to_string())).await?;
+
+    // 连接上可能先推送生命周期等事件，跳过直到找到本次请求的响应
+    let response = loop {
+      let message = socket
+        .next()
+        .await
+        .ok_or_else(|| anyhow!("connection closed before response"))??;
+      let Message::Text(text) = message else {
+        continue;
+      };
+      let value: Value = serde_json::from_str(&text)?;
+      if value.get("echo").and_then(Value::as_str) == Some(echo) {
+        break value;
+      }
+    };
+    let _ = socket.close(None).await;
+    Ok(response)
+  }
+}
+
+#[async_trait]
+impl Relay for OneBotRelay {
+  fn name(&self) -> &str {
+    "onebot"
+  }
+
+  fn wants(&self, notice_type: &NoticeType) -> bool {
+    self.config.types.is_empty() || self.config.types.contains(notice_type)
+  }
+
+  async fn send(&self, notice: &PlainNotice) -> Result<()> {
+    let text = format!("{}\n{}", notice.title, notice.body);
+
+    for group_id in &self.config.group_ids {
+      let params = json!({ "group_id": group_id, "message": text, "auto_escape": true });
+      let response = if self.is_websocket() {
+        let echo = format!("dc-bot-{}-{}", group_id, SystemClock.now_millis());
+        self.send_websocket(&params, &echo).await?
+      } else {
+        self.send_http(&params).await?
+      };
+
+      let retcode = response.get("retcode").and_then(Value::as_i64).unwrap_or(0);
+      if retcode != 0 {
+        return Err(anyhow!(
+          "group {} rejected the message (retcode {}): {}",
+          group_id,
+          retcode,
+          response
+        ));
+      }
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,128 @@
+use serenity::builder::{
+  CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponse,
+  CreateInteractionResponseMessage, EditInteractionResponse,
+};
+use serenity::model::application::{ButtonStyle, ComponentInteraction};
+use serenity::prelude::Context;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::log;
+
+// 翻页按钮的 custom_id 前缀，后接目标页码
+const PAGE_PREFIX: &str = "page:";
+// 超过这个时间没人翻页，页面内容从内存中清除，按钮随之失效
+const PAGE_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Clone, Default)]
+pub struct Page {
+  pub content: Option<String>,
+  pub embeds: Vec<CreateEmbed>,
+}
+
+struct Pages {
+  pages: Vec<Page>,
+  touched: Instant,
+}
+
+// 长输出的分页状态：按命令回复的消息 ID 保存全部页面，翻页时整页替换
+pub struct Paginator {
+  messages: Mutex<HashMap<u64, Pages>>,
+}
+
+impl Paginator {
+  pub fn new() -> Self {
+    Self {
+      messages: Mutex::new(HashMap::new()),
+    }
+  }
+
+  // 第一页附上翻页按钮；只有一页时不需要保存状态
+  pub fn first_page(pages: &[Page]) -> EditInteractionResponse {
+    let page = pages.first().cloned().unwrap_or_default();
+    let mut message = EditInteractionResponse::new().embeds(page.embeds);
+    if let Some(content) = page.content {
+      message = message.content(content);
+    }
+    if pages.len() > 1 {
+      message = message.components(vec![buttons(0, pages.len())]);
+    }
+    message
+  }
+
+  pub fn remember(&self, message_id: u64, pages: Vec<Page>) {
+    if pages.len() <= 1 {
+      return;
+    }
+    let now = Instant::now();
+    let mut messages = self.messages.lock().unwrap();
+    messages.retain(|_, state| now.duration_since(state.touched) < PAGE_TTL);
+    messages.insert(
+      message_id,
+      Pages {
+        pages,
+        touched: now,
+      },
+    );
+  }
+
+  // 不是翻页按钮时忽略
+  pub async fn turn(&self, ctx: &Context, component: &ComponentInteraction) {
+    let Some(index) = component
+      .data
+      .custom_id
+      .strip_prefix(PAGE_PREFIX)
+      .and_then(|index| index.parse::<usize>().ok())
+    else {
+      return;
+    };
+
+    let page = {
+      let now = Instant::now();
+      let mut messages = self.messages.lock().unwrap();
+      match messages.get_mut(&component.message.id.get()) {
+        Some(state) if now.duration_since(state.touched) < PAGE_TTL => {
+          state.touched = now;
+          let index = index.min(state.pages.len() - 1);
+          Some((state.pages[index].clone(), index, state.pages.len()))
+        }
+        _ => None,
+      }
+    };
+
+    let message = match page {
+      Some((page, index, total)) => CreateInteractionResponseMessage::new()
+        .content(page.content.unwrap_or_default())
+        .embeds(page.embeds)
+        .components(vec![buttons(index, total)]),
+      None => CreateInteractionResponseMessage::new()
+        .content("分页已过期，请重新执行命令")
+        .components(Vec::new()),
+    };
+    if let Err(e) = component
+      .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(message))
+      .await
+    {
+      log::error(format!("Failed to turn page: {}", e));
+    }
+  }
+}
+
+fn buttons(index: usize, total: usize) -> CreateActionRow {
+  CreateActionRow::Buttons(vec![
+    CreateButton::new(format!("{}{}", PAGE_PREFIX, index.saturating_sub(1)))
+      .label("上一页")
+      .style(ButtonStyle::Secondary)
+      .disabled(index == 0),
+    // 页码只用于显示，custom_id 不会被点击
+    CreateButton::new("page-indicator")
+      .label(format!("{}/{}", index + 1, total))
+      .style(ButtonStyle::Secondary)
+      .disabled(true),
+    CreateButton::new(format!("{}{}", PAGE_PREFIX, index + 1))
+      .label("下一页")
+      .style(ButtonStyle::Secondary)
+      .disabled(index + 1 >= total),
+  ])
+}
@@ -0,0 +1,251 @@
+use anyhow::{Result, anyhow};
+use serenity::model::channel::Channel;
+use serenity::model::guild::{Member, PartialGuild};
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::prelude::Context;
+
+use crate::alerts::AlertManager;
+use crate::config::Config;
+use crate::log;
+
+// 播报频道需要的最小权限
+pub fn broadcast_permissions() -> Permissions {
+  Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES | Permissions::EMBED_LINKS
+}
+
+// 管理频道还需要添加 ✅ 反应和回复告警消息
+pub fn admin_permissions() -> Permissions {
+  broadcast_permissions() | Permissions::ADD_REACTIONS | Permissions::READ_MESSAGE_HISTORY
+}
+
+// 讨论子区从播报消息上创建，之后的公告发进子区
+pub fn thread_permissions() -> Permissions {
+  Permissions::CREATE_PUBLIC_THREADS | Permissions::SEND_MESSAGES_IN_THREADS
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+  Channel(u64),
+  // 身份组这类服务器级别的权限，不受频道覆盖影响
+  Guild(u64),
+}
+
+// 某项已启用的功能对频道或服务器的权限要求；feature 用于日志，label 用于 /setup 的回复
+#[derive(Debug, Clone)]
+pub struct Requirement {
+  pub feature: &'static str,
+  pub label: &'static str,
+  pub target: Target,
+  pub permissions: Permissions,
+}
+
+impl Requirement {
+  fn channel(
+    feature: &'static str,
+    label: &'static str,
+    channel_id: u64,
+    permissions: Permissions,
+  ) -> Self {
+    Self {
+      feature,
+      label,
+      target: Target::Channel(channel_id),
+      permissions,
+    }
+  }
+}
+
+// 按配置列出每项功能需要的权限，未启用的功能不检查
+pub fn requirements(config: &Config) -> Vec<Requirement> {
+  let broadcast = broadcast_permissions();
+  let mut required = vec![Requirement::channel(
+    "broadcast",
+    "播报频道",
+    config.discord.channel_id,
+    broadcast,
+  )];
+  for guild in &config.guilds {
+    required.push(Requirement::channel(
+      "guild",
+      "服务器播报频道",
+      guild.channel_id,
+      broadcast,
+    ));
+  }
+  if let Some(admin_channel_id) = config.discord.admin_channel_id {
+    required.push(Requirement::channel(
+      "admin",
+      "管理频道",
+      admin_channel_id,
+      admin_permissions(),
+    ));
+  }
+  if let Some(audit_channel_id) = config.discord.audit_channel_id {
+    required.push(Requirement::channel(
+      "audit",
+      "审计频道",
+      audit_channel_id,
+      broadcast,
+    ));
+  }
+  for destination in &config.destinations {
+    required.push(Requirement::channel(
+      "destination",
+      "额外转发频道",
+      destination.channel_id,
+      broadcast,
+    ));
+  }
+
+  let optional_channels = [
+    (
+      "countdown",
+      "倒计时频道",
+      config.countdown.as_ref().and_then(|c| c.channel_id),
+    ),
+    (
+      "registration",
+      "报名提醒频道",
+      config.registration.as_ref().and_then(|c| c.channel_id),
+    ),
+    (
+      "spotlight",
+      "零解播报频道",
+      config.spotlight.as_ref().and_then(|c| c.channel_id),
+    ),
+    (
+      "visibility",
+      "比赛变更频道",
+      config.visibility.as_ref().and_then(|c| c.channel_id),
+    ),
+    (
+      "containers",
+      "容器事件频道",
+      config.containers.as_ref().map(|c| c.channel_id),
+    ),
+  ];
+  for (feature, label, channel_id) in optional_channels {
+    if let Some(channel_id) = channel_id {
+      required.push(Requirement::channel(feature, label, channel_id, broadcast));
+    }
+  }
+
+  if let Some(discussion) = &config.discussion {
+    for channel_id in &discussion.channel_ids {
+      required.push(Requirement::channel(
+        "discussion",
+        "讨论子区",
+        *channel_id,
+        thread_permissions(),
+      ));
+    }
+  }
+  if let Some(rewards) = &config.rewards {
+    required.push(Requirement {
+      feature: "rewards",
+      label: "一血身份组",
+      target: Target::Guild(rewards.guild_id),
+      permissions: Permissions::MANAGE_ROLES,
+    });
+  }
+  required
+}
+
+// 频道所在的服务器以及机器人在频道中的实际权限（含频道覆盖）
+pub async fn channel_permissions(
+  ctx: &Context,
+  channel_id: u64,
+  bot_user_id: UserId,
+) -> Result<(GuildId, Permissions)> {
+  let channel = match ctx.http.get_channel(ChannelId::new(channel_id)).await? {
+    Channel::Guild(channel) => channel,
+    _ => return Err(anyhow!("channel {} is not a guild channel", channel_id)),
+  };
+
+  let guild = ctx.http.get_guild(channel.guild_id).await?;
+  let member = ctx.http.get_member(channel.guild_id, bot_user_id).await?;
+
+  Ok((
+    channel.guild_id,
+    guild.user_permissions_in(&channel, &member),
+  ))
+}
+
+// 服务器级别的权限：@everyone 加上成员所有身份组，管理员拥有全部权限
+fn guild_permissions(guild: &PartialGuild, member: &Member) -> Permissions {
+  if guild.owner_id == member.user.id {
+    return Permissions::all();
+  }
+  let everyone = guild
+    .roles
+    .get(&RoleId::new(guild.id.get()))
+    .map(|role| role.permissions)
+    .unwrap_or_default();
+  let permissions = member
+    .roles
+    .iter()
+    .filter_map(|id| guild.roles.get(id))
+    .fold(everyone, |permissions, role| permissions | role.permissions);
+  if permissions.administrator() {
+    Permissions::all()
+  } else {
+    permissions
+  }
+}
+
+// 检查一项要求，返回目标所在的服务器和缺少的权限
+pub async fn check(
+  ctx: &Context,
+  requirement: &Requirement,
+  bot_user_id: UserId,
+) -> Result<(GuildId, Permissions)> {
+  match requirement.target {
+    Target::Channel(channel_id) => {
+      let (guild_id, granted) = channel_permissions(ctx, channel_id, bot_user_id).await?;
+      Ok((guild_id, requirement.permissions - granted))
+    }
+    Target::Guild(guild_id) => {
+      let guild_id = GuildId::new(guild_id);
+      let guild = ctx.http.get_guild(guild_id).await?;
+      let member = ctx.http.get_member(guild_id, bot_user_id).await?;
+      Ok((
+        guild_id,
+        requirement.permissions - guild_permissions(&guild, &member),
+      ))
+    }
+  }
+}
+
+pub async fn self_test(ctx: &Context, config: &Config, alerts: &AlertManager, bot_user_id: UserId) {
+  for requirement in requirements(config) {
+    let (target, key) = match requirement.target {
+      Target::Channel(id) => (format!("channel {}", id), id),
+      Target::Guild(id) => (format!("guild {}", id), id),
+    };
+    let problem = match check(ctx, &requirement, bot_user_id).await {
+      Ok((_, missing)) if missing.is_empty() => {
+        log::success(format!(
+          "Permission check passed for {} {}",
+          requirement.feature, target
+        ));
+        continue;
+      }
+      Ok((_, missing)) => format!("缺少权限: {}", missing.get_permission_names().join(", ")),
+      Err(e) => format!("无法访问: {}", e),
+    };
+
+    log::error(format!(
+      "Permission check failed for {} {}: {}",
+      requirement.feature, target, problem
+    ));
+    alerts
+      .raise(
+        ctx,
+        &format!("permissions:{}:{}", requirement.feature, key),
+        &format!("{} {} 权限不足", requirement.label, key),
+        &problem,
+      )
+      .await;
+  }
+}
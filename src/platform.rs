@@ -0,0 +1,46 @@
+use anyhow::Result;
+use serenity::async_trait;
+use std::sync::Arc;
+
+use crate::config::{GzctfConfig, PlatformKind};
+use crate::ctfd::CtfdClient;
+use crate::feed::FeedClient;
+use crate::gzctf::GzctfClient;
+use crate::limiter::RequestLimiter;
+use crate::models::{GameInfo, Notice, Scoreboard};
+
+// 比赛平台的数据来源，轮询、缓存和命令只依赖这个接口；
+// 公告统一转换成 GZCTF 的格式，之后的队列和 Discord 发送逻辑不区分平台
+#[async_trait]
+pub trait CtfPlatform: Send + Sync {
+  async fn fetch_notices(&self, match_id: u32) -> Result<Vec<Notice>>;
+
+  // 只拉取 ID 大于 last_id 的公告，平台不支持时可以返回完整列表
+  async fn fetch_notices_since(
+    &self,
+    match_id: u32,
+    last_id: u64,
+    page_size: u32,
+  ) -> Result<Vec<Notice>>;
+
+  async fn fetch_scoreboard(&self, match_id: u32) -> Result<Scoreboard>;
+
+  async fn fetch_game(&self, match_id: u32) -> Result<GameInfo>;
+
+  // 没有排行榜的平台不同步题目信息，避免每轮都报错
+  fn has_scoreboard(&self) -> bool {
+    true
+  }
+}
+
+// limiter 只用于 GZCTF，其他平台的请求量很小
+pub fn connect(
+  config: &GzctfConfig,
+  limiter: &Arc<RequestLimiter>,
+) -> Result<Arc<dyn CtfPlatform>> {
+  Ok(match config.platform {
+    PlatformKind::Gzctf => Arc::new(GzctfClient::new(config, Arc::clone(limiter))?),
+    PlatformKind::Ctfd => Arc::new(CtfdClient::new(config)?),
+    PlatformKind::Feed => Arc::new(FeedClient::new(config)?),
+  })
+}
@@ -1,40 +1,154 @@
 use anyhow::Result;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tokio::sync::{Mutex, RwLock};
 use tokio::time::{Duration, sleep};
 
-use crate::config::{Config, MatchConfig};
-use crate::discord::DiscordMessenger;
-use crate::gzctf::{GzctfClient, create_embed};
+use crate::alerts::AlertManager;
+use crate::challenges::ChallengeStore;
+use crate::clock::Clock;
+use crate::config::{
+  Config, DeliveryMode, MatchConfig, PlatformKind, PosterPlacement, SpoilerField,
+};
+use crate::digest::{DigestBuffer, DigestEntry};
+use crate::discord::{DeliveryState, DiscordMessenger};
+use crate::discussion;
+use crate::gzctf::{
+  GzctfClient, create_blood_board, create_embed, deploy_label, format_time, poster_url, spoiler,
+  trunc_text, with_spoilers,
+};
+use crate::handler::BotHandler;
+use crate::links::TeamLinks;
 use crate::log;
-use crate::models::{Notice, NoticeType};
+use crate::models::{Notice, NoticeType, NoticesByType, Scoreboard};
+use crate::platform::{self, CtfPlatform};
 use crate::queue::{MessageItem, MessageQueue};
+use crate::recent::{RecentNotice, RecentNotices};
+use crate::relays::Relays;
+use crate::rewards::RoleRewarder;
+use crate::scores::ScoreWatcher;
+use crate::shutdown::ShutdownBarrier;
+use crate::subscriptions::Subscriptions;
+use crate::tasks;
+use crate::template;
 use crate::tracker::NoticeTracker;
+use crate::translate::Translator;
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+use serenity::model::channel::Message;
 use serenity::prelude::Context;
 
+// digest 模式目标频道的待汇总公告
+const DIGEST_PATH: &str = "digest_buffer.json";
+// 静默时段内暂缓的公告，静默结束后汇总发送
+const QUIET_PATH: &str = "quiet_buffer.json";
+// 维护模式期间扣留的公告，退出维护后汇总发送
+const HELD_PATH: &str = "held_buffer.json";
+
 pub struct PollingService {
   config: Arc<Config>,
-  gzctf_client: GzctfClient,
+  platform: Arc<dyn CtfPlatform>,
   messenger: DiscordMessenger,
+  delivery: Arc<DeliveryState>,
   tracker: Arc<RwLock<NoticeTracker>>,
   message_queue: Arc<MessageQueue>,
+  alerts: Arc<AlertManager>,
+  rewarder: Option<RoleRewarder>,
+  links: Arc<TeamLinks>,
+  digests: DigestBuffer,
+  quiet_buffer: DigestBuffer,
+  // 维护期间暂存的公告
+  held: DigestBuffer,
+  recent: Arc<RecentNotices>,
+  translator: Option<Translator>,
+  relays: Arc<Relays>,
+  scores: ScoreWatcher,
+  challenges: Arc<ChallengeStore>,
+  shutdown: Arc<ShutdownBarrier>,
+  subscriptions: Arc<Subscriptions>,
+  clock: Arc<dyn Clock>,
+  anchors: Mutex<HashMap<u32, FetchAnchor>>,
+  // 比赛海报地址，没有海报的比赛缓存为 None
+  posters: Mutex<HashMap<u32, Option<String>>>,
+  // 有目标频道需要提示原文时才创建
+  hint_client: Option<GzctfClient>,
+  // 本轮剩余可发送的公告数
+  budget: AtomicUsize,
+  // 进程内已完成过一次初始化；panic 后被 supervise 重启时保留 tracker 的进度
+  initialized: AtomicBool,
+  // 公告还没记录进 tracker 时已送达（或已入队、暂存）的频道消息 ID；
+  // 部分频道失败时下一轮只重发失败的频道，全部成功后清除
+  delivered: Mutex<HashSet<String>>,
+}
+
+// 增量拉取的起点：已全部处理的最大公告 ID，以及距上次完整拉取的次数
+#[derive(Debug, Clone, Copy, Default)]
+struct FetchAnchor {
+  last_id: u64,
+  since_full: u32,
 }
 
 impl PollingService {
-  pub fn new(
-    config: Arc<Config>,
-    tracker: Arc<RwLock<NoticeTracker>>,
-    message_queue: Arc<MessageQueue>,
-  ) -> Result<Self> {
-    let gzctf_client = GzctfClient::new(config.gzctf.url.clone())?;
-    let messenger = DiscordMessenger::new(config.discord.channel_id);
+  // 与事件处理器共享配置、进度、队列等状态
+  pub fn new(handler: &BotHandler) -> Result<Self> {
+    let config = Arc::clone(&handler.config);
+    let links = Arc::clone(&handler.links);
+    let platform = platform::connect(&config.gzctf, &handler.limiter)?;
+    let messenger = DiscordMessenger::new(config.discord.channel_id, Arc::clone(&handler.delivery));
+    let rewarder = config
+      .rewards
+      .clone()
+      .map(|rewards| RoleRewarder::new(rewards, Arc::clone(&links)));
+    let translator = config
+      .translation
+      .clone()
+      .map(Translator::new)
+      .transpose()?;
+    let relays = Arc::new(Relays::from_config(&config)?);
+    let digests = DigestBuffer::load(config.storage.path(DIGEST_PATH), handler.cipher.clone());
+    let quiet_buffer = DigestBuffer::load(config.storage.path(QUIET_PATH), handler.cipher.clone());
+    let held = DigestBuffer::load(config.storage.path(HELD_PATH), handler.cipher.clone());
+    let hint_client = if config.gzctf.platform == PlatformKind::Gzctf
+      && config.destinations.iter().any(|d| d.hint_content)
+    {
+      if config.gzctf.session_cookie.is_none() {
+        log::warn("Hint content needs gzctf.session_cookie of a team member in the match");
+      }
+      Some(GzctfClient::new(
+        &config.gzctf,
+        Arc::clone(&handler.limiter),
+      )?)
+    } else {
+      None
+    };
 
     Ok(Self {
       config,
-      gzctf_client,
+      platform,
       messenger,
-      tracker,
-      message_queue,
+      delivery: Arc::clone(&handler.delivery),
+      tracker: Arc::clone(&handler.tracker),
+      message_queue: Arc::clone(&handler.message_queue),
+      alerts: Arc::clone(&handler.alerts),
+      rewarder,
+      links,
+      digests,
+      quiet_buffer,
+      held,
+      recent: Arc::clone(&handler.recent),
+      translator,
+      relays,
+      scores: ScoreWatcher::new(),
+      challenges: Arc::clone(&handler.challenges),
+      shutdown: Arc::clone(&handler.shutdown),
+      subscriptions: Arc::clone(&handler.subscriptions),
+      clock: Arc::clone(&handler.clock),
+      anchors: Mutex::new(HashMap::new()),
+      posters: Mutex::new(HashMap::new()),
+      hint_client,
+      budget: AtomicUsize::new(0),
+      initialized: AtomicBool::new(false),
+      delivered: Mutex::new(HashSet::new()),
     })
   }
 
@@ -68,69 +182,367 @@ impl PollingService {
     match_config: &MatchConfig,
     notice_types: &[NoticeType],
   ) -> Result<()> {
-    let notices = self.gzctf_client.fetch_notices(match_config.id).await?;
+    let notices = self.platform.fetch_notices(match_config.id).await?;
     let mut tracker = self.tracker.write().await;
+    // 按 on_start 留出要重放的公告，不计入已处理；只在第一次监控该比赛时生效，避免重启后重复播报
+    let replayed = if tracker.knows_match(match_config.id) {
+      HashSet::new()
+    } else {
+      let announced = NoticesByType::new(&notices);
+      let candidates: Vec<&Notice> = match_config
+        .announced_types()
+        .iter()
+        .flat_map(|notice_type| announced.get(notice_type).iter().copied())
+        .collect();
+      match_config.on_start.replayed(&candidates)
+    };
+    if !replayed.is_empty() {
+      log::info(format!(
+        "   Replaying {} existing notices of match {}",
+        replayed.len(),
+        match_config.id
+      ));
+    }
 
+    let kept: Vec<Notice> = notices
+      .iter()
+      .filter(|n| !replayed.contains(&n.id))
+      .cloned()
+      .collect();
+    let by_type = NoticesByType::new(&kept);
     notice_types.iter().for_each(|notice_type| {
-      let filtered = GzctfClient::filter_by_type(&notices, notice_type.clone());
+      let filtered = by_type.get(notice_type);
       let type_str = format!("{:?}", notice_type);
 
-      filtered.iter().map(|n| n.time).max().map(|max_time| {
-        tracker.update_timestamp(match_config.id, &type_str, max_time);
+      tracker.init(match_config.id, &type_str, filtered);
+      if let Some(latest) = filtered.iter().max_by_key(|n| n.id) {
         log::info(format!(
-          "   {:?}: latest timestamp = {}",
-          notice_type, max_time
+          "   {:?}: latest notice ID = {} (timestamp {})",
+          notice_type, latest.id, latest.time
         ));
-      });
+      }
     });
 
+    // 增量拉取的起点停在最早的未处理公告（重放的和停机期间发布的）之前
+    let all = NoticesByType::new(&notices);
+    let pending = match_config
+      .announced_types()
+      .into_iter()
+      .flat_map(|notice_type| {
+        let type_str = format!("{:?}", notice_type);
+        let tracker = &tracker;
+        all
+          .get(&notice_type)
+          .iter()
+          .filter(move |n| tracker.is_new(match_config.id, &type_str, n))
+      })
+      .map(|n| n.id)
+      .min();
+    let last_id = match pending {
+      Some(first) => Some(first.saturating_sub(1)),
+      None => notices.iter().map(|n| n.id).max(),
+    };
+    if let Some(last_id) = last_id {
+      self.anchors.lock().await.insert(
+        match_config.id,
+        FetchAnchor {
+          last_id,
+          since_full: 0,
+        },
+      );
+    }
+
     Ok(())
   }
 
-  async fn check_match(&self, ctx: &Context, match_config: &MatchConfig) -> Result<()> {
-    let notice_types = NoticeType::all();
-    let notices = self.gzctf_client.fetch_notices(match_config.id).await?;
-    let mut tracker = self.tracker.write().await;
+  // 还有未处理完的公告时，起点停在它之前，下一轮增量拉取仍能拿到它
+  async fn update_anchor(
+    &self,
+    match_config: &MatchConfig,
+    notices: &[Notice],
+    by_type: &NoticesByType<'_>,
+    tracker: &NoticeTracker,
+  ) {
+    let pending = match_config
+      .announced_types()
+      .into_iter()
+      .flat_map(|notice_type| {
+        let type_str = format!("{:?}", notice_type);
+        by_type
+          .get(&notice_type)
+          .iter()
+          .filter(move |n| tracker.is_new(match_config.id, &type_str, n))
+      })
+      .map(|n| n.id)
+      .min();
+    let mut anchors = self.anchors.lock().await;
+    let anchor = anchors.entry(match_config.id).or_default();
+    anchor.last_id = match pending {
+      Some(id) => id.saturating_sub(1),
+      None => notices
+        .iter()
+        .map(|n| n.id)
+        .max()
+        .unwrap_or(0)
+        .max(anchor.last_id),
+    };
+  }
 
-    for notice_type in &notice_types {
-      self
-        .handle_notices(ctx, match_config, notice_type, &notices, &mut tracker)
-        .await;
+  // 定期做一次完整拉取，其余时候只拉取起点之后的公告
+  async fn fetch_notices(&self, match_id: u32) -> Result<Vec<Notice>> {
+    let gzctf = &self.config.gzctf;
+    let incremental = {
+      let mut anchors = self.anchors.lock().await;
+      let anchor = anchors.entry(match_id).or_default();
+      let incremental =
+        gzctf.page_size > 0 && anchor.last_id > 0 && anchor.since_full < gzctf.full_fetch_every;
+      anchor.since_full = if incremental {
+        anchor.since_full + 1
+      } else {
+        0
+      };
+      incremental.then_some(anchor.last_id)
+    };
+
+    match incremental {
+      Some(last_id) => {
+        self
+          .platform
+          .fetch_notices_since(match_id, last_id, gzctf.page_size)
+          .await
+      }
+      None => self.platform.fetch_notices(match_id).await,
     }
+  }
 
-    Ok(())
+  // 按配置为血榜补充得分和排名，并隐藏解出人数不足的题目名；获取排行榜失败时不补充，题目名全部隐藏
+  async fn prepare_bloods(&self, match_config: &MatchConfig, notices: Vec<Notice>) -> Vec<Notice> {
+    if match_config.mask_challenges_below.is_none() && !match_config.enrich_bloods {
+      return notices;
+    }
+    let is_blood = |n: &Notice| {
+      matches!(
+        NoticeType::from_str(&n.notice_type),
+        Some(NoticeType::FirstBlood | NoticeType::SecondBlood | NoticeType::ThirdBlood)
+      )
+    };
+    if !notices.iter().any(is_blood) {
+      return notices;
+    }
+
+    let scoreboard = match self.platform.fetch_scoreboard(match_config.id).await {
+      Ok(scoreboard) => {
+        self.challenges.update(match_config.id, &scoreboard).await;
+        Some(scoreboard)
+      }
+      Err(e) => {
+        log::error(format!(
+          "Failed to fetch scoreboard for match {}: {}. Bloods go out without scores and with masked names.",
+          match_config.id, e
+        ));
+        None
+      }
+    };
+
+    notices
+      .into_iter()
+      .map(|mut notice| {
+        if !is_blood(&notice) {
+          return notice;
+        }
+        if match_config.enrich_bloods
+          && let Some(scoreboard) = &scoreboard
+          && let Some(gain) = score_gain(scoreboard, &notice)
+        {
+          notice.values.push(gain);
+        }
+        let Some(threshold) = match_config.mask_challenges_below else {
+          return notice;
+        };
+        let Some(title) = notice.values.get_mut(1) else {
+          return notice;
+        };
+
+        let challenge = scoreboard.as_ref().and_then(|s| s.find_challenge(title));
+        match challenge {
+          Some(c) if c.solved >= threshold => {}
+          Some(c) => *title = format!("{} / ???", c.category),
+          None => *title = "???".to_string(),
+        }
+        notice
+      })
+      .collect()
   }
 
+  // 同一比赛各类型的新公告合并成一条按 ID 排序的流，连续的同类公告一起处理；
+  // 某条公告留到下一轮时其后的公告也一并推迟，频道里的顺序与 GZCTF 上的发布顺序一致
   async fn handle_notices(
     &self,
     ctx: &Context,
     match_config: &MatchConfig,
-    notice_type: &NoticeType,
-    notices: &[Notice],
+    by_type: &NoticesByType<'_>,
     tracker: &mut tokio::sync::RwLockWriteGuard<'_, NoticeTracker>,
   ) {
+    let seen: &NoticeTracker = tracker;
+    let mut stream: Vec<(NoticeType, &Notice)> = match_config
+      .announced_types()
+      .into_iter()
+      .flat_map(|notice_type| {
+        let type_str = format!("{:?}", notice_type);
+        Self::get_new_notices(by_type.get(&notice_type), seen, match_config.id, &type_str)
+          .into_iter()
+          .map(move |notice| (notice_type.clone(), notice))
+      })
+      .collect();
+    stream.sort_by_key(|(_, notice)| notice.id);
+
+    let mut boards = if match_config.group_bloods {
+      blood_boards(&stream)
+    } else {
+      HashMap::new()
+    };
+    let mut pending: Vec<(NoticeType, &Notice)> = Vec::new();
+    for (notice_type, notice) in stream {
+      // 血榜在第一条血的位置发出，同一题后面的血已包含在内
+      let board = match challenge_title(&notice_type, notice) {
+        Some(title) if is_blood(&notice_type) => boards.get_mut(title).map(Option::take),
+        _ => None,
+      };
+      match board {
+        Some(None) => continue,
+        Some(Some(members)) => {
+          if !self
+            .flush_run(ctx, match_config, &mut pending, tracker)
+            .await
+            || !self
+              .broadcast_board(ctx, match_config, members, tracker)
+              .await
+          {
+            return;
+          }
+        }
+        None => {
+          if pending.last().is_some_and(|(t, _)| *t != notice_type)
+            && !self
+              .flush_run(ctx, match_config, &mut pending, tracker)
+              .await
+          {
+            return;
+          }
+          pending.push((notice_type, notice));
+        }
+      }
+    }
+    self
+      .flush_run(ctx, match_config, &mut pending, tracker)
+      .await;
+  }
+
+  // 处理一段连续的同类公告，返回是否全部处理完
+  async fn flush_run(
+    &self,
+    ctx: &Context,
+    match_config: &MatchConfig,
+    run: &mut Vec<(NoticeType, &Notice)>,
+    tracker: &mut tokio::sync::RwLockWriteGuard<'_, NoticeTracker>,
+  ) -> bool {
+    let Some((notice_type, _)) = run.first() else {
+      return true;
+    };
+    let notice_type = notice_type.clone();
     let type_str = format!("{:?}", notice_type);
-    let filtered = GzctfClient::filter_by_type(notices, notice_type.clone());
-    let last_timestamp = tracker.get_timestamp(match_config.id, &type_str);
-    let new_notices = self.get_new_notices(&filtered, last_timestamp);
-    if !new_notices.is_empty() {
-      self.log_new_notice(match_config, notice_type, new_notices.len());
-      self
+    let notices: Vec<&Notice> = run.drain(..).map(|(_, notice)| notice).collect();
+    self.log_new_notice(match_config, &notice_type, notices.len());
+    self
+      .broadcast(ctx, match_config, &notice_type, notices, tracker, &type_str)
+      .await
+  }
+
+  // 血榜成员逐条过滤分类，剩下不止一条时合并发送，每条仍分别记入 tracker
+  async fn broadcast_board(
+    &self,
+    ctx: &Context,
+    match_config: &MatchConfig,
+    members: Vec<(NoticeType, &Notice)>,
+    tracker: &mut tokio::sync::RwLockWriteGuard<'_, NoticeTracker>,
+  ) -> bool {
+    let mut kept: Vec<(NoticeType, &Notice)> = Vec::new();
+    for (notice_type, notice) in members {
+      let type_str = format!("{:?}", notice_type);
+      let Some(notices) = self
+        .filter_categories(match_config, &notice_type, vec![notice], tracker, &type_str)
+        .await
+      else {
+        return false;
+      };
+      kept.extend(notices.into_iter().map(|n| (notice_type.clone(), n)));
+    }
+    if kept.len() < 2 {
+      let Some((notice_type, notice)) = kept.pop() else {
+        return true;
+      };
+      let type_str = format!("{:?}", notice_type);
+      return self
         .broadcast(
           ctx,
           match_config,
-          notice_type,
-          new_notices,
+          &notice_type,
+          vec![notice],
           tracker,
           &type_str,
         )
         .await;
     }
+
+    let ids: Vec<String> = kept.iter().map(|(_, n)| n.id.to_string()).collect();
+    log::info(format!(
+      "[Match {}] Combining bloods {} into one board",
+      match_config.id,
+      ids.join(", ")
+    ));
+    if !self.take_budget() {
+      log::info(format!(
+        "   Reached max_messages_per_cycle, deferring blood board ({}) to the next cycle",
+        ids.join(", ")
+      ));
+      return false;
+    }
+    let Some(_inflight) = self.shutdown.enter() else {
+      return false;
+    };
+
+    let raw_notices: Vec<Notice> = kept.iter().map(|(_, n)| (*n).clone()).collect();
+    let prepared = self.prepare_bloods(match_config, raw_notices).await;
+    let members: Vec<(NoticeType, Notice)> = kept
+      .iter()
+      .map(|(notice_type, _)| notice_type.clone())
+      .zip(prepared)
+      .collect();
+
+    let correlation = log::new_correlation_id("msg");
+    let relay = self.relay_board(ctx, match_config, &kept, &members);
+    if !log::correlate(&correlation, relay).await {
+      return false;
+    }
+    for (notice_type, raw) in &kept {
+      tracker.record(match_config.id, &format!("{:?}", notice_type), raw);
+    }
+    true
   }
 
-  fn get_new_notices<'a>(&self, notices: &'a [Notice], last_max: u64) -> Vec<&'a Notice> {
-    let mut new_notices: Vec<_> = notices.iter().filter(|n| n.time > last_max).collect();
-    new_notices.sort_by_key(|n| n.time);
+  // 按 ID 排序而不是时间，服务器时钟回拨时顺序依然正确
+  fn get_new_notices<'a>(
+    notices: &[&'a Notice],
+    tracker: &NoticeTracker,
+    match_id: u32,
+    type_str: &str,
+  ) -> Vec<&'a Notice> {
+    let mut new_notices: Vec<_> = notices
+      .iter()
+      .filter(|n| tracker.is_new(match_id, type_str, n))
+      .copied()
+      .collect();
+    new_notices.sort_by_key(|n| n.id);
     new_notices
   }
 
@@ -142,15 +554,226 @@ impl PollingService {
     notices: Vec<&Notice>,
     tracker: &mut tokio::sync::RwLockWriteGuard<'_, NoticeTracker>,
     type_str: &str,
-  ) {
+  ) -> bool {
+    let Some(notices) = self
+      .filter_categories(match_config, notice_type, notices, tracker, type_str)
+      .await
+    else {
+      return false;
+    };
+
+    // 隐藏题目名和翻译只作用于发送内容，tracker 始终记录原始公告的指纹
+    let raw_notices: Vec<Notice> = notices.iter().map(|n| (*n).clone()).collect();
+    let prepared = self.prepare_bloods(match_config, raw_notices).await;
+
+    for (raw, notice) in notices.into_iter().zip(prepared) {
+      if !self.take_budget() {
+        log::info(format!(
+          "   Reached max_messages_per_cycle, deferring notice ID {} to the next cycle",
+          notice.id
+        ));
+        return false;
+      }
+      // 退出过程中不再开始新的发送，未记录的公告下次启动会重新发现
+      let Some(_inflight) = self.shutdown.enter() else {
+        return false;
+      };
+      let correlation = log::new_correlation_id("msg");
+      let relay = self.relay(ctx, match_config, notice_type, raw, notice);
+      // 只有在消息已送达或已可靠入队后才推进进度，否则留到下一轮重新处理
+      if !log::correlate(&correlation, relay).await {
+        return false;
+      }
+      tracker.record(match_config.id, type_str, raw);
+    }
+    true
+  }
+
+  // 不在 announce_categories 中的题目直接记为已处理；题目信息获取失败时整批留到下一轮
+  async fn filter_categories<'a>(
+    &self,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    notices: Vec<&'a Notice>,
+    tracker: &mut tokio::sync::RwLockWriteGuard<'_, NoticeTracker>,
+    type_str: &str,
+  ) -> Option<Vec<&'a Notice>> {
+    if match_config.announce_categories.is_empty() {
+      return Some(notices);
+    }
+    let mut kept = Vec::new();
     for notice in notices {
-      self
-        .broadcast_single(ctx, match_config, notice_type, notice)
+      let Some(title) = challenge_title(notice_type, notice) else {
+        kept.push(notice);
+        continue;
+      };
+      let category = match self
+        .challenges
+        .find(self.platform.as_ref(), match_config.id, title)
         .await
-        .unwrap_or_else(|e| log::error(format!("Failed to send embed message: {}", e)));
+      {
+        Ok(challenge) => challenge.map(|c| c.category),
+        Err(e) => {
+          log::error(format!(
+            "Failed to resolve challenge categories for match {}: {}. Will retry next cycle.",
+            match_config.id, e
+          ));
+          return None;
+        }
+      };
+
+      // 排行榜里找不到的题目同样不播报，避免未公开的题目泄露
+      if category
+        .as_deref()
+        .is_some_and(|c| match_config.announces_category(c))
+      {
+        kept.push(notice);
+      } else {
+        log::info(format!(
+          "   Skipping notice ID {} for '{}' in category {}",
+          notice.id,
+          title,
+          category.as_deref().unwrap_or("<unknown>")
+        ));
+        tracker.record(match_config.id, type_str, notice);
+      }
+    }
+    Some(kept)
+  }
+
+  // 未达到每轮上限时占用一个名额；未送达的公告不会被记录，下一轮会重新发现
+  fn take_budget(&self) -> bool {
+    if self.config.discord.max_messages_per_cycle == 0 {
+      return true;
+    }
+    self
+      .budget
+      .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+        left.checked_sub(1)
+      })
+      .is_ok()
+  }
+
+  // 发送单条新公告及其附带动作，返回是否已送达或可靠入队
+  async fn relay(
+    &self,
+    ctx: &Context,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    raw: &Notice,
+    notice: Notice,
+  ) -> bool {
+    let notice = &self.translate_notice(notice_type, &notice).await;
+    if let Err(e) = self
+      .broadcast_single(ctx, match_config, notice_type, notice, None)
+      .await
+    {
+      log::error(format!(
+        "Failed to deliver or queue notice {}: {}. Will retry next cycle.",
+        notice.id, e
+      ));
+      return false;
+    }
+    self
+      .relay_extras(ctx, match_config, notice_type, raw, notice)
+      .await;
+    true
+  }
+
+  // 血榜只在主播报频道合并，其余输出仍按单条公告处理
+  async fn relay_board(
+    &self,
+    ctx: &Context,
+    match_config: &MatchConfig,
+    raw: &[(NoticeType, &Notice)],
+    members: &[(NoticeType, Notice)],
+  ) -> bool {
+    let board = create_blood_board(
+      members,
+      match_config.name.as_deref(),
+      match_config.id,
+      &self.config.gzctf.url,
+      self.config.gzctf.platform,
+      &match_config.branding,
+    );
+    let (lead_type, lead) = &members[0];
+    if let Err(e) = self
+      .broadcast_single(ctx, match_config, lead_type, lead, Some((board, members)))
+      .await
+    {
+      log::error(format!(
+        "Failed to deliver or queue blood board for notice {}: {}. Will retry next cycle.",
+        lead.id, e
+      ));
+      return false;
+    }
+    for ((notice_type, raw), (_, notice)) in raw.iter().zip(members) {
+      self
+        .relay_extras(ctx, match_config, notice_type, raw, notice)
+        .await;
+    }
+    true
+  }
 
-      tracker.update_timestamp(match_config.id, type_str, notice.time);
+  async fn relay_extras(
+    &self,
+    ctx: &Context,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    raw: &Notice,
+    notice: &Notice,
+  ) {
+    self
+      .recent
+      .record(
+        match_config.id,
+        RecentNotice {
+          match_name: match_config.name.clone(),
+          notice_type: notice_type.clone(),
+          notice: notice.clone(),
+        },
+      )
+      .await;
+    self.fan_out(ctx, match_config, notice_type, notice).await;
+    self
+      .notify_subscribers(ctx, match_config, notice_type, raw, notice)
+      .await;
+    // 邮件、OneBot、webhook 逐个发送可能较慢，放到后台，不占用 tracker 写锁
+    let relays = Arc::clone(&self.relays);
+    let config = Arc::clone(&self.config);
+    let match_config = match_config.clone();
+    let (relayed_type, relayed) = (notice_type.clone(), notice.clone());
+    tokio::spawn(async move {
+      relays
+        .send(&config, &match_config, &relayed_type, &relayed)
+        .await;
+    });
+
+    if *notice_type == NoticeType::FirstBlood
+      && let (Some(rewarder), Some(team_name)) = (&self.rewarder, notice.values.first())
+    {
+      rewarder.reward_first_blood(ctx, team_name).await;
+    }
+  }
+
+  // 比赛公告的译文追加为第二个 value，随公告一起进入队列和汇总
+  async fn translate_notice(&self, notice_type: &NoticeType, notice: &Notice) -> Notice {
+    let mut notice = notice.clone();
+    let (Some(translator), NoticeType::Normal) = (&self.translator, notice_type) else {
+      return notice;
+    };
+    let Some(text) = notice.values.first() else {
+      return notice;
+    };
+
+    match translator.translate(text).await {
+      Ok(translated) => notice.values.push(translated),
+      Err(e) => log::error(format!(
+        "Failed to translate notice {}: {}. Posting original only.",
+        notice.id, e
+      )),
     }
+    notice
   }
 
   async fn broadcast_single(
@@ -159,44 +782,736 @@ impl PollingService {
     match_config: &MatchConfig,
     notice_type: &NoticeType,
     notice: &Notice,
+    // 合并后的血榜及其成员：发送血榜，暂存时仍逐条保存
+    board: Option<(CreateEmbed, &[(NoticeType, Notice)])>,
+  ) -> Result<()> {
+    let base_id = format!("{}:{}:{}", match_config.id, notice.id, notice.time);
+    let (embed, members) = match board {
+      Some((embed, members)) => (embed, members.to_vec()),
+      None => (
+        self
+          .build_embed(match_config, notice_type, notice, &[])
+          .await,
+        vec![(notice_type.clone(), notice.clone())],
+      ),
+    };
+    let (congrats, mentions) = match self
+      .congrats_content(match_config, notice_type, notice)
+      .await
+    {
+      Some((content, members)) => (Some(content), members),
+      None => (None, Vec::new()),
+    };
+
+    // 每个频道单独计结果：某个频道入队失败不影响其余频道，也不让已送达的频道下一轮重复收到
+    let mut handled = Vec::new();
+    let mut failure = None;
+    for channel_id in self.config.broadcast_channels(match_config.id) {
+      let content = Self::with_prefix(
+        self.config.broadcast_prefix(channel_id, notice_type),
+        match_config,
+        notice_type,
+        notice,
+        congrats.clone(),
+      );
+      let embed = Self::with_footer(
+        self.config.broadcast_footer(channel_id, notice_type),
+        match_config,
+        notice_type,
+        notice,
+        embed.clone(),
+      );
+      // 默认频道沿用原来的消息 ID，其他服务器的频道追加 @channel 以区分
+      let message_id = if channel_id == self.config.discord.channel_id {
+        base_id.clone()
+      } else {
+        format!("{}@{}", base_id, channel_id)
+      };
+      if self.delivered.lock().await.contains(&message_id) {
+        log::info(format!(
+          "   Notice {} was already handled for this channel, skipping.",
+          message_id
+        ));
+        handled.push(message_id);
+        continue;
+      }
+      if self.message_queue.contains(&message_id).await {
+        log::info(format!(
+          "   Notice {} is already waiting in the retry queue, skipping.",
+          message_id
+        ));
+        handled.push(message_id);
+        continue;
+      }
+
+      let result = if self.delivery.maintenance().is_active() {
+        log::info(format!(
+          "   Maintenance: holding notice ID {} for channel {}",
+          notice.id, channel_id
+        ));
+        Self::hold(&self.held, channel_id, match_config, &members).await
+      } else if self.is_quiet_for(notice_type) {
+        log::info(format!(
+          "   Quiet hours: holding notice ID {} for channel {} until they end",
+          notice.id, channel_id
+        ));
+        Self::hold(&self.quiet_buffer, channel_id, match_config, &members).await
+      } else {
+        let item = MessageItem::new(
+          message_id.clone(),
+          notice.clone(),
+          notice_type.clone(),
+          match_config.name.clone(),
+          match_config.id,
+          self.config.gzctf.url.clone(),
+          self.clock.now(),
+        )
+        .with_content(content)
+        .with_mentions(mentions.clone())
+        .with_channel(channel_id)
+        .with_branding(match_config.branding.clone())
+        .with_platform(self.config.gzctf.platform);
+        self
+          .deliver(ctx, match_config, channel_id, embed, item)
+          .await
+      };
+      match result {
+        Ok(()) => handled.push(message_id),
+        Err(e) => {
+          log::error(format!(
+            "Failed to deliver or queue notice {} for channel {}: {}",
+            notice.id, channel_id, e
+          ));
+          failure = Some(e);
+        }
+      }
+    }
+
+    // 任一频道失败时都不记录公告，已处理的频道记下来，下一轮只重试失败的频道
+    let mut delivered = self.delivered.lock().await;
+    match failure {
+      Some(e) => {
+        delivered.extend(handled);
+        Err(e)
+      }
+      None => {
+        for message_id in &handled {
+          delivered.remove(message_id);
+        }
+        Ok(())
+      }
+    }
+  }
+
+  async fn hold(
+    buffer: &DigestBuffer,
+    channel_id: u64,
+    match_config: &MatchConfig,
+    members: &[(NoticeType, Notice)],
+  ) -> Result<()> {
+    for (member_type, member) in members {
+      let entry = Self::digest_entry(match_config, member_type, member);
+      buffer.push(channel_id, entry).await?;
+    }
+    Ok(())
+  }
+
+  // 直接发送，失败时带上渲染好的 embed 进入重试队列；只有入队也失败时返回错误
+  async fn deliver(
+    &self,
+    ctx: &Context,
+    match_config: &MatchConfig,
+    channel_id: u64,
+    embed: CreateEmbed,
+    item: MessageItem,
   ) -> Result<()> {
     log::info(format!(
-      "   Broadcasting notice ID {} (time: {}, type: {:?})",
-      notice.id, notice.time, notice_type
+      "   Broadcasting notice ID {} (time: {}, type: {:?}) to channel {}",
+      item.notice.id, item.notice.time, item.notice_type, channel_id
+    ));
+
+    let result = self
+      .messenger
+      .send_embed_mentioning(
+        ctx,
+        channel_id,
+        item.content.as_deref(),
+        &item.mentions,
+        embed.clone(),
+      )
+      .await;
+    let e = match result {
+      Ok(message) => {
+        self
+          .open_discussion(ctx, match_config, &item.notice_type, &message, &item.notice)
+          .await;
+        return Ok(());
+      }
+      Err(e) => e,
+    };
+
+    log::error(format!(
+      "Failed to send message: {}. Adding to retry queue.",
+      e
     ));
+    self.message_queue.enqueue(item.with_embed(embed)).await?;
+    Ok(())
+  }
+
+  // 转发到额外目标，失败不影响主频道的投递语义
+  async fn fan_out(
+    &self,
+    ctx: &Context,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    notice: &Notice,
+  ) {
+    let destinations = self
+      .config
+      .destinations
+      .iter()
+      .filter(|d| d.wants_match(match_config.id));
+    // 多个频道需要提示原文时只拉取一次
+    let mut hint: Option<Option<String>> = None;
+
+    for destination in destinations {
+      // 比赛公告属于关键消息，即使在 digest 模式下也立即发送
+      if destination.mode == DeliveryMode::Digest && *notice_type != NoticeType::Normal {
+        let entry = Self::digest_entry(match_config, notice_type, notice);
+        Self::buffer(&self.digests, destination.channel_id, entry).await;
+        continue;
+      }
+      if self.delivery.maintenance().is_active() {
+        let entry = Self::digest_entry(match_config, notice_type, notice);
+        Self::buffer(&self.held, destination.channel_id, entry).await;
+        continue;
+      }
+      if self.is_quiet_for(notice_type) {
+        let entry = Self::digest_entry(match_config, notice_type, notice);
+        Self::buffer(&self.quiet_buffer, destination.channel_id, entry).await;
+        continue;
+      }
+
+      let mut embed = self
+        .build_embed(match_config, notice_type, notice, &destination.spoilers)
+        .await;
+      if *notice_type == NoticeType::NewHint && destination.hint_content {
+        if hint.is_none() {
+          hint = Some(self.hint_text(match_config, notice).await);
+        }
+        if let Some(Some(text)) = &hint {
+          embed = embed.field(
+            "提示内容",
+            Self::hint_field(text, &destination.spoilers),
+            false,
+          );
+        }
+      }
+      let shown = with_spoilers(notice, notice_type, &destination.spoilers);
+      let embed = Self::with_footer(
+        destination.footer_for(notice_type),
+        match_config,
+        notice_type,
+        &shown,
+        embed,
+      );
+      let content = Self::with_prefix(
+        destination.prefix_for(notice_type),
+        match_config,
+        notice_type,
+        &shown,
+        None,
+      );
+      let result = self
+        .messenger
+        .send_embed_to(
+          ctx,
+          destination.channel_id,
+          content.as_deref(),
+          embed.clone(),
+        )
+        .await;
+      let e = match result {
+        Ok(message) => {
+          self
+            .open_discussion(ctx, match_config, notice_type, &message, notice)
+            .await;
+          continue;
+        }
+        Err(e) => e,
+      };
+
+      log::error(format!(
+        "Failed to send to destination {}: {}. Adding to retry queue.",
+        destination.channel_id, e
+      ));
+      let message_item = MessageItem::new(
+        format!(
+          "{}:{}:{}@{}",
+          match_config.id, notice.id, notice.time, destination.channel_id
+        ),
+        notice.clone(),
+        notice_type.clone(),
+        match_config.name.clone(),
+        match_config.id,
+        self.config.gzctf.url.clone(),
+        self.clock.now(),
+      )
+      .with_content(content)
+      .with_channel(destination.channel_id)
+      .with_branding(match_config.branding.clone())
+      .with_platform(self.config.gzctf.platform)
+      .with_embed(embed);
+      if let Err(e) = self.message_queue.enqueue(message_item).await {
+        log::error(format!("Failed to queue destination message: {}", e));
+      }
+    }
+  }
+
+  // 提示和血榜私信给订阅了对应分类的用户；按原始题目名查分类，私信内容与频道一致（包括隐藏的题目名）
+  async fn notify_subscribers(
+    &self,
+    ctx: &Context,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    raw: &Notice,
+    notice: &Notice,
+  ) {
+    if matches!(notice_type, NoticeType::Normal | NoticeType::NewChallenge)
+      || self.delivery.maintenance().is_active()
+    {
+      return;
+    }
+    let Some(title) = challenge_title(notice_type, raw) else {
+      return;
+    };
+    let category = match self
+      .challenges
+      .find(self.platform.as_ref(), match_config.id, title)
+      .await
+    {
+      Ok(Some(challenge)) => challenge.category,
+      Ok(None) => return,
+      Err(e) => {
+        log::error(format!(
+          "Failed to resolve category of '{}' for subscribers: {}",
+          title, e
+        ));
+        return;
+      }
+    };
+    let users = self
+      .subscriptions
+      .subscribers(match_config.id, &category)
+      .await;
+    if users.is_empty() {
+      return;
+    }
 
-    let embed = create_embed(
-      notice,
-      notice_type.clone(),
+    let embed = self
+      .build_embed(match_config, notice_type, notice, &[])
+      .await;
+    let ctx = ctx.clone();
+    let delivery = Arc::clone(&self.delivery);
+    // 私信逐个发送可能较慢，放到后台，失败不重试
+    tokio::spawn(async move {
+      for user_id in users {
+        if let Err(e) = delivery.send_dm(&ctx, user_id, embed.clone()).await {
+          log::error(format!("Failed to DM subscriber {}: {}", user_id, e));
+        }
+      }
+    });
+  }
+
+  // 比赛公告开讨论子区；启用题目子区时，新题目开子区，同一题目的后续公告转发进去
+  async fn open_discussion(
+    &self,
+    ctx: &Context,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    message: &Message,
+    notice: &Notice,
+  ) {
+    let Some(discussion) = &self.config.discussion else {
+      return;
+    };
+
+    match notice_type {
+      NoticeType::Normal => {
+        discussion::open_thread(ctx, discussion, message, notice).await;
+      }
+      _ if !discussion.challenge_threads => {}
+      NoticeType::NewChallenge => {
+        if let Some(thread) =
+          discussion::open_challenge_thread(ctx, discussion, message, notice).await
+        {
+          let key = self
+            .thread_key(match_config, notice_type, notice, message)
+            .await;
+          self.messenger.remember_thread(key, thread).await;
+        }
+      }
+      _ => {
+        let key = self
+          .thread_key(match_config, notice_type, notice, message)
+          .await;
+        let embed = self
+          .build_embed(match_config, notice_type, notice, &[])
+          .await;
+        let archive = discussion::archive_duration(discussion.auto_archive_minutes);
+        // 子区里的副本只是方便讨论，失败时频道中的原消息已经送达，不再重试
+        if let Some(Err(e)) = self
+          .messenger
+          .send_to_thread(ctx, &key, archive, embed)
+          .await
+        {
+          log::error(format!(
+            "Failed to post notice {} into challenge thread: {}",
+            notice.id, e
+          ));
+        }
+      }
+    }
+  }
+
+  // 题目子区按 比赛 + 题目 + 频道 区分，已缓存题目信息时用题目 ID，题目改名后仍能找到原子区
+  async fn thread_key(
+    &self,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    notice: &Notice,
+    message: &Message,
+  ) -> String {
+    let title = challenge_title(notice_type, notice).unwrap_or_default();
+    let mut cached = self.challenges.cached(match_config.id, title).await;
+    // 新题目可能还没进缓存，先刷新一次，保证之后的提示和血榜算出同一个键
+    if cached.is_none() && *notice_type == NoticeType::NewChallenge {
+      if let Err(e) = self
+        .challenges
+        .refresh(self.platform.as_ref(), match_config.id)
+        .await
+      {
+        log::error(format!(
+          "Failed to sync challenges for match {}: {}",
+          match_config.id, e
+        ));
+      }
+      cached = self.challenges.cached(match_config.id, title).await;
+    }
+    let challenge = match cached {
+      Some(challenge) => format!("#{}", challenge.id),
+      None => title.to_string(),
+    };
+    format!(
+      "challenge:{}:{}@{}",
+      match_config.id, challenge, message.channel_id
+    )
+  }
+
+  async fn flush_digests(&self, ctx: &Context) {
+    if self.delivery.maintenance().is_active() {
+      return;
+    }
+    self.held.flush_due(ctx, &self.messenger, |_, _| true).await;
+
+    let is_due = |channel_id: u64, elapsed: Duration| {
+      let secs = self
+        .config
+        .destinations
+        .iter()
+        .find(|d| d.channel_id == channel_id)
+        .map(|d| d.digest_interval)
+        .unwrap_or(0);
+      elapsed >= Duration::from_secs(secs)
+    };
+    self.digests.flush_due(ctx, &self.messenger, is_due).await;
+
+    if !self.quiet_hours_active() {
+      self
+        .quiet_buffer
+        .flush_due(ctx, &self.messenger, |_, _| true)
+        .await;
+    }
+  }
+
+  fn quiet_hours_active(&self) -> bool {
+    self
+      .config
+      .quiet_hours
+      .as_ref()
+      .is_some_and(|q| q.is_active(self.clock.utc()))
+  }
+
+  // 静默时段只暂存非关键类型，比赛公告照常发送
+  fn is_quiet_for(&self, notice_type: &NoticeType) -> bool {
+    *notice_type != NoticeType::Normal && self.quiet_hours_active()
+  }
+
+  // 新题目公告附带缓存中的分类、分值和题目类型，缓存里没有时保持原样
+  async fn build_embed(
+    &self,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    notice: &Notice,
+    spoilers: &[SpoilerField],
+  ) -> CreateEmbed {
+    let mut embed = create_embed(
+      &with_spoilers(notice, notice_type, spoilers),
+      notice_type,
       match_config.name.as_deref(),
       match_config.id,
       &self.config.gzctf.url,
+      self.config.gzctf.platform,
+      &match_config.branding,
     );
+    if *notice_type == NoticeType::NewChallenge
+      && let Some(title) = notice.values.first()
+      && let Some(challenge) = self.challenges.cached(match_config.id, title).await
+    {
+      embed = embed.field("分类", challenge.category, true).field(
+        "分值",
+        challenge.score.to_string(),
+        true,
+      );
+      if let Some(challenge_type) = challenge.challenge_type.as_deref() {
+        embed = embed.field("类型", deploy_label(challenge_type), true);
+      }
+    }
+    if *notice_type == NoticeType::Normal
+      && let Some(placement) = match_config.branding.poster
+      && let Some(poster) = self.poster(match_config.id).await
+    {
+      embed = match placement {
+        PosterPlacement::Image => embed.image(poster),
+        PosterPlacement::Thumbnail => embed.thumbnail(poster),
+      };
+    }
+    embed
+  }
 
-    match self.messenger.send_embed(ctx, embed).await {
-      Ok(_) => Ok(()),
+  // 提示公告只带题目名，按缓存的题目 ID 查详情，取最后一条（最新加入的）提示
+  async fn hint_text(&self, match_config: &MatchConfig, notice: &Notice) -> Option<String> {
+    let client = self.hint_client.as_ref()?;
+    let title = notice.values.first()?;
+    let challenge = match self
+      .challenges
+      .find(self.platform.as_ref(), match_config.id, title)
+      .await
+    {
+      Ok(challenge) => challenge?,
+      Err(e) => {
+        log::error(format!("Failed to resolve challenge '{}': {}", title, e));
+        return None;
+      }
+    };
+    match client.fetch_challenge(match_config.id, challenge.id).await {
+      Ok(detail) => detail.hints.last().cloned(),
       Err(e) => {
         log::error(format!(
-          "Failed to send message: {}. Adding to retry queue.",
-          e
+          "Failed to fetch hints of challenge '{}': {}",
+          title, e
         ));
+        None
+      }
+    }
+  }
 
-        let message_id = format!("{}:{}:{}", match_config.id, notice.id, notice.time);
-        let message_item = MessageItem::new(
-          message_id,
-          notice.clone(),
-          notice_type.clone(),
-          match_config.name.clone(),
-          match_config.id,
-          self.config.gzctf.url.clone(),
-        );
-        self.message_queue.enqueue(message_item).await;
+  // 字段值上限 1024 字符，spoiler 标记也算在内
+  fn hint_field(text: &str, spoilers: &[SpoilerField]) -> String {
+    if spoilers.contains(&SpoilerField::Hint) {
+      spoiler(&trunc_text(text, 1020))
+    } else {
+      trunc_text(text, 1024)
+    }
+  }
 
-        Err(e)
+  // 每场比赛只拉取一次；拉取失败不缓存，下一条公告再试
+  async fn poster(&self, match_id: u32) -> Option<String> {
+    if let Some(poster) = self.posters.lock().await.get(&match_id) {
+      return poster.clone();
+    }
+    let poster = match self.platform.fetch_game(match_id).await {
+      Ok(game) => game
+        .poster
+        .map(|poster| poster_url(&self.config.gzctf.url, &poster)),
+      Err(e) => {
+        log::error(format!(
+          "Failed to fetch poster of match {}: {}",
+          match_id, e
+        ));
+        return None;
       }
+    };
+    self.posters.lock().await.insert(match_id, poster.clone());
+    poster
+  }
+
+  fn digest_entry(
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    notice: &Notice,
+  ) -> DigestEntry {
+    DigestEntry {
+      match_name: match_config
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("比赛 {}", match_config.id)),
+      notice_type: notice_type.clone(),
+      notice: notice.clone(),
     }
   }
 
+  // 附加目标频道暂存失败只影响该频道，不阻止公告被记录
+  async fn buffer(buffer: &DigestBuffer, channel_id: u64, entry: DigestEntry) {
+    let notice_id = entry.notice.id;
+    if let Err(e) = buffer.push(channel_id, entry).await {
+      log::error(format!(
+        "Failed to buffer notice {} for channel {}: {}",
+        notice_id, channel_id, e
+      ));
+    }
+  }
+
+  // 祝贺文本和其中提及的关联队员；只有这些队员会被提及
+  async fn congrats_content(
+    &self,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    notice: &Notice,
+  ) -> Option<(String, Vec<u64>)> {
+    let template_str = self.config.congrats.as_ref()?.template_for(notice_type)?;
+    let team = notice.values.first()?;
+    let members = self.links.members_of(team).await;
+    if members.is_empty() {
+      return None;
+    }
+
+    let mentions = members
+      .iter()
+      .map(|id| format!("<@{}>", id))
+      .collect::<Vec<_>>()
+      .join(" ");
+    let challenge = notice.values.get(1).map(String::as_str).unwrap_or_default();
+    let match_name = match_config.name.as_deref().unwrap_or("未命名比赛");
+
+    let content = template::render(
+      template_str,
+      &[
+        ("mentions", &mentions),
+        ("team", team),
+        ("challenge", challenge),
+        ("match", match_name),
+      ],
+    );
+    Some((content, members))
+  }
+
+  // 目标频道配置了前缀时放在纯文本第一行，便于按关键词设置通知
+  fn with_prefix(
+    prefix: Option<&str>,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    notice: &Notice,
+    content: Option<String>,
+  ) -> Option<String> {
+    let Some(prefix) = prefix else {
+      return content;
+    };
+    let prefix = Self::render_template(prefix, match_config, notice_type, notice);
+    Some(match content {
+      Some(content) => format!("{}\n{}", prefix, content),
+      None => prefix,
+    })
+  }
+
+  // 目标频道配置了页脚模板时替换默认的时间页脚
+  fn with_footer(
+    footer: Option<&str>,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    notice: &Notice,
+    embed: CreateEmbed,
+  ) -> CreateEmbed {
+    let Some(footer) = footer else {
+      return embed;
+    };
+    let footer = Self::render_template(footer, match_config, notice_type, notice);
+    embed.footer(CreateEmbedFooter::new(footer))
+  }
+
+  // 前缀、页脚和按类型模板共用的变量；{team} 只对血播报有值
+  fn render_template(
+    template_str: &str,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    notice: &Notice,
+  ) -> String {
+    let match_id = match_config.id.to_string();
+    let team = match notice_type {
+      NoticeType::FirstBlood | NoticeType::SecondBlood | NoticeType::ThirdBlood => {
+        notice.values.first().map(String::as_str)
+      }
+      _ => None,
+    };
+    template::render(
+      template_str,
+      &[
+        ("time", &format_time(notice.time)),
+        (
+          "match",
+          match_config.name.as_deref().unwrap_or("未命名比赛"),
+        ),
+        ("match_id", &match_id),
+        ("type", notice_type.get_title().trim_matches('*')),
+        ("team", team.unwrap_or_default()),
+        (
+          "challenge",
+          challenge_title(notice_type, notice).unwrap_or_default(),
+        ),
+      ],
+    )
+  }
+
+  async fn unknown_matches(&self, matches: &[MatchConfig]) -> Vec<MatchConfig> {
+    let tracker = self.tracker.read().await;
+    matches
+      .iter()
+      .filter(|m| !tracker.knows_match(m.id))
+      .cloned()
+      .collect()
+  }
+
+  // 一次性模式：只初始化从未见过的比赛，轮询一轮，汇总缓冲直接发出后返回
+  pub async fn run_once(&self, ctx: &Context) -> Result<()> {
+    let matches = self.config.get_matches();
+    if matches.is_empty() {
+      log::error("No matches configured to monitor!");
+      return Ok(());
+    }
+
+    let unknown = self.unknown_matches(&matches).await;
+    if !unknown.is_empty() {
+      self.init_counts(&unknown).await;
+    }
+
+    let correlation = log::new_correlation_id("once");
+    let cycle = async {
+      log::info("Polling for new notices once...");
+      self.poll_matches(ctx, &matches).await;
+      // 进程即将退出，缓冲中的汇总和静默时段消息无法留到下次
+      self
+        .digests
+        .flush_due(ctx, &self.messenger, |_, _| true)
+        .await;
+      self
+        .quiet_buffer
+        .flush_due(ctx, &self.messenger, |_, _| true)
+        .await;
+    };
+    log::correlate(&correlation, cycle).await;
+    Ok(())
+  }
+
   pub async fn start_polling(self: Arc<Self>, ctx: Arc<Context>) -> Result<()> {
     let matches = self.config.get_matches();
 
@@ -206,26 +1521,91 @@ impl PollingService {
     }
 
     self.log_match_info(&matches);
-    self.init_counts(&matches).await;
+    if self.initialized.swap(true, Ordering::SeqCst) {
+      // 重启前可能已有公告到达但尚未处理，重新初始化会把它们标记为已处理；只补上之前初始化失败的比赛
+      let unknown = self.unknown_matches(&matches).await;
+      if !unknown.is_empty() {
+        self.init_counts(&unknown).await;
+      }
+      log::info("Polling restarted, keeping tracker state.");
+    } else {
+      self.init_counts(&matches).await;
+    }
+    tasks::started("polling");
 
     loop {
       sleep(Duration::from_secs(self.config.gzctf.poll_interval)).await;
-      log::info("Polling for new notices...");
-      self.poll_matches(&ctx, &matches).await;
+      if self.shutdown.is_closing() {
+        return Ok(());
+      }
+      let correlation = log::new_correlation_id("poll");
+      let cycle = async {
+        log::info("Polling for new notices...");
+        self.poll_matches(&ctx, &matches).await;
+        // 维护期间不比较分数，结束后与维护前的状态比较，期间越过的里程碑仍会播报
+        if !self.delivery.maintenance().is_active() {
+          self.watch_scores(&ctx, &matches).await;
+        }
+        self.flush_digests(&ctx).await;
+      };
+      log::correlate(&correlation, cycle).await;
+      tasks::touch("polling");
     }
   }
 
+  // 先拉取所有比赛，再逐场按发布顺序处理
   async fn poll_matches(&self, ctx: &Context, matches: &[MatchConfig]) {
+    self.budget.store(
+      self.config.discord.max_messages_per_cycle,
+      Ordering::Relaxed,
+    );
+    let mut fetched = Vec::new();
     for match_config in matches {
-      self
-        .check_match(ctx, match_config)
-        .await
-        .unwrap_or_else(|e| {
+      match self.fetch_notices(match_config.id).await {
+        Ok(notices) => {
+          self.alerts.record_poll_success(match_config.id).await;
+          if self.platform.has_scoreboard()
+            && let Err(e) = self
+              .challenges
+              .refresh_if_stale(self.platform.as_ref(), match_config.id)
+              .await
+          {
+            log::error(format!(
+              "Failed to sync challenges for match {}: {}",
+              match_config.id, e
+            ));
+          }
+          fetched.push((match_config, notices));
+        }
+        Err(e) => {
           log::error(format!(
             "Failed to fetch notices for match {}: {}",
             match_config.id, e
-          ))
-        });
+          ));
+          self
+            .alerts
+            .record_poll_failure(match_config.id, e.to_string())
+            .await;
+        }
+      }
+    }
+
+    {
+      let bucketed: Vec<_> = fetched
+        .iter()
+        .map(|(match_config, notices)| (*match_config, notices, NoticesByType::new(notices)))
+        .collect();
+      let mut tracker = self.tracker.write().await;
+      for (match_config, _, by_type) in &bucketed {
+        self
+          .handle_notices(ctx, match_config, by_type, &mut tracker)
+          .await;
+      }
+      for (match_config, notices, by_type) in &bucketed {
+        self
+          .update_anchor(match_config, notices, by_type, &tracker)
+          .await;
+      }
     }
 
     // Save tracker once after all matches are checked
@@ -233,6 +1613,42 @@ impl PollingService {
       log::error(format!("Failed to save tracker: {}", e));
     }
   }
+
+  // 分数播报是锦上添花，发送失败不入队
+  async fn watch_scores(&self, ctx: &Context, matches: &[MatchConfig]) {
+    for match_config in matches.iter().filter(|m| m.watches_scores()) {
+      let scoreboard = match self.platform.fetch_scoreboard(match_config.id).await {
+        Ok(scoreboard) => scoreboard,
+        Err(e) => {
+          log::error(format!(
+            "Failed to fetch scoreboard for match {}: {}",
+            match_config.id, e
+          ));
+          continue;
+        }
+      };
+      self.challenges.update(match_config.id, &scoreboard).await;
+
+      let embeds = self
+        .scores
+        .diff(
+          match_config,
+          &scoreboard,
+          &self.config.gzctf.url,
+          self.config.gzctf.platform,
+        )
+        .await;
+      for embed in embeds {
+        for channel_id in self.config.broadcast_channels(match_config.id) {
+          let _ = self
+            .messenger
+            .send_embed_to(ctx, channel_id, None, embed.clone())
+            .await;
+        }
+      }
+    }
+  }
+
   fn log_match_info(&self, matches: &[MatchConfig]) {
     log::info(format!("Monitoring {} match(es)", matches.len()));
 
@@ -253,3 +1669,51 @@ impl PollingService {
     ));
   }
 }
+
+// 血榜的补充说明，如 "+500, now #3"；排行榜里找不到队伍或题目时返回 None
+fn score_gain(scoreboard: &Scoreboard, notice: &Notice) -> Option<String> {
+  let team = notice.values.first()?;
+  let challenge = scoreboard.find_challenge(notice.values.get(1)?)?;
+  let rank = scoreboard.rank_of(team)?;
+  Some(format!("+{}, now #{}", challenge.score, rank))
+}
+
+// 一血、二血、三血
+fn is_blood(notice_type: &NoticeType) -> bool {
+  matches!(
+    notice_type,
+    NoticeType::FirstBlood | NoticeType::SecondBlood | NoticeType::ThirdBlood
+  )
+}
+
+// 本轮中同一道题有多个血时，按题目名分组（保持 ID 顺序）；值为 None 表示血榜已发出
+fn blood_boards<'a>(
+  stream: &[(NoticeType, &'a Notice)],
+) -> HashMap<&'a str, Option<Vec<(NoticeType, &'a Notice)>>> {
+  let mut groups: HashMap<&str, Vec<(NoticeType, &Notice)>> = HashMap::new();
+  for (notice_type, notice) in stream {
+    if is_blood(notice_type)
+      && let Some(title) = challenge_title(notice_type, notice)
+    {
+      groups
+        .entry(title)
+        .or_default()
+        .push((notice_type.clone(), *notice));
+    }
+  }
+  groups
+    .into_iter()
+    .filter(|(_, members)| members.len() > 1)
+    .map(|(title, members)| (title, Some(members)))
+    .collect()
+}
+
+// 公告中的题目名：血榜在第二个 value，新题目和提示在第一个
+fn challenge_title<'a>(notice_type: &NoticeType, notice: &'a Notice) -> Option<&'a str> {
+  let index = match notice_type {
+    NoticeType::Normal => return None,
+    NoticeType::NewChallenge | NoticeType::NewHint => 0,
+    NoticeType::FirstBlood | NoticeType::SecondBlood | NoticeType::ThirdBlood => 1,
+  };
+  notice.values.get(index).map(String::as_str)
+}
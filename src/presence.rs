@@ -0,0 +1,41 @@
+use serenity::gateway::ActivityData;
+use serenity::model::user::OnlineStatus;
+use serenity::prelude::Context;
+use std::sync::Arc;
+use tokio::time::{Duration, sleep};
+
+use crate::alerts::AlertManager;
+use crate::log;
+
+// 网关对状态更新有频率限制，不随每次轮询刷新
+const UPDATE_INTERVAL: Duration = Duration::from_secs(30);
+
+// 在成员列表中展示监控状态：正常时 "Watching N matches | last poll Xs ago"，GZCTF 出错时显示重连中
+pub fn start(ctx: Arc<Context>, alerts: Arc<AlertManager>, match_count: usize) {
+  tokio::spawn(async move {
+    log::info("Presence updater started.");
+    loop {
+      let (last_success, failing) = alerts.poll_health().await;
+      if failing {
+        ctx.set_presence(
+          Some(ActivityData::custom("Reconnecting to GZCTF…")),
+          OnlineStatus::Idle,
+        );
+      } else {
+        let last_poll = last_success
+          .map(|at| format!("last poll {}s ago", at.elapsed().as_secs()))
+          .unwrap_or_else(|| "starting".to_string());
+        ctx.set_presence(
+          Some(ActivityData::watching(format!(
+            "{} match{} | {}",
+            match_count,
+            if match_count == 1 { "" } else { "es" },
+            last_poll
+          ))),
+          OnlineStatus::Online,
+        );
+      }
+      sleep(UPDATE_INTERVAL).await;
+    }
+  });
+}
@@ -0,0 +1,251 @@
+use anyhow::Result;
+use serenity::http::Http;
+use serenity::model::id::ChannelId;
+use std::sync::Arc;
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::Config;
+use crate::discord;
+use crate::gzctf;
+use crate::limiter::RequestLimiter;
+use crate::log;
+use crate::models::{Notice, NoticeType, NoticesByType};
+use crate::platform;
+use crate::text;
+use crate::tracker::{NoticeTracker, TRACKER_PATH};
+
+// 各类型公告的示例数据，用于 test-send
+pub fn sample_notice(notice_type: &NoticeType) -> Notice {
+  let values = match notice_type {
+    NoticeType::Normal => vec!["这是一条测试公告".to_string()],
+    NoticeType::NewChallenge | NoticeType::NewHint => vec!["Sample Challenge".to_string()],
+    NoticeType::FirstBlood | NoticeType::SecondBlood | NoticeType::ThirdBlood => {
+      vec!["Sample Team".to_string(), "Sample Challenge".to_string()]
+    }
+  };
+
+  Notice {
+    id: 0,
+    notice_type: format!("{:?}", notice_type),
+    values,
+    time: SystemClock.now_millis(),
+  }
+}
+
+// --dry-run: 拉取一次公告并打印下一轮将要发送的内容，不连接 Discord
+pub async fn dry_run(config: &Config) -> Result<()> {
+  let client = platform::connect(
+    &config.gzctf,
+    &Arc::new(RequestLimiter::from_config(&config.gzctf)),
+  )?;
+  let tracker = NoticeTracker::load_from_disk(&config.storage.path(TRACKER_PATH)).await?;
+
+  for match_config in config.get_matches() {
+    let notices = match client.fetch_notices(match_config.id).await {
+      Ok(notices) => notices,
+      Err(e) => {
+        log::error(format!(
+          "Failed to fetch notices for match {}: {}",
+          match_config.id, e
+        ));
+        continue;
+      }
+    };
+
+    let by_type = NoticesByType::new(&notices);
+    // 第一次监控的比赛只会发送 on_start 指定重放的公告，其余直接记为已处理
+    let replayed = (!tracker.knows_match(match_config.id)).then(|| {
+      let candidates: Vec<&Notice> = match_config
+        .announced_types()
+        .iter()
+        .flat_map(|notice_type| by_type.get(notice_type).iter().copied())
+        .collect();
+      match_config.on_start.replayed(&candidates)
+    });
+    let mut count = 0;
+    for notice_type in match_config.announced_types() {
+      let type_str = format!("{:?}", notice_type);
+      let pending = by_type
+        .get(&notice_type)
+        .iter()
+        .filter(|n| match &replayed {
+          Some(replayed) => replayed.contains(&n.id),
+          None => tracker.is_new(match_config.id, &type_str, n),
+        });
+      for notice in pending {
+        count += 1;
+        let embed = gzctf::create_embed(
+          notice,
+          &notice_type,
+          match_config.name.as_deref(),
+          match_config.id,
+          &config.gzctf.url,
+          config.gzctf.platform,
+          &match_config.branding,
+        );
+        println!("{}\n", text::preview(None, &embed));
+      }
+    }
+    log::info(format!(
+      "Match {}: {} new notice(s) would be sent.",
+      match_config.id, count
+    ));
+  }
+
+  Ok(())
+}
+
+// test-send: 向频道发送每种类型的示例播报，同时在终端打印预览
+pub async fn test_send(config: &Config, channel_id: Option<u64>) -> Result<()> {
+  let http = Http::new(&config.discord.token);
+  let channel = ChannelId::new(channel_id.unwrap_or(config.discord.channel_id));
+  let match_config = config.get_matches().into_iter().next();
+  let match_id = match_config.as_ref().map_or(0, |m| m.id);
+  let match_name = match_config.as_ref().and_then(|m| m.name.as_deref());
+  let branding = match_config
+    .as_ref()
+    .map(|m| m.branding.clone())
+    .unwrap_or_default();
+  let text_channel = config.text_channels().contains(&channel.get());
+
+  for notice_type in NoticeType::all() {
+    let notice = sample_notice(&notice_type);
+    let embed = gzctf::create_embed(
+      &notice,
+      &notice_type,
+      match_name,
+      match_id,
+      &config.gzctf.url,
+      config.gzctf.platform,
+      &branding,
+    );
+    println!("{}\n", text::preview(None, &embed));
+
+    channel
+      .send_message(
+        &http,
+        discord::build_message(text_channel, None, &[], &embed),
+      )
+      .await?;
+  }
+
+  log::success(format!("Sent test notices to channel {}", channel));
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::{Branding, PlatformKind};
+
+  fn notice(notice_type: &str, values: &[&str]) -> Notice {
+    Notice {
+      id: 1,
+      notice_type: notice_type.to_string(),
+      values: values.iter().map(|v| v.to_string()).collect(),
+      // 2024-01-01 00:00:00 UTC
+      time: 1_704_067_200_000,
+    }
+  }
+
+  #[test]
+  fn first_blood_snapshot() {
+    let embed = gzctf::create_embed(
+      &notice("FirstBlood", &["Team A", "Web 1"]),
+      &NoticeType::FirstBlood,
+      Some("Test CTF"),
+      3,
+      "https://ctf.example.com",
+      PlatformKind::Gzctf,
+      &Branding::default(),
+    );
+
+    assert_eq!(
+      text::preview(Some("<@1> 恭喜"), &embed),
+      "> <@1> 恭喜\n\
+       ## **一血播报**\n\
+       color: #ef4444\n\
+       **赛事:** [Test CTF](https://ctf.example.com/games/3)\n\
+       - **队伍**: Team A\n\
+       - **题目**: Web 1\n\
+       _2024-01-01 08:00:00_"
+    );
+  }
+
+  #[test]
+  fn blood_board_snapshot() {
+    let bloods = vec![
+      (
+        NoticeType::FirstBlood,
+        notice("FirstBlood", &["Team A", "Web 1"]),
+      ),
+      (
+        NoticeType::SecondBlood,
+        notice("SecondBlood", &["Team B", "Web 1", "+480, now #2"]),
+      ),
+    ];
+    let embed = gzctf::create_blood_board(
+      &bloods,
+      Some("Test CTF"),
+      3,
+      "https://ctf.example.com",
+      PlatformKind::Gzctf,
+      &Branding::default(),
+    );
+
+    assert_eq!(
+      text::preview(None, &embed),
+      "## **血榜播报**\n\
+       color: #ef4444\n\
+       **赛事:** [Test CTF](https://ctf.example.com/games/3)\n\
+       - **题目**: Web 1\n\
+       - **一血**: Team A\n\
+       - **二血**: Team B (+480, now #2)\n\
+       _2024-01-01 08:00:00_"
+    );
+  }
+
+  #[test]
+  fn normal_notice_snapshot() {
+    let embed = gzctf::create_embed(
+      &notice("Normal", &["比赛开始"]),
+      &NoticeType::Normal,
+      None,
+      3,
+      "https://ctf.example.com",
+      PlatformKind::Gzctf,
+      &Branding::default(),
+    );
+
+    assert_eq!(
+      text::preview(None, &embed),
+      "## **比赛公告**\n\
+       color: #3b82f6\n\
+       - **公告内容**: 比赛开始\n\
+       _2024-01-01 08:00:00_"
+    );
+  }
+
+  #[test]
+  fn text_render_drops_embed_only_details() {
+    let embed = gzctf::create_embed(
+      &notice("NewChallenge", &["Web 1"]),
+      &NoticeType::NewChallenge,
+      None,
+      3,
+      "https://ctf.example.com",
+      PlatformKind::Gzctf,
+      &Branding::default(),
+    )
+    .field("分值", "500", true);
+
+    assert_eq!(
+      text::render(Some("📢"), &embed),
+      "> 📢\n\
+       ## **新增题目**\n\
+       - **题目**: Web 1\n\
+       - **分值**: 500\n\
+       _2024-01-01 08:00:00_"
+    );
+  }
+}
@@ -1,19 +1,30 @@
 use anyhow::Result;
+use futures_util::{StreamExt, stream};
 use serde::{Deserialize, Serialize};
-use serenity::all::Context;
-use std::collections::VecDeque;
+use serenity::all::{Context, CreateEmbed};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::Path;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 use tokio::fs;
 use tokio::sync::{Mutex, RwLock};
-use tokio::time::{Duration, sleep};
+use tokio::time::{Duration, sleep, timeout};
 use tokio_util::sync::CancellationToken;
 
-use crate::discord::DiscordMessenger;
+use crate::alerts::AlertManager;
+use crate::clock::{Clock, SystemClock};
+use crate::config::{Branding, PlatformKind};
+use crate::crypto::{self, Cipher};
+use crate::discord::{ContextMessenger, DeliveryState, DiscordMessenger, Messenger};
 use crate::gzctf::create_embed;
+use crate::journal::Journal;
 use crate::log;
 use crate::models::{Notice, NoticeType};
+use crate::tasks;
+
+// 重试时同时发送的频道数
+const RETRY_CONCURRENCY: usize = 4;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageItem {
@@ -25,6 +36,30 @@ pub struct MessageItem {
   pub base_url: String,
   pub retry_count: u8,
   pub next_retry_at: u64,
+  // 显示在 embed 上方的纯文本（例如祝贺消息）
+  #[serde(default)]
+  pub content: Option<String>,
+  // 正文中允许提及的用户，其余提及不生效
+  #[serde(default)]
+  pub mentions: Vec<u64>,
+  // 目标频道，None 表示主播报频道
+  #[serde(default)]
+  pub channel_id: Option<u64>,
+  #[serde(default)]
+  pub branding: Branding,
+  // 决定赛事链接的格式，旧版本持久化的消息按默认的 GZCTF 处理
+  #[serde(default)]
+  pub platform: PlatformKind,
+  // 首次入队时间（秒），用于统计最早未送达消息的积压时长；
+  // 旧版本持久化的消息没有该字段，加载时按队列时钟的当前时间补齐
+  #[serde(default)]
+  pub enqueued_at: u64,
+  // 创建时所在的日志关联 ID，重试时沿用以便串起整条链路
+  #[serde(default)]
+  pub correlation_id: Option<String>,
+  // 首次发送时渲染好的 embed，重试时直接复用；不落盘，从磁盘恢复的消息按公告重新渲染
+  #[serde(skip)]
+  pub embed: Option<CreateEmbed>,
 }
 
 impl MessageItem {
@@ -35,6 +70,7 @@ impl MessageItem {
     match_name: Option<String>,
     match_id: u32,
     base_url: String,
+    now: u64,
   ) -> Self {
     Self {
       id,
@@ -44,15 +80,73 @@ impl MessageItem {
       match_id,
       base_url,
       retry_count: 0,
-      next_retry_at: Self::current_timestamp(),
+      next_retry_at: now,
+      content: None,
+      mentions: Vec::new(),
+      channel_id: None,
+      branding: Branding::default(),
+      platform: PlatformKind::default(),
+      enqueued_at: now,
+      correlation_id: log::current_correlation(),
+      embed: None,
+    }
+  }
+
+  // 旧版本持久化的消息没有关联 ID，退回使用消息 ID
+  pub fn correlation(&self) -> &str {
+    self.correlation_id.as_deref().unwrap_or(&self.id)
+  }
+
+  pub fn with_channel(mut self, channel_id: u64) -> Self {
+    self.channel_id = Some(channel_id);
+    self
+  }
+
+  pub fn with_branding(mut self, branding: Branding) -> Self {
+    self.branding = branding;
+    self
+  }
+
+  pub fn with_platform(mut self, platform: PlatformKind) -> Self {
+    self.platform = platform;
+    self
+  }
+
+  pub fn with_content(mut self, content: Option<String>) -> Self {
+    self.content = content;
+    self
+  }
+
+  pub fn with_mentions(mut self, mentions: Vec<u64>) -> Self {
+    self.mentions = mentions;
+    self
+  }
+
+  pub fn with_embed(mut self, embed: CreateEmbed) -> Self {
+    self.embed = Some(embed);
+    self
+  }
+
+  pub fn render(&self) -> CreateEmbed {
+    if let Some(embed) = &self.embed {
+      return embed.clone();
     }
+    create_embed(
+      &self.notice,
+      &self.notice_type,
+      self.match_name.as_deref(),
+      self.match_id,
+      &self.base_url,
+      self.platform,
+      &self.branding,
+    )
   }
 
-  fn current_timestamp() -> u64 {
-    std::time::SystemTime::now()
-      .duration_since(std::time::UNIX_EPOCH)
-      .unwrap()
-      .as_secs()
+  fn stamped(mut self, now: u64) -> Self {
+    if self.enqueued_at == 0 {
+      self.enqueued_at = now;
+    }
+    self
   }
 
   // delay: 2**(retry_count+1)s
@@ -60,14 +154,14 @@ impl MessageItem {
     1u64 << (self.retry_count + 1)
   }
 
-  pub fn increment_retry(&mut self) {
+  pub fn increment_retry(&mut self, now: u64) {
     self.retry_count += 1;
     let delay = self.calc_delay();
-    self.next_retry_at = Self::current_timestamp() + delay;
+    self.next_retry_at = now + delay;
   }
 
-  pub fn can_retry(&self) -> bool {
-    Self::current_timestamp() >= self.next_retry_at
+  pub fn can_retry(&self, now: u64) -> bool {
+    now >= self.next_retry_at
   }
 
   pub fn should_persist(&self) -> bool {
@@ -75,41 +169,106 @@ impl MessageItem {
   }
 }
 
+// /status 展示的队列指标
+pub struct QueueMetrics {
+  pub depth: usize,
+  pub retries: u64,
+  pub persisted: u64,
+  pub oldest_age: Option<Duration>,
+}
+
+// /queue status 列出的单条消息
+pub struct QueueEntry {
+  pub id: String,
+  pub match_id: u32,
+  pub notice_type: NoticeType,
+  pub channel_id: Option<u64>,
+  pub retry_count: u8,
+  pub age: Duration,
+  pub retry_in: Duration,
+}
+
+#[derive(Default)]
+struct QueueCounters {
+  retries: AtomicU64,
+  persisted: AtomicU64,
+}
+
 pub struct MessageQueue {
   queue: Arc<RwLock<VecDeque<MessageItem>>>,
   persist_path: String,
+  snapshot_path: String,
+  snapshot_interval: Option<Duration>,
   messenger: Arc<DiscordMessenger>,
   persist_lock: Arc<Mutex<()>>,
   shutdown_token: CancellationToken,
   retry_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+  counters: Arc<QueueCounters>,
+  cipher: Option<Arc<Cipher>>,
+  journal: Option<Arc<Journal>>,
+  clock: Arc<dyn Clock>,
 }
 
 impl MessageQueue {
-  pub fn new(persist_path: String, messenger: Arc<DiscordMessenger>) -> Self {
+  pub fn new(
+    persist_path: String,
+    snapshot_interval: u64,
+    messenger: Arc<DiscordMessenger>,
+  ) -> Self {
     Self {
       queue: Arc::new(RwLock::new(VecDeque::new())),
+      snapshot_path: format!("{}.snapshot", persist_path),
+      snapshot_interval: (snapshot_interval > 0).then(|| Duration::from_secs(snapshot_interval)),
       persist_path,
       messenger,
       persist_lock: Arc::new(Mutex::new(())),
       shutdown_token: CancellationToken::new(),
       retry_handle: Arc::new(Mutex::new(None)),
+      counters: Arc::new(QueueCounters::default()),
+      cipher: None,
+      journal: None,
+      clock: Arc::new(SystemClock),
     }
   }
 
+  // 持久化文件和快照落盘前加密
+  pub fn with_cipher(mut self, cipher: Option<Arc<Cipher>>) -> Self {
+    self.cipher = cipher;
+    self
+  }
+
+  // 重试退避和积压时长使用的时钟
+  pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+    self.clock = clock;
+    self
+  }
+
+  // 入队和重试成功都写入日志，崩溃后据此恢复快照之后的变化
+  pub fn with_journal(mut self, journal: Option<Arc<Journal>>) -> Self {
+    self.journal = journal;
+    self
+  }
+
   pub async fn load_from_disk(&self) -> Result<()> {
-    let path = Path::new(&self.persist_path);
+    let persist = Path::new(&self.persist_path);
+    let snapshot = Path::new(&self.snapshot_path);
 
-    if !path.exists() {
+    if !persist.exists() && !snapshot.exists() {
       log::info("No persisted messages found.");
       return Ok(());
     }
 
-    let content = fs::read_to_string(path).await?;
-    let items: Vec<MessageItem> = serde_json::from_str(&content)?;
+    // 快照与持久化文件可能包含同一条消息（例如关机保存后未来得及清理快照），按 ID 去重
+    let cipher = self.cipher.as_deref();
+    let mut items = Self::read_items(snapshot, cipher).await?;
+    items.extend(Self::read_items(persist, cipher).await?);
+    let mut seen = HashSet::new();
+    items.retain(|item| seen.insert(item.id.clone()));
 
+    let now = self.clock.now();
     let mut queue = self.queue.write().await;
     for item in items {
-      queue.push_back(item);
+      queue.push_back(item.stamped(now));
     }
 
     log::success(format!(
@@ -118,139 +277,265 @@ impl MessageQueue {
     ));
 
     drop(queue);
-    fs::remove_file(path).await?;
+    for path in [persist, snapshot] {
+      if path.exists() {
+        fs::remove_file(path).await?;
+      }
+    }
     log::info("Cleared persist file after loading messages.");
 
     Ok(())
   }
 
-  pub async fn enqueue(&self, message: MessageItem) {
+  async fn read_items(path: &Path, cipher: Option<&Cipher>) -> Result<Vec<MessageItem>> {
+    if !path.exists() {
+      return Ok(Vec::new());
+    }
+
+    let content = crypto::open(cipher, fs::read(path).await?)?;
+    Ok(serde_json::from_slice(&content)?)
+  }
+
+  // 定期保存在内存中等待重试的消息，避免崩溃时丢失
+  async fn write_snapshot(
+    queue: &RwLock<VecDeque<MessageItem>>,
+    persist_lock: &Mutex<()>,
+    snapshot_path: &str,
+    cipher: Option<&Cipher>,
+  ) -> Result<()> {
+    let items: Vec<MessageItem> = queue.read().await.iter().cloned().collect();
+    let _guard = persist_lock.lock().await;
+
+    if items.is_empty() {
+      if fs::try_exists(snapshot_path).await.unwrap_or(false) {
+        fs::remove_file(snapshot_path).await?;
+      }
+      return Ok(());
+    }
+
+    let data = crypto::seal(cipher, serde_json::to_vec_pretty(&items)?)?;
+    let tmp_path = format!("{}.tmp", snapshot_path);
+    fs::write(&tmp_path, data).await?;
+    fs::rename(&tmp_path, snapshot_path).await?;
+
+    Ok(())
+  }
+
+  // 同一 ID 的消息只会入队一次，返回是否实际入队。
+  // 启用快照时会立即落盘，落盘失败则撤销入队并返回错误
+  pub async fn enqueue(&self, message: MessageItem) -> Result<bool> {
+    let id = message.id.clone();
+    {
+      let mut queue = self.queue.write().await;
+      if queue.iter().any(|item| item.id == id) {
+        log::info(format!("Message {} already queued, skipping.", id));
+        return Ok(false);
+      }
+
+      log::info(format!(
+        "Enqueued message: {} (retry_count={})",
+        id, message.retry_count
+      ));
+      queue.push_back(message);
+    }
+
+    if self.snapshot_interval.is_some()
+      && let Err(e) = Self::write_snapshot(
+        &self.queue,
+        &self.persist_lock,
+        &self.snapshot_path,
+        self.cipher.as_deref(),
+      )
+      .await
+    {
+      self.queue.write().await.retain(|item| item.id != id);
+      return Err(e);
+    }
+
+    if let Some(journal) = &self.journal
+      && let Some(item) = self.queue.read().await.iter().find(|item| item.id == id)
+    {
+      journal.scheduled(item);
+    }
+
+    Ok(true)
+  }
+
+  // 重放日志时使用：不再写回日志，也不立即落盘，由随后的检查点统一保存
+  pub async fn restore(&self, message: MessageItem) {
     let mut queue = self.queue.write().await;
-    queue.push_back(message.clone());
-    log::info(format!(
-      "Enqueued message: {} (retry_count={})",
-      message.id, message.retry_count
-    ));
+    if !queue.iter().any(|item| item.id == message.id) {
+      queue.push_back(message.stamped(self.clock.now()));
+    }
   }
 
-  pub async fn retrying(&self, ctx: Arc<Context>) {
-    let queue = Arc::clone(&self.queue);
-    let messenger = Arc::clone(&self.messenger);
-    let persist_path = self.persist_path.clone();
-    let persist_lock = Arc::clone(&self.persist_lock);
-    let shutdown_token = self.shutdown_token.clone();
+  // 移除日志中记录为已送达、但仍残留在旧快照里的消息
+  pub async fn forget(&self, delivered: &HashSet<String>) {
+    self
+      .queue
+      .write()
+      .await
+      .retain(|item| !delivered.contains(&item.id));
+  }
 
-    let handle = tokio::spawn(async move {
-      log::info("Message queue retry loop started.");
-
-      loop {
-        tokio::select! {
-          _ = shutdown_token.cancelled() => {
-            log::info("Retry loop received shutdown signal, exiting...");
-            break;
-          }
-          _ = sleep(Duration::from_secs(1)) => {
-          }
-        }
+  // 无论是否启用定期快照都立即写一次快照，之后才能清空日志
+  pub async fn checkpoint(&self) -> Result<()> {
+    Self::write_snapshot(
+      &self.queue,
+      &self.persist_lock,
+      &self.snapshot_path,
+      self.cipher.as_deref(),
+    )
+    .await
+  }
 
-        // use read lock
-        let items_to_retry: Vec<MessageItem> = {
-          let queue_guard = queue.read().await;
-          queue_guard
-            .iter()
-            .filter(|item| item.can_retry())
-            .cloned()
-            .collect()
-        };
-        // lock released
-
-        if items_to_retry.is_empty() {
-          continue;
-        }
+  pub async fn contains(&self, id: &str) -> bool {
+    self.queue.read().await.iter().any(|item| item.id == id)
+  }
 
-        let mut send_results = Vec::new();
-        for item in items_to_retry {
-          let embed = create_embed(
-            &item.notice,
-            item.notice_type.clone(),
-            item.match_name.as_deref(),
-            item.match_id,
-            &item.base_url,
-          );
-
-          let result = messenger.send_embed(&ctx, embed).await;
-          send_results.push((item.id.clone(), result));
-        }
+  pub async fn len(&self) -> usize {
+    self.queue.read().await.len()
+  }
 
-        // use write lock
-        let mut to_persist = Vec::new();
-        let mut remove_persist_succ = Vec::new();
-        let mut remove_retry_succ = Vec::new();
+  // 等待到期的重试被处理完，最多等 limit；一次性模式退出前使用
+  pub async fn wait_idle(&self, limit: Duration) {
+    let deadline = Instant::now() + limit;
+    while Instant::now() < deadline
+      && self
+        .queue
+        .read()
+        .await
+        .iter()
+        .any(|item| item.can_retry(self.clock.now()))
+    {
+      sleep(Duration::from_secs(1)).await;
+    }
+  }
 
-        {
-          let mut queue_guard = queue.write().await;
-
-          for (msg_id, result) in send_results {
-            if let Some(item) = queue_guard.iter_mut().find(|i| i.id == msg_id) {
-              match result {
-                Ok(_) => {
-                  log::success(format!("Retry succeeded for message: {}", item.id));
-                  remove_retry_succ.push(item.id.clone());
-                }
-                Err(e) => {
-                  log::error(format!("Retry failed for message {}: {}", item.id, e));
-
-                  if item.should_persist() {
-                    log::info(format!(
-                      "Message {} exceeded max retries. Persisting to disk.",
-                      item.id
-                    ));
-                    to_persist.push(item.clone());
-                    remove_persist_succ.push(item.id.clone());
-                  } else {
-                    item.increment_retry();
-                    let delay = item.calc_delay();
-                    log::info(format!(
-                      "Message {} will retry in {}s (retry_count={})",
-                      item.id, delay, item.retry_count
-                    ));
-                  }
-                }
-              }
-            }
-          }
+  pub async fn metrics(&self) -> QueueMetrics {
+    let queue = self.queue.read().await;
+    let now = self.clock.now();
+    QueueMetrics {
+      depth: queue.len(),
+      retries: self.counters.retries.load(Ordering::Relaxed),
+      persisted: self.counters.persisted.load(Ordering::Relaxed),
+      oldest_age: queue
+        .iter()
+        .map(|item| item.enqueued_at)
+        .min()
+        .map(|at| Duration::from_secs(now.saturating_sub(at))),
+    }
+  }
+
+  // 按入队顺序排列
+  pub async fn entries(&self) -> Vec<QueueEntry> {
+    let now = self.clock.now();
+    self
+      .queue
+      .read()
+      .await
+      .iter()
+      .map(|item| QueueEntry {
+        id: item.id.clone(),
+        match_id: item.match_id,
+        notice_type: item.notice_type.clone(),
+        channel_id: item.channel_id,
+        retry_count: item.retry_count,
+        age: Duration::from_secs(now.saturating_sub(item.enqueued_at)),
+        retry_in: Duration::from_secs(item.next_retry_at.saturating_sub(now)),
+      })
+      .collect()
+  }
+
+  // 重试循环 panic 时由 tasks::supervise 重启并在管理频道告警；
+  // 网关重连再次调用时先中止旧的循环，同一时间只有一个重试循环
+  pub async fn retrying(&self, ctx: Arc<Context>, alerts: Arc<AlertManager>) {
+    let worker = self.worker(Arc::new(ContextMessenger::new(
+      Arc::clone(&ctx),
+      Arc::clone(&self.messenger),
+    )));
+    let shutdown_token = self.shutdown_token.clone();
+    let snapshot_path = self.snapshot_path.clone();
+    let snapshot_interval = self.snapshot_interval;
 
-          queue_guard.retain(|item| !remove_retry_succ.contains(&item.id));
+    let mut retry_handle = self.retry_handle.lock().await;
+    if let Some(previous) = retry_handle.take() {
+      previous.abort();
+    }
+    let handle = tasks::supervise("retry", Some((ctx, alerts)), move || {
+      let worker = worker.clone();
+      let shutdown_token = shutdown_token.clone();
+      let snapshot_path = snapshot_path.clone();
+      Self::retry_loop(worker, shutdown_token, snapshot_path, snapshot_interval)
+    });
+    *retry_handle = Some(handle);
+  }
+
+  async fn retry_loop(
+    worker: RetryWorker,
+    shutdown_token: CancellationToken,
+    snapshot_path: String,
+    snapshot_interval: Option<Duration>,
+  ) {
+    log::info("Message queue retry loop started.");
+    tasks::started("retry");
+    let mut last_snapshot = Instant::now();
+
+    loop {
+      tokio::select! {
+        _ = shutdown_token.cancelled() => {
+          log::info("Retry loop received shutdown signal, exiting...");
+          break;
+        }
+        _ = sleep(Duration::from_secs(1)) => {
         }
-        // lock released
+      }
 
-        if !to_persist.is_empty() {
-          match Self::append_to_disk(&persist_lock, &persist_path, &to_persist).await {
-            Ok(_) => {
-              // can be removed only if persisted successfully
-              let mut queue_guard = queue.write().await;
-              queue_guard.retain(|item| !remove_persist_succ.contains(&item.id));
-              log::info(format!(
-                "Removed {} persisted messages from queue.",
-                remove_persist_succ.len()
-              ));
-            }
-            Err(e) => {
-              log::error(format!("Failed to persist messages to disk: {}", e));
-              log::info("Messages will remain in queue for retry.");
-            }
-          }
+      if let Some(interval) = snapshot_interval
+        && last_snapshot.elapsed() >= interval
+      {
+        if let Err(e) = Self::write_snapshot(
+          &worker.queue,
+          &worker.persist_lock,
+          &snapshot_path,
+          worker.cipher.as_deref(),
+        )
+        .await
+        {
+          log::error(format!("Failed to snapshot message queue: {}", e));
         }
+        last_snapshot = Instant::now();
       }
 
-      log::info("Retry loop finished.");
-    });
+      tasks::touch("retry");
+      // 维护期间消息留在队列中，结束后继续重试
+      if worker.delivery.maintenance().is_active() {
+        continue;
+      }
 
-    let mut retry_handle = self.retry_handle.lock().await;
-    *retry_handle = Some(handle);
+      worker.retry_due().await;
+    }
+
+    tasks::finished("retry");
+    log::info("Retry loop finished.");
   }
 
-  pub async fn shutdown(&self) -> Result<()> {
+  fn worker(&self, messenger: Arc<dyn Messenger>) -> RetryWorker {
+    RetryWorker {
+      queue: Arc::clone(&self.queue),
+      messenger,
+      persist_path: self.persist_path.clone(),
+      persist_lock: Arc::clone(&self.persist_lock),
+      counters: Arc::clone(&self.counters),
+      cipher: self.cipher.clone(),
+      journal: self.journal.clone(),
+      clock: Arc::clone(&self.clock),
+      delivery: Arc::clone(self.messenger.state()),
+    }
+  }
+
+  // 最多等待重试循环 limit 时长，超时则中止它，队列中剩余的消息照常写入磁盘
+  pub async fn shutdown(&self, limit: Duration) -> Result<()> {
     log::info("Shutting down message queue...");
 
     self.shutdown_token.cancel();
@@ -260,10 +545,19 @@ impl MessageQueue {
       retry_handle.take()
     };
 
-    if let Some(h) = handle {
+    if let Some(mut h) = handle {
       log::info("Waiting for retry loop to finish...");
-      if let Err(e) = h.await {
-        log::error(format!("Error waiting for retry loop: {}", e));
+      match timeout(limit, &mut h).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => log::error(format!("Error waiting for retry loop: {}", e)),
+        Err(_) => {
+          log::warn(format!(
+            "Retry loop did not finish within {}s, aborting it",
+            limit.as_secs()
+          ));
+          h.abort();
+          let _ = h.await;
+        }
       }
     }
 
@@ -273,14 +567,24 @@ impl MessageQueue {
 
     if remaining_items.is_empty() {
       log::info("No pending messages to save.");
-      return Ok(());
+    } else {
+      Self::append_to_disk(
+        &self.persist_lock,
+        &self.persist_path,
+        &remaining_items,
+        self.cipher.as_deref(),
+      )
+      .await?;
+      log::success(format!(
+        "Saved {} pending messages before shutdown.",
+        remaining_items.len()
+      ));
     }
 
-    Self::append_to_disk(&self.persist_lock, &self.persist_path, &remaining_items).await?;
-    log::success(format!(
-      "Saved {} pending messages before shutdown.",
-      remaining_items.len()
-    ));
+    // remaining items now live in the persist file, the snapshot is redundant
+    if fs::try_exists(&self.snapshot_path).await.unwrap_or(false) {
+      fs::remove_file(&self.snapshot_path).await?;
+    }
 
     Ok(())
   }
@@ -289,6 +593,7 @@ impl MessageQueue {
     persist_lock: &Mutex<()>,
     persist_path: &str,
     items: &[MessageItem],
+    cipher: Option<&Cipher>,
   ) -> Result<()> {
     if items.is_empty() {
       return Ok(());
@@ -299,16 +604,17 @@ impl MessageQueue {
     let path = Path::new(persist_path);
 
     let mut existing_items: Vec<MessageItem> = if path.exists() {
-      let content = fs::read_to_string(path).await?;
-      serde_json::from_str(&content).unwrap_or_default()
+      // 解密失败时不能覆盖原文件，否则会丢掉其中的消息
+      let content = crypto::open(cipher, fs::read(path).await?)?;
+      serde_json::from_slice(&content).unwrap_or_default()
     } else {
       Vec::new()
     };
 
     existing_items.extend_from_slice(items);
 
-    let json = serde_json::to_string_pretty(&existing_items)?;
-    fs::write(path, json).await?;
+    let data = crypto::seal(cipher, serde_json::to_vec_pretty(&existing_items)?)?;
+    fs::write(path, data).await?;
 
     log::info(format!(
       "Appended {} messages to persist file.",
@@ -317,3 +623,486 @@ impl MessageQueue {
     Ok(())
   }
 }
+
+// 重试循环每一轮用到的状态，从队列中克隆出来交给后台任务；测试中直接驱动单轮重试
+#[derive(Clone)]
+struct RetryWorker {
+  queue: Arc<RwLock<VecDeque<MessageItem>>>,
+  messenger: Arc<dyn Messenger>,
+  persist_path: String,
+  persist_lock: Arc<Mutex<()>>,
+  counters: Arc<QueueCounters>,
+  cipher: Option<Arc<Cipher>>,
+  journal: Option<Arc<Journal>>,
+  clock: Arc<dyn Clock>,
+  delivery: Arc<DeliveryState>,
+}
+
+impl RetryWorker {
+  // 同一频道的消息保持原有顺序依次发送，不同频道之间并发
+  fn lanes(&self, items: Vec<MessageItem>) -> Vec<Vec<MessageItem>> {
+    let mut lanes: Vec<Vec<MessageItem>> = Vec::new();
+    let mut lane_of: HashMap<u64, usize> = HashMap::new();
+    for item in items {
+      let channel = self.messenger.resolve_channel(item.channel_id);
+      let lane = *lane_of.entry(channel).or_insert_with(|| {
+        lanes.push(Vec::new());
+        lanes.len() - 1
+      });
+      lanes[lane].push(item);
+    }
+    lanes
+  }
+
+  async fn send_lane(&self, items: Vec<MessageItem>) -> Vec<(String, Result<()>)> {
+    let mut results = Vec::with_capacity(items.len());
+    for item in items {
+      let send = self.messenger.send(
+        item.channel_id,
+        item.content.as_deref(),
+        &item.mentions,
+        item.render(),
+      );
+      let result = log::correlate(item.correlation(), send).await;
+      results.push((item.id, result));
+    }
+    results
+  }
+
+  async fn retry_due(&self) {
+    let now = self.clock.now();
+
+    // use read lock
+    // 缺少权限的频道在探测到期前不发送，消息原样保留，不消耗重试次数
+    let items_to_retry: Vec<MessageItem> = {
+      let queue_guard = self.queue.read().await;
+      fair_order(
+        queue_guard
+          .iter()
+          .filter(|item| {
+            item.can_retry(now)
+              && !self
+                .delivery
+                .is_degraded(self.messenger.resolve_channel(item.channel_id))
+          })
+          .cloned()
+          .collect(),
+      )
+    };
+    // lock released
+
+    if items_to_retry.is_empty() {
+      return;
+    }
+
+    self
+      .counters
+      .retries
+      .fetch_add(items_to_retry.len() as u64, Ordering::Relaxed);
+    let send_results: Vec<(String, Result<()>)> = stream::iter(self.lanes(items_to_retry))
+      .map(|lane| self.send_lane(lane))
+      .buffer_unordered(RETRY_CONCURRENCY)
+      .collect::<Vec<_>>()
+      .await
+      .into_iter()
+      .flatten()
+      .collect();
+
+    // use write lock
+    let mut to_persist = Vec::new();
+    let mut remove_persist_succ = Vec::new();
+    let mut remove_retry_succ = Vec::new();
+
+    {
+      let mut queue_guard = self.queue.write().await;
+
+      for (msg_id, result) in send_results {
+        if let Some(item) = queue_guard.iter_mut().find(|i| i.id == msg_id) {
+          let correlation = item.correlation().to_string();
+          log::correlate_sync(&correlation, || match result {
+            Ok(_) => {
+              log::success(format!("Retry succeeded for message: {}", item.id));
+              remove_retry_succ.push(item.id.clone());
+            }
+            Err(e) => {
+              log::error(format!("Retry failed for message {}: {}", item.id, e));
+
+              if self
+                .delivery
+                .is_degraded(self.messenger.resolve_channel(item.channel_id))
+              {
+                log::info(format!(
+                  "Message {} is held until its channel is reachable again",
+                  item.id
+                ));
+              } else if item.should_persist() {
+                log::info(format!(
+                  "Message {} exceeded max retries. Persisting to disk.",
+                  item.id
+                ));
+                to_persist.push(item.clone());
+                remove_persist_succ.push(item.id.clone());
+              } else {
+                item.increment_retry(now);
+                let delay = item.calc_delay();
+                log::info(format!(
+                  "Message {} will retry in {}s (retry_count={})",
+                  item.id, delay, item.retry_count
+                ));
+              }
+            }
+          });
+        }
+      }
+
+      queue_guard.retain(|item| !remove_retry_succ.contains(&item.id));
+    }
+
+    if let Some(journal) = &self.journal {
+      for id in &remove_retry_succ {
+        journal.delivered(id);
+      }
+    }
+    // lock released
+
+    if !to_persist.is_empty() {
+      match MessageQueue::append_to_disk(
+        &self.persist_lock,
+        &self.persist_path,
+        &to_persist,
+        self.cipher.as_deref(),
+      )
+      .await
+      {
+        Ok(_) => {
+          // can be removed only if persisted successfully
+          self
+            .counters
+            .persisted
+            .fetch_add(to_persist.len() as u64, Ordering::Relaxed);
+          let mut queue_guard = self.queue.write().await;
+          queue_guard.retain(|item| !remove_persist_succ.contains(&item.id));
+          log::info(format!(
+            "Removed {} persisted messages from queue.",
+            remove_persist_succ.len()
+          ));
+        }
+        Err(e) => {
+          log::error(format!("Failed to persist messages to disk: {}", e));
+          log::info("Messages will remain in queue for retry.");
+        }
+      }
+    }
+  }
+}
+
+// 先按公告类型优先级，同一优先级内在各比赛之间轮流发送，
+// 避免一场比赛积压的血榜排在其他比赛的公告前面
+fn fair_order(items: Vec<MessageItem>) -> Vec<MessageItem> {
+  let mut ranks: HashMap<(u8, u32), usize> = HashMap::new();
+  let mut keyed: Vec<((u8, usize), MessageItem)> = items
+    .into_iter()
+    .map(|item| {
+      let priority = item.notice_type.priority();
+      let rank = ranks.entry((priority, item.match_id)).or_default();
+      let key = (priority, *rank);
+      *rank += 1;
+      (key, item)
+    })
+    .collect();
+  // 稳定排序，同一轮次内保持入队顺序
+  keyed.sort_by_key(|(key, _)| *key);
+  keyed.into_iter().map(|(_, item)| item).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::clock::MockClock;
+  use serenity::async_trait;
+  use std::sync::atomic::AtomicBool;
+
+  // 记录每次发送的纯文本内容，failing 为真时全部失败
+  #[derive(Default)]
+  struct MockMessenger {
+    failing: AtomicBool,
+    sent: std::sync::Mutex<Vec<String>>,
+  }
+
+  impl MockMessenger {
+    fn failing() -> Self {
+      Self {
+        failing: AtomicBool::new(true),
+        ..Self::default()
+      }
+    }
+
+    fn sent(&self) -> Vec<String> {
+      self.sent.lock().unwrap().clone()
+    }
+  }
+
+  #[async_trait]
+  impl Messenger for MockMessenger {
+    async fn send(
+      &self,
+      _channel_id: Option<u64>,
+      content: Option<&str>,
+      _mentions: &[u64],
+      _embed: CreateEmbed,
+    ) -> Result<()> {
+      self
+        .sent
+        .lock()
+        .unwrap()
+        .push(content.unwrap_or_default().to_string());
+      if self.failing.load(Ordering::SeqCst) {
+        return Err(anyhow::anyhow!("mock send failure"));
+      }
+      Ok(())
+    }
+
+    fn resolve_channel(&self, channel_id: Option<u64>) -> u64 {
+      channel_id.unwrap_or(0)
+    }
+  }
+
+  fn test_queue() -> MessageQueue {
+    MessageQueue::new(
+      "test_failed_messages.json".to_string(),
+      0,
+      Arc::new(DiscordMessenger::new(0, Arc::default())),
+    )
+  }
+
+  // 每个测试使用独立的持久化文件，结束时清理
+  struct TempPersist(String);
+
+  impl TempPersist {
+    fn new(name: &str) -> Self {
+      let path = std::env::temp_dir().join(format!("dc-bot-{}-{}.json", name, std::process::id()));
+      let persist = Self(path.to_string_lossy().into_owned());
+      persist.cleanup();
+      persist
+    }
+
+    fn queue(&self, clock: &Arc<MockClock>) -> MessageQueue {
+      MessageQueue::new(
+        self.0.clone(),
+        0,
+        Arc::new(DiscordMessenger::new(0, Arc::default())),
+      )
+      .with_clock(Arc::clone(clock) as Arc<dyn Clock>)
+    }
+
+    fn cleanup(&self) {
+      for path in [self.0.clone(), format!("{}.snapshot", self.0)] {
+        let _ = std::fs::remove_file(path);
+      }
+    }
+  }
+
+  impl Drop for TempPersist {
+    fn drop(&mut self) {
+      self.cleanup();
+    }
+  }
+
+  fn test_item(id: &str) -> MessageItem {
+    let notice = Notice {
+      id: 1,
+      notice_type: "Normal".to_string(),
+      values: vec!["hello".to_string()],
+      time: 1_700_000_000_000,
+    };
+    MessageItem::new(
+      id.to_string(),
+      notice,
+      NoticeType::Normal,
+      None,
+      1,
+      "https://example.com".to_string(),
+      0,
+    )
+  }
+
+  #[tokio::test]
+  async fn enqueue_is_idempotent_by_id() {
+    let queue = test_queue();
+
+    assert!(queue.enqueue(test_item("1:1:1")).await.unwrap());
+    assert!(!queue.enqueue(test_item("1:1:1")).await.unwrap());
+    assert!(queue.enqueue(test_item("1:2:2")).await.unwrap());
+
+    assert_eq!(queue.len().await, 2);
+    assert!(queue.contains("1:1:1").await);
+    assert!(!queue.contains("1:3:3").await);
+  }
+
+  fn content_item(id: &str) -> MessageItem {
+    test_item(id).with_content(Some(id.to_string()))
+  }
+
+  #[tokio::test]
+  async fn retry_removes_delivered_messages() {
+    let persist = TempPersist::new("retry-ok");
+    let clock = Arc::new(MockClock::new());
+    let queue = persist.queue(&clock);
+    let messenger = Arc::new(MockMessenger::default());
+
+    queue.enqueue(content_item("1:1:1")).await.unwrap();
+    queue.worker(messenger.clone()).retry_due().await;
+
+    assert_eq!(messenger.sent(), ["1:1:1"]);
+    assert_eq!(queue.len().await, 0);
+  }
+
+  #[tokio::test]
+  async fn retry_keeps_order_within_each_channel() {
+    let persist = TempPersist::new("retry-lanes");
+    let clock = Arc::new(MockClock::new());
+    let queue = persist.queue(&clock);
+    let messenger = Arc::new(MockMessenger::default());
+
+    for (id, channel) in [
+      ("a1", 1),
+      ("b1", 2),
+      ("a2", 1),
+      ("c1", 3),
+      ("b2", 2),
+      ("a3", 1),
+    ] {
+      queue
+        .enqueue(content_item(id).with_channel(channel))
+        .await
+        .unwrap();
+    }
+    queue.worker(messenger.clone()).retry_due().await;
+
+    let sent = messenger.sent();
+    assert_eq!(sent.len(), 6);
+    for lane in ["a", "b", "c"] {
+      let order: Vec<_> = sent.iter().filter(|id| id.starts_with(lane)).collect();
+      let mut sorted = order.clone();
+      sorted.sort();
+      assert_eq!(order, sorted);
+    }
+    assert_eq!(queue.len().await, 0);
+  }
+
+  #[tokio::test]
+  async fn failed_retry_waits_for_backoff() {
+    let persist = TempPersist::new("retry-backoff");
+    let clock = Arc::new(MockClock::new());
+    let queue = persist.queue(&clock);
+    let messenger = Arc::new(MockMessenger::failing());
+    let worker = queue.worker(messenger.clone());
+
+    queue.enqueue(content_item("1:1:1")).await.unwrap();
+    worker.retry_due().await;
+    assert_eq!(messenger.sent().len(), 1);
+
+    // 第一次失败后等待 2^2 秒
+    worker.retry_due().await;
+    clock.advance(3);
+    worker.retry_due().await;
+    assert_eq!(messenger.sent().len(), 1);
+
+    clock.advance(1);
+    worker.retry_due().await;
+    assert_eq!(messenger.sent().len(), 2);
+    assert_eq!(queue.queue.read().await[0].retry_count, 2);
+  }
+
+  #[tokio::test]
+  async fn exhausted_messages_are_persisted() {
+    let persist = TempPersist::new("retry-persist");
+    let clock = Arc::new(MockClock::new());
+    let queue = persist.queue(&clock);
+
+    let mut item = content_item("1:1:1");
+    item.retry_count = 4;
+    queue.enqueue(item).await.unwrap();
+    queue
+      .worker(Arc::new(MockMessenger::failing()))
+      .retry_due()
+      .await;
+
+    assert_eq!(queue.len().await, 0);
+    assert_eq!(queue.metrics().await.persisted, 1);
+
+    let reloaded = persist.queue(&clock);
+    reloaded.load_from_disk().await.unwrap();
+    assert!(reloaded.contains("1:1:1").await);
+  }
+
+  #[tokio::test]
+  async fn shutdown_saves_pending_messages() {
+    let persist = TempPersist::new("shutdown");
+    let clock = Arc::new(MockClock::new());
+    let queue = persist.queue(&clock);
+
+    queue.enqueue(content_item("1:1:1")).await.unwrap();
+    queue.enqueue(content_item("1:2:2")).await.unwrap();
+    queue.shutdown(Duration::from_secs(1)).await.unwrap();
+
+    let reloaded = persist.queue(&clock);
+    reloaded.load_from_disk().await.unwrap();
+    assert_eq!(reloaded.len().await, 2);
+  }
+
+  #[tokio::test]
+  async fn legacy_messages_are_stamped_with_queue_clock() {
+    let persist = TempPersist::new("legacy-stamp");
+    let clock = Arc::new(MockClock::new());
+    let queue = persist.queue(&clock);
+
+    // enqueued_at 为 0 与旧版本缺少该字段时的反序列化结果相同
+    queue.enqueue(content_item("1:1:1")).await.unwrap();
+    queue.shutdown(Duration::from_secs(1)).await.unwrap();
+
+    let reloaded = persist.queue(&clock);
+    reloaded.load_from_disk().await.unwrap();
+    clock.advance(5);
+    assert_eq!(
+      reloaded.metrics().await.oldest_age,
+      Some(Duration::from_secs(5))
+    );
+  }
+
+  #[tokio::test]
+  async fn shutdown_aborts_stuck_retry_loop() {
+    let persist = TempPersist::new("shutdown-stuck");
+    let clock = Arc::new(MockClock::new());
+    let queue = persist.queue(&clock);
+
+    queue.enqueue(content_item("1:1:1")).await.unwrap();
+    // 模拟卡在发送中的重试循环
+    *queue.retry_handle.lock().await = Some(tokio::spawn(std::future::pending()));
+    queue.shutdown(Duration::from_millis(50)).await.unwrap();
+
+    let reloaded = persist.queue(&clock);
+    reloaded.load_from_disk().await.unwrap();
+    assert_eq!(reloaded.len().await, 1);
+  }
+
+  #[test]
+  fn fair_order_interleaves_matches_by_priority() {
+    let item = |id: &str, match_id: u32, notice_type: NoticeType| {
+      let mut item = test_item(id);
+      item.match_id = match_id;
+      item.notice_type = notice_type;
+      item
+    };
+    let items = vec![
+      item("a1", 1, NoticeType::FirstBlood),
+      item("a2", 1, NoticeType::FirstBlood),
+      item("a3", 1, NoticeType::FirstBlood),
+      item("b1", 2, NoticeType::FirstBlood),
+      item("b2", 2, NoticeType::Normal),
+    ];
+
+    let ids: Vec<String> = fair_order(items).into_iter().map(|i| i.id).collect();
+    assert_eq!(ids, ["b2", "a1", "b1", "a2", "a3"]);
+  }
+}
@@ -0,0 +1,65 @@
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::RwLock;
+
+use crate::models::{Notice, NoticeType};
+
+#[derive(Debug, Clone)]
+pub struct RecentNotice {
+  pub match_name: Option<String>,
+  pub notice_type: NoticeType,
+  pub notice: Notice,
+}
+
+// 每场比赛最近转发的公告，供 /last 重新展示
+pub struct RecentNotices {
+  capacity: usize,
+  per_match: RwLock<HashMap<u32, VecDeque<RecentNotice>>>,
+}
+
+impl RecentNotices {
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      per_match: RwLock::new(HashMap::new()),
+    }
+  }
+
+  pub async fn record(&self, match_id: u32, entry: RecentNotice) {
+    if self.capacity == 0 {
+      return;
+    }
+
+    let mut per_match = self.per_match.write().await;
+    let notices = per_match.entry(match_id).or_default();
+    notices.push_back(entry);
+    while notices.len() > self.capacity {
+      notices.pop_front();
+    }
+  }
+
+  // 返回最近 count 条，按时间先后排列
+  pub async fn last(&self, match_id: u32, count: usize) -> Vec<RecentNotice> {
+    self
+      .per_match
+      .read()
+      .await
+      .get(&match_id)
+      .map(|notices| {
+        let skip = notices.len().saturating_sub(count);
+        notices.iter().skip(skip).cloned().collect()
+      })
+      .unwrap_or_default()
+  }
+
+  // 所有比赛中最近的 count 条，最新的在前
+  pub async fn latest(&self, count: usize) -> Vec<(u32, RecentNotice)> {
+    let per_match = self.per_match.read().await;
+    let mut all: Vec<(u32, RecentNotice)> = per_match
+      .iter()
+      .flat_map(|(match_id, notices)| notices.iter().map(|n| (*match_id, n.clone())))
+      .collect();
+    all.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.notice.time));
+    all.truncate(count);
+    all
+  }
+}
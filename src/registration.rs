@@ -0,0 +1,164 @@
+use chrono::{DateTime, Utc};
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+use serenity::model::colour::Colour;
+use serenity::prelude::Context;
+use std::sync::Arc;
+use tokio::time::Duration;
+
+use crate::config::{Config, MatchConfig};
+use crate::countdown::format_remaining;
+use crate::discord::{DeliveryState, DiscordMessenger};
+use crate::gzctf::{format_time, game_url};
+use crate::limiter::RequestLimiter;
+use crate::log;
+use crate::platform;
+use crate::scheduler::{self, Scheduler};
+
+struct Reminder {
+  channels: Vec<u64>,
+  match_name: String,
+  game_url: String,
+  deadline: DateTime<Utc>,
+  remaining: Duration,
+}
+
+// 为每场比赛的报名截止时间安排提醒：优先使用比赛配置的 registration_deadline，
+// 否则以比赛开始时间作为截止时间
+pub fn start(
+  ctx: Arc<Context>,
+  config: Arc<Config>,
+  scheduler: Arc<Scheduler>,
+  delivery: Arc<DeliveryState>,
+  limiter: Arc<RequestLimiter>,
+) {
+  let Some(registration) = config.registration.clone() else {
+    return;
+  };
+
+  let offsets: Vec<Duration> = registration
+    .offsets
+    .iter()
+    .filter_map(|raw| {
+      let offset = scheduler::parse_offset(raw);
+      if offset.is_none() {
+        log::error(format!(
+          "Ignoring invalid registration reminder offset '{}'",
+          raw
+        ));
+      }
+      offset
+    })
+    .collect();
+
+  tokio::spawn(async move {
+    let client = match platform::connect(&config.gzctf, &limiter) {
+      Ok(client) => client,
+      Err(e) => {
+        log::error(format!(
+          "Failed to create client for registration reminders: {}",
+          e
+        ));
+        return;
+      }
+    };
+    let messenger = Arc::new(DiscordMessenger::new(config.discord.channel_id, delivery));
+    for match_config in config.get_matches() {
+      let game = match client.fetch_game(match_config.id).await {
+        Ok(game) => game,
+        Err(e) => {
+          log::error(format!(
+            "Failed to fetch game info for match {}: {}",
+            match_config.id, e
+          ));
+          continue;
+        }
+      };
+      let Some(deadline) = deadline(&match_config, game.start) else {
+        continue;
+      };
+
+      let channels = match registration.channel_id {
+        Some(channel_id) => vec![channel_id],
+        None => config.broadcast_channels(match_config.id),
+      };
+
+      let mut scheduled = 0;
+      for offset in &offsets {
+        let at = deadline - *offset;
+        if at <= scheduler.now() {
+          continue;
+        }
+
+        let reminder = Reminder {
+          channels: channels.clone(),
+          match_name: match_config.name.clone().unwrap_or(game.title.clone()),
+          game_url: game_url(&config.gzctf.url, match_config.id, config.gzctf.platform),
+          deadline,
+          remaining: *offset,
+        };
+        let key = format!("registration:{}:{}", match_config.id, offset.as_secs());
+        let task = run(Arc::clone(&ctx), Arc::clone(&messenger), reminder);
+        if scheduler.schedule_at(key, at, task) {
+          scheduled += 1;
+        }
+      }
+      if scheduled > 0 {
+        log::info(format!(
+          "Scheduled {} registration reminder(s) for match {} closing at {}",
+          scheduled,
+          match_config.id,
+          format_time(deadline.timestamp_millis() as u64)
+        ));
+      }
+    }
+  });
+}
+
+fn deadline(match_config: &MatchConfig, start: u64) -> Option<DateTime<Utc>> {
+  match &match_config.registration_deadline {
+    Some(raw) => DateTime::parse_from_rfc3339(raw)
+      .ok()
+      .map(|t| t.with_timezone(&Utc)),
+    None => DateTime::from_timestamp_millis(start as i64),
+  }
+}
+
+async fn run(ctx: Arc<Context>, messenger: Arc<DiscordMessenger>, reminder: Reminder) {
+  let embed = reminder_embed(&reminder);
+  for channel_id in &reminder.channels {
+    if messenger
+      .send_embed_to(&ctx, *channel_id, None, embed.clone())
+      .await
+      .is_ok()
+    {
+      log::success(format!(
+        "Posted registration reminder for {} to channel {}",
+        reminder.match_name, channel_id
+      ));
+    }
+  }
+}
+
+fn reminder_embed(reminder: &Reminder) -> CreateEmbed {
+  let remaining = chrono::Duration::from_std(reminder.remaining).unwrap_or_default();
+  let (title, text) = if remaining.is_zero() {
+    ("**报名已截止**", "报名已经截止，感谢各位的关注！")
+  } else {
+    ("**报名即将截止**", "还没有组队报名的队伍请尽快完成报名！")
+  };
+
+  let mut embed = CreateEmbed::new()
+    .title(title)
+    .color(Colour::from_rgb(234, 179, 8))
+    .description(format!(
+      "**赛事:** [{}]({})\n{}",
+      reminder.match_name, reminder.game_url, text
+    ));
+  if !remaining.is_zero() {
+    embed = embed.field("距离截止", format_remaining(remaining), false);
+  }
+  embed.footer(CreateEmbedFooter::new(format!(
+    "报名截止 {}",
+    format_time(reminder.deadline.timestamp_millis() as u64)
+  )))
+}
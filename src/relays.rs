@@ -0,0 +1,130 @@
+use anyhow::Result;
+use serenity::async_trait;
+use tokio::time::{Duration, timeout};
+
+use crate::config::{Config, MatchConfig};
+use crate::gzctf::{format_time, game_url};
+use crate::log;
+use crate::mail::MailRelay;
+use crate::models::{Notice, NoticeType};
+use crate::onebot::OneBotRelay;
+use crate::webhook::WebhookRelay;
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(15);
+
+// 与 embed 内容一致的纯文本版本，供 Discord 以外的目标使用
+pub struct PlainNotice {
+  pub notice_type: NoticeType,
+  pub title: String,
+  pub body: String,
+}
+
+impl PlainNotice {
+  pub fn new(
+    config: &Config,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    notice: &Notice,
+  ) -> Self {
+    let match_name = match_config
+      .name
+      .clone()
+      .unwrap_or_else(|| format!("比赛 {}", match_config.id));
+    let type_title = notice_type.get_title().trim_matches('*');
+    let content = match notice_type {
+      NoticeType::Normal => notice.values.join("\n\n"),
+      NoticeType::NewChallenge | NoticeType::NewHint => {
+        format!(
+          "题目: {}",
+          notice.values.first().cloned().unwrap_or_default()
+        )
+      }
+      NoticeType::FirstBlood | NoticeType::SecondBlood | NoticeType::ThirdBlood => {
+        let team = notice.values.first().cloned().unwrap_or_default();
+        let team = match notice.values.get(2) {
+          Some(gain) => format!("{} ({})", team, gain),
+          None => team,
+        };
+        format!(
+          "队伍: {}\n题目: {}",
+          team,
+          notice.values.get(1).cloned().unwrap_or_default()
+        )
+      }
+    };
+
+    Self {
+      notice_type: notice_type.clone(),
+      title: format!("[{}] {}", match_name, type_title),
+      body: format!(
+        "{}\n\n赛事链接: {}\n发布时间: {}",
+        content,
+        game_url(&config.gzctf.url, match_config.id, config.gzctf.platform),
+        format_time(notice.time)
+      ),
+    }
+  }
+}
+
+// Discord 以外的转发目标（邮件、IM webhook 等），尽力而为，失败不重试
+#[async_trait]
+pub trait Relay: Send + Sync {
+  fn name(&self) -> &str;
+  fn wants(&self, notice_type: &NoticeType) -> bool;
+  async fn send(&self, notice: &PlainNotice) -> Result<()>;
+}
+
+pub struct Relays {
+  relays: Vec<Box<dyn Relay>>,
+}
+
+impl Relays {
+  pub fn from_config(config: &Config) -> Result<Self> {
+    let mut relays: Vec<Box<dyn Relay>> = Vec::new();
+    if let Some(mail) = &config.mail {
+      relays.push(Box::new(MailRelay::new(mail.clone())?));
+    }
+    if let Some(onebot) = &config.onebot {
+      relays.push(Box::new(OneBotRelay::new(onebot.clone())?));
+    }
+    for webhook in &config.webhooks {
+      relays.push(Box::new(WebhookRelay::new(webhook.clone())?));
+    }
+    Ok(Self { relays })
+  }
+
+  pub async fn send(
+    &self,
+    config: &Config,
+    match_config: &MatchConfig,
+    notice_type: &NoticeType,
+    notice: &Notice,
+  ) {
+    let targets: Vec<_> = self
+      .relays
+      .iter()
+      .filter(|r| r.wants(notice_type))
+      .collect();
+    if targets.is_empty() {
+      return;
+    }
+
+    let plain = PlainNotice::new(config, match_config, notice_type, notice);
+    for relay in targets {
+      match timeout(SEND_TIMEOUT, relay.send(&plain)).await {
+        Ok(Ok(())) => log::success(format!("Relayed notice {} via {}", notice.id, relay.name())),
+        Ok(Err(e)) => log::error(format!(
+          "Failed to relay notice {} via {}: {}",
+          notice.id,
+          relay.name(),
+          e
+        )),
+        Err(_) => log::error(format!(
+          "Timeout relaying notice {} via {}",
+          notice.id,
+          relay.name()
+        )),
+      }
+    }
+  }
+}
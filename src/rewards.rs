@@ -0,0 +1,54 @@
+use serenity::model::id::{GuildId, RoleId, UserId};
+use serenity::prelude::Context;
+use std::sync::Arc;
+
+use crate::config::RewardsConfig;
+use crate::links::TeamLinks;
+use crate::log;
+
+pub struct RoleRewarder {
+  config: RewardsConfig,
+  links: Arc<TeamLinks>,
+}
+
+impl RoleRewarder {
+  pub fn new(config: RewardsConfig, links: Arc<TeamLinks>) -> Self {
+    Self { config, links }
+  }
+
+  pub async fn reward_first_blood(&self, ctx: &Context, team_name: &str) {
+    let members = self.links.members_of(team_name).await;
+    if members.is_empty() {
+      log::info(format!(
+        "No verified Discord members for team '{}', skipping role reward.",
+        team_name
+      ));
+      return;
+    }
+
+    let guild_id = GuildId::new(self.config.guild_id);
+    let role_id = RoleId::new(self.config.first_blood_role_id);
+
+    for user_id in members {
+      match ctx
+        .http
+        .add_member_role(
+          guild_id,
+          UserId::new(user_id),
+          role_id,
+          Some("GZCTF first blood"),
+        )
+        .await
+      {
+        Ok(_) => log::success(format!(
+          "Granted first blood role to user {} (team '{}')",
+          user_id, team_name
+        )),
+        Err(e) => log::error(format!(
+          "Failed to grant first blood role to user {}: {}",
+          user_id, e
+        )),
+      }
+    }
+  }
+}
@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, sleep};
+
+use crate::clock::Clock;
+use crate::tasks;
+
+// 按绝对时间执行一次性任务，同一 key 在完成前不会重复安排（重连后再次 ready 也安全）
+pub struct Scheduler {
+  jobs: Mutex<HashMap<String, JoinHandle<()>>>,
+  clock: Arc<dyn Clock>,
+}
+
+impl Scheduler {
+  pub fn new(clock: Arc<dyn Clock>) -> Self {
+    Self {
+      jobs: Mutex::new(HashMap::new()),
+      clock,
+    }
+  }
+
+  pub fn now(&self) -> DateTime<Utc> {
+    self.clock.utc()
+  }
+
+  pub fn clock(&self) -> Arc<dyn Clock> {
+    Arc::clone(&self.clock)
+  }
+
+  pub fn schedule_at<F>(&self, key: impl Into<String>, at: DateTime<Utc>, task: F) -> bool
+  where
+    F: Future<Output = ()> + Send + 'static,
+  {
+    let key = key.into();
+    let mut jobs = self.jobs.lock().unwrap();
+    jobs.retain(|_, handle| !handle.is_finished());
+    if jobs.contains_key(&key) {
+      return false;
+    }
+
+    let delay = (at - self.now()).to_std().unwrap_or_default();
+    let name = format!("scheduler:{}", key);
+    let handle = tokio::spawn(async move {
+      sleep(delay).await;
+      tasks::started(&name);
+      task.await;
+      tasks::finished(&name);
+    });
+    jobs.insert(key, handle);
+    true
+  }
+}
+
+// 解析 "30s" "10m" "1h" "2d" 形式的时长，"start" 表示 0
+pub fn parse_offset(raw: &str) -> Option<Duration> {
+  let raw = raw.trim();
+  if raw == "start" {
+    return Some(Duration::ZERO);
+  }
+
+  let (split, _) = raw.char_indices().last()?;
+  let (number, unit) = raw.split_at(split);
+  let value: u64 = number.parse().ok()?;
+  let secs = match unit {
+    "s" => value,
+    "m" => value * 60,
+    "h" => value * 3600,
+    "d" => value * 86400,
+    _ => return None,
+  };
+  Some(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::clock::MockClock;
+  use std::sync::atomic::{AtomicBool, Ordering};
+
+  #[tokio::test(start_paused = true)]
+  async fn job_runs_when_clock_reaches_its_time() {
+    let scheduler = Scheduler::new(Arc::new(MockClock::new()));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let at = scheduler.now() + chrono::Duration::seconds(60);
+    let flag = Arc::clone(&done);
+    assert!(scheduler.schedule_at("job", at, async move {
+      flag.store(true, Ordering::SeqCst);
+    }));
+    assert!(!scheduler.schedule_at("job", at, async {}));
+
+    tokio::time::sleep(Duration::from_secs(59)).await;
+    assert!(!done.load(Ordering::SeqCst));
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(done.load(Ordering::SeqCst));
+    assert!(scheduler.now() >= at);
+  }
+}
@@ -0,0 +1,135 @@
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+use serenity::model::colour::Colour;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::{MatchConfig, PlatformKind};
+use crate::gzctf::{format_time, game_url};
+use crate::models::Scoreboard;
+
+#[derive(Debug, Default)]
+struct Snapshot {
+  scores: HashMap<String, u64>,
+  // 最近一次播报（或首次记录）的榜首，冷却期内的来回易主不会改变它
+  announced_leader: Option<String>,
+  announced_at: Option<Instant>,
+}
+
+// 对比相邻两次排行榜，生成分数里程碑和榜首易主播报
+pub struct ScoreWatcher {
+  snapshots: Mutex<HashMap<u32, Snapshot>>,
+}
+
+impl ScoreWatcher {
+  pub fn new() -> Self {
+    Self {
+      snapshots: Mutex::new(HashMap::new()),
+    }
+  }
+
+  // 每场比赛的第一次快照只记录不播报，避免启动时把历史变化一次性发出
+  pub async fn diff(
+    &self,
+    match_config: &MatchConfig,
+    scoreboard: &Scoreboard,
+    base_url: &str,
+    platform: PlatformKind,
+  ) -> Vec<CreateEmbed> {
+    let scores: HashMap<String, u64> = scoreboard
+      .items
+      .iter()
+      .map(|t| (t.name.clone(), t.score))
+      .collect();
+    let leader = leader_of(scoreboard);
+
+    let mut snapshots = self.snapshots.lock().await;
+    let Some(state) = snapshots.get_mut(&match_config.id) else {
+      snapshots.insert(
+        match_config.id,
+        Snapshot {
+          scores,
+          announced_leader: leader,
+          announced_at: None,
+        },
+      );
+      return Vec::new();
+    };
+    let previous = std::mem::replace(&mut state.scores, scores);
+
+    let mut embeds = Vec::new();
+    let match_line = match_line(match_config, base_url, platform);
+
+    for (team, score) in &state.scores {
+      let before = previous.get(team).copied().unwrap_or(0);
+      let crossed = match_config
+        .score_milestones
+        .iter()
+        .filter(|m| before < **m && *score >= **m)
+        .max();
+      if let Some(milestone) = crossed {
+        embeds.push(
+          base_embed("**分数里程碑**", Colour::from_rgb(168, 85, 247))
+            .description(format!(
+              "{}\n**{}** 总分突破 **{}** 分！",
+              match_line, team, milestone
+            ))
+            .field("当前总分", score.to_string(), true),
+        );
+      }
+    }
+
+    let cooldown = Duration::from_secs(match_config.lead_change_cooldown);
+    let cooled_down = state.announced_at.is_none_or(|at| at.elapsed() >= cooldown);
+    match (&state.announced_leader, leader) {
+      (None, leader) => state.announced_leader = leader,
+      (Some(old), Some(new)) if *old != new && cooled_down => {
+        if match_config.announce_lead_change {
+          embeds.push(
+            base_embed("**榜首易主**", Colour::from_rgb(239, 68, 68)).description(format!(
+              "{}\n**{}** 超越 **{}** 登上榜首！",
+              match_line, new, old
+            )),
+          );
+        }
+        state.announced_leader = Some(new);
+        state.announced_at = Some(Instant::now());
+      }
+      _ => {}
+    }
+
+    embeds
+  }
+}
+
+// 排行榜按排名返回，同分时取排名靠前的队伍；没有得分时不算榜首
+fn leader_of(scoreboard: &Scoreboard) -> Option<String> {
+  let top = scoreboard.items.iter().map(|t| t.score).max()?;
+  scoreboard
+    .items
+    .iter()
+    .find(|t| t.score == top && top > 0)
+    .map(|t| t.name.clone())
+}
+
+fn match_line(match_config: &MatchConfig, base_url: &str, platform: PlatformKind) -> String {
+  let name = match_config
+    .name
+    .clone()
+    .unwrap_or_else(|| format!("比赛 {}", match_config.id));
+  format!(
+    "**赛事:** [{}]({})",
+    name,
+    game_url(base_url, match_config.id, platform)
+  )
+}
+
+fn base_embed(title: &str, colour: Colour) -> CreateEmbed {
+  CreateEmbed::new()
+    .title(title)
+    .color(colour)
+    .footer(CreateEmbedFooter::new(format_time(
+      SystemClock.now_millis(),
+    )))
+}
@@ -0,0 +1,139 @@
+// Windows 服务：由服务控制管理器（SCM）启动，停止和关机请求按 SIGTERM 处理，保存状态后退出。
+// 服务没有控制台，日志需要在 [log] 中配置写入文件
+use anyhow::{Context as _, Result};
+use std::ffi::OsString;
+use std::sync::OnceLock;
+use std::time::Duration;
+use windows_service::service::{
+  ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+  ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::{define_windows_service, service_dispatcher};
+
+use crate::log;
+use crate::signals;
+
+const SERVICE_NAME: &str = "dc-bot";
+const SERVICE_TYPE: ServiceType = ServiceType::OWN_PROCESS;
+
+type Entry = Box<dyn Fn() -> Result<()> + Send + Sync>;
+
+// SCM 在自己的线程上调用 service_main，无法传参，运行入口由 start 放在这里
+static ENTRY: OnceLock<Entry> = OnceLock::new();
+
+define_windows_service!(ffi_service_main, service_main);
+
+// 阻塞到服务停止；不是由 SCM 启动时立即返回错误
+pub fn start(entry: impl Fn() -> Result<()> + Send + Sync + 'static) -> Result<()> {
+  let _ = ENTRY.set(Box::new(entry));
+  service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+    .context("Failed to connect to the service control manager; --service is only for runs started as a Windows service")
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+  if let Err(e) = serve() {
+    log::error(format!("Service stopped with an error: {:#}", e));
+  }
+}
+
+fn serve() -> Result<()> {
+  let status = service_control_handler::register(SERVICE_NAME, |control| match control {
+    ServiceControl::Stop | ServiceControl::Shutdown => {
+      signals::request_stop();
+      ServiceControlHandlerResult::NoError
+    }
+    ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+    _ => ServiceControlHandlerResult::NotImplemented,
+  })?;
+  let report = |state, controls_accepted, exit_code| {
+    status.set_service_status(ServiceStatus {
+      service_type: SERVICE_TYPE,
+      current_state: state,
+      controls_accepted,
+      exit_code,
+      checkpoint: 0,
+      wait_hint: Duration::default(),
+      process_id: None,
+    })
+  };
+
+  report(
+    ServiceState::Running,
+    ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+    ServiceExitCode::Win32(0),
+  )?;
+  let result = ENTRY.get().map_or(Ok(()), |entry| entry());
+  let exit_code = match result {
+    Ok(()) => ServiceExitCode::Win32(0),
+    Err(_) => ServiceExitCode::ServiceSpecific(1),
+  };
+  report(
+    ServiceState::Stopped,
+    ServiceControlAccept::empty(),
+    exit_code,
+  )?;
+  result
+}
+
+// 以当前可执行文件和配置文件的绝对路径注册为开机自启的服务，需要管理员权限
+pub fn install(config: &str, profile: Option<&str>) -> Result<()> {
+  let config = std::fs::canonicalize(config)
+    .with_context(|| format!("Failed to resolve config file '{}'", config))?;
+  let mut launch_arguments = vec![
+    OsString::from("--service"),
+    OsString::from("--config"),
+    config.into_os_string(),
+  ];
+  if let Some(profile) = profile {
+    launch_arguments.extend([OsString::from("--profile"), OsString::from(profile)]);
+  }
+
+  let manager = ServiceManager::local_computer(
+    None::<&str>,
+    ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+  )
+  .context("Failed to open the service control manager")?;
+  let info = ServiceInfo {
+    name: OsString::from(SERVICE_NAME),
+    display_name: OsString::from("GZCTF Discord Bot"),
+    service_type: SERVICE_TYPE,
+    start_type: ServiceStartType::AutoStart,
+    error_control: ServiceErrorControl::Normal,
+    executable_path: std::env::current_exe()?,
+    launch_arguments,
+    dependencies: Vec::new(),
+    account_name: None,
+    account_password: None,
+  };
+  manager
+    .create_service(&info, ServiceAccess::empty())
+    .context("Failed to create the service")?;
+
+  log::success(format!(
+    "Installed service '{}'; start it with `sc start {}`",
+    SERVICE_NAME, SERVICE_NAME
+  ));
+  Ok(())
+}
+
+// 运行中的服务先停止再删除
+pub fn uninstall() -> Result<()> {
+  let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+    .context("Failed to open the service control manager")?;
+  let service = manager
+    .open_service(
+      SERVICE_NAME,
+      ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+    )
+    .with_context(|| format!("Failed to open service '{}'", SERVICE_NAME))?;
+
+  if service.query_status()?.current_state != ServiceState::Stopped {
+    service.stop().context("Failed to stop the service")?;
+  }
+  service.delete().context("Failed to delete the service")?;
+
+  log::success(format!("Removed service '{}'", SERVICE_NAME));
+  Ok(())
+}
@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tokio::sync::Notify;
+use tokio::time::{Duration, Instant, timeout};
+
+// 退出前的屏障：关闭后不再开始新的发送，并等待已开始的发送完成或入队，
+// 之后才保存队列和 tracker，避免公告在两者之间丢失
+pub struct ShutdownBarrier {
+  closing: AtomicBool,
+  inflight: AtomicUsize,
+  idle: Notify,
+}
+
+pub struct InflightGuard<'a> {
+  barrier: &'a ShutdownBarrier,
+}
+
+impl ShutdownBarrier {
+  pub fn new() -> Self {
+    Self {
+      closing: AtomicBool::new(false),
+      inflight: AtomicUsize::new(0),
+      idle: Notify::new(),
+    }
+  }
+
+  // 关闭后返回 None，调用方应放弃本次发送，留给下次启动重新发现
+  pub fn enter(&self) -> Option<InflightGuard<'_>> {
+    self.inflight.fetch_add(1, Ordering::SeqCst);
+    if self.closing.load(Ordering::SeqCst) {
+      self.leave();
+      return None;
+    }
+    Some(InflightGuard { barrier: self })
+  }
+
+  pub fn is_closing(&self) -> bool {
+    self.closing.load(Ordering::SeqCst)
+  }
+
+  // 返回是否在时限内等到所有发送结束
+  pub async fn close(&self, limit: Duration) -> bool {
+    self.closing.store(true, Ordering::SeqCst);
+    let deadline = Instant::now() + limit;
+    loop {
+      let idle = self.idle.notified();
+      if self.inflight.load(Ordering::SeqCst) == 0 {
+        return true;
+      }
+      let left = deadline.saturating_duration_since(Instant::now());
+      if timeout(left, idle).await.is_err() {
+        return false;
+      }
+    }
+  }
+
+  fn leave(&self) {
+    if self.inflight.fetch_sub(1, Ordering::SeqCst) == 1 {
+      self.idle.notify_waiters();
+    }
+  }
+}
+
+impl Drop for InflightGuard<'_> {
+  fn drop(&mut self) {
+    self.barrier.leave();
+  }
+}
@@ -0,0 +1,55 @@
+// 进程信号：SIGTERM 与 Ctrl+C 一样正常退出，SIGHUP 重新加载配置，SIGUSR1 切换维护模式；
+// 非 Unix 平台上这些信号不存在，对应的 future 永远不会完成；Windows 服务的停止请求代替 SIGTERM
+#[cfg(unix)]
+use tokio::signal::unix::{SignalKind, signal};
+#[cfg(windows)]
+use tokio::sync::watch;
+
+#[cfg(unix)]
+use crate::log;
+
+#[cfg(unix)]
+async fn recv(kind: SignalKind, name: &str) {
+  let mut listener = match signal(kind) {
+    Ok(listener) => listener,
+    Err(e) => {
+      log::error(format!("Failed to listen for {}: {}", name, e));
+      return std::future::pending().await;
+    }
+  };
+  listener.recv().await;
+}
+
+// 停止请求可能在两次运行之间到达，用 watch 保留状态，下一次运行开始时立即退出
+#[cfg(windows)]
+static STOP: std::sync::LazyLock<watch::Sender<bool>> =
+  std::sync::LazyLock::new(|| watch::Sender::new(false));
+
+// 由服务控制处理函数在 SCM 的线程上调用
+#[cfg(windows)]
+pub fn request_stop() {
+  STOP.send_replace(true);
+}
+
+pub async fn terminate() {
+  #[cfg(unix)]
+  recv(SignalKind::terminate(), "SIGTERM").await;
+  #[cfg(windows)]
+  let _ = STOP.subscribe().wait_for(|stop| *stop).await;
+  #[cfg(not(any(unix, windows)))]
+  std::future::pending::<()>().await;
+}
+
+pub async fn hangup() {
+  #[cfg(unix)]
+  recv(SignalKind::hangup(), "SIGHUP").await;
+  #[cfg(not(unix))]
+  std::future::pending::<()>().await;
+}
+
+pub async fn user_defined() {
+  #[cfg(unix)]
+  recv(SignalKind::user_defined1(), "SIGUSR1").await;
+  #[cfg(not(unix))]
+  std::future::pending::<()>().await;
+}
@@ -0,0 +1,205 @@
+use chrono::{DateTime, Utc};
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+use serenity::model::colour::Colour;
+use serenity::prelude::Context;
+use std::sync::Arc;
+use tokio::time::{Duration, sleep};
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::{Config, MatchConfig};
+use crate::discord::{ContextMessenger, DeliveryState, DiscordMessenger, Messenger};
+use crate::gzctf::{format_time, game_url, trunc_text};
+use crate::limiter::RequestLimiter;
+use crate::log;
+use crate::platform::{self, CtfPlatform};
+use crate::scheduler::{self, Scheduler};
+
+// 为每场比赛安排零解题目播报，重启后从下一个周期继续
+pub fn start(
+  ctx: Arc<Context>,
+  config: Arc<Config>,
+  scheduler: Arc<Scheduler>,
+  delivery: Arc<DeliveryState>,
+  limiter: Arc<RequestLimiter>,
+) {
+  let Some(spotlight) = config.spotlight.clone() else {
+    return;
+  };
+  let Some(after) = scheduler::parse_offset(&spotlight.after) else {
+    log::error(format!(
+      "Invalid spotlight.after '{}', spotlight disabled",
+      spotlight.after
+    ));
+    return;
+  };
+  let repeat = spotlight.repeat.as_deref().and_then(|raw| {
+    let repeat = scheduler::parse_offset(raw).filter(|d| !d.is_zero());
+    if repeat.is_none() {
+      log::error(format!("Invalid spotlight.repeat '{}', posting once", raw));
+    }
+    repeat
+  });
+
+  tokio::spawn(async move {
+    let client = match platform::connect(&config.gzctf, &limiter) {
+      Ok(client) => client,
+      Err(e) => {
+        log::error(format!("Failed to create client for spotlight: {}", e));
+        return;
+      }
+    };
+    let messenger = Arc::new(ContextMessenger::new(
+      Arc::clone(&ctx),
+      Arc::new(DiscordMessenger::new(config.discord.channel_id, delivery)),
+    ));
+
+    for match_config in config.get_matches() {
+      let game = match client.fetch_game(match_config.id).await {
+        Ok(game) => game,
+        Err(e) => {
+          log::error(format!(
+            "Failed to fetch game info for match {}: {}",
+            match_config.id, e
+          ));
+          continue;
+        }
+      };
+      let (Some(start), Some(end)) = (
+        DateTime::from_timestamp_millis(game.start as i64),
+        DateTime::from_timestamp_millis(game.end as i64),
+      ) else {
+        continue;
+      };
+
+      let Some(first) = next_occurrence(start + after, repeat, scheduler.now()) else {
+        continue;
+      };
+      if first >= end {
+        continue;
+      }
+
+      let channels = match spotlight.channel_id {
+        Some(channel_id) => vec![channel_id],
+        None => config.broadcast_channels(match_config.id),
+      };
+      let task = run(
+        Arc::clone(&messenger),
+        Arc::clone(&client),
+        Arc::clone(&config),
+        match_config.clone(),
+        channels,
+        repeat,
+        end - first,
+      );
+      if scheduler.schedule_at(format!("spotlight:{}", match_config.id), first, task) {
+        log::info(format!(
+          "Scheduled unsolved spotlight for match {} at {}",
+          match_config.id,
+          format_time(first.timestamp_millis() as u64)
+        ));
+      }
+    }
+  });
+}
+
+// 首次时间已过时，取之后最近的一个重复周期；不重复则跳过
+fn next_occurrence(
+  first: DateTime<Utc>,
+  repeat: Option<Duration>,
+  now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+  if first > now {
+    return Some(first);
+  }
+  let repeat = chrono::Duration::from_std(repeat?).ok()?;
+  let elapsed = now - first;
+  let periods = elapsed.num_seconds() / repeat.num_seconds() + 1;
+  Some(first + repeat * periods as i32)
+}
+
+async fn run(
+  messenger: Arc<ContextMessenger>,
+  client: Arc<dyn CtfPlatform>,
+  config: Arc<Config>,
+  match_config: MatchConfig,
+  channels: Vec<u64>,
+  repeat: Option<Duration>,
+  // 从本轮播报到比赛结束的剩余时长，按计划时间推算，不依赖实际耗时
+  mut left: chrono::Duration,
+) {
+  loop {
+    match unsolved_embed(client.as_ref(), &config, &match_config).await {
+      Ok(Some(embed)) => {
+        for channel_id in &channels {
+          // 发送失败只记录日志，下一个周期会重新统计
+          let _ = messenger
+            .send(Some(*channel_id), None, &[], embed.clone())
+            .await;
+        }
+      }
+      Ok(None) => {
+        log::info(format!(
+          "Every challenge in match {} has been solved, stopping spotlight",
+          match_config.id
+        ));
+        return;
+      }
+      Err(e) => log::error(format!(
+        "Failed to build spotlight for match {}: {}",
+        match_config.id, e
+      )),
+    }
+
+    let Some(repeat) = repeat else {
+      return;
+    };
+    match chrono::Duration::from_std(repeat) {
+      Ok(step) if step < left => left -= step,
+      _ => return,
+    }
+    sleep(repeat).await;
+  }
+}
+
+async fn unsolved_embed(
+  client: &dyn CtfPlatform,
+  config: &Config,
+  match_config: &MatchConfig,
+) -> anyhow::Result<Option<CreateEmbed>> {
+  let scoreboard = client.fetch_scoreboard(match_config.id).await?;
+  let mut unsolved: Vec<String> = scoreboard
+    .challenges
+    .values()
+    .flatten()
+    .filter(|c| c.solved == 0)
+    .map(|c| format!("`{}` {}", c.category, c.title))
+    .collect();
+  if unsolved.is_empty() {
+    return Ok(None);
+  }
+  unsolved.sort();
+
+  let match_name = match_config
+    .name
+    .clone()
+    .unwrap_or_else(|| format!("比赛 {}", match_config.id));
+  let game_url = game_url(&config.gzctf.url, match_config.id, config.gzctf.platform);
+  let description = format!(
+    "**赛事:** [{}]({})\n以下题目还没有队伍解出，快来拿下一血！\n\n{}",
+    match_name,
+    game_url,
+    unsolved.join("\n")
+  );
+
+  Ok(Some(
+    CreateEmbed::new()
+      .title("**零解题目**")
+      .color(Colour::from_rgb(234, 179, 8))
+      .description(trunc_text(&description, 3800))
+      .footer(CreateEmbedFooter::new(format!(
+        "共 {} 道题目尚未解出 · {}",
+        unsolved.len(),
+        format_time(SystemClock.now_millis())
+      ))),
+  ))
+}
@@ -0,0 +1,102 @@
+use anyhow::{Context as _, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::fs;
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::StorageConfig;
+use crate::crypto::Cipher;
+use crate::journal::{JOURNAL_PATH, Journal};
+use crate::log;
+use crate::tracker::{NoticeTracker, TRACKER_PATH};
+
+const BUNDLE_VERSION: u32 = 1;
+
+// 迁移用的状态包，带版本号以便以后加入其他状态
+#[derive(Serialize, Deserialize)]
+struct StateBundle {
+  version: u32,
+  exported_at: String,
+  tracker: NoticeTracker,
+}
+
+// state export: 导出已处理公告的记录，迁移到新主机后导入即可避免重复播报。
+// 上次检查点之后的记录还在预写日志里，先重放到 tracker 上再导出
+pub async fn export(
+  path: &str,
+  storage: &StorageConfig,
+  cipher: Option<Arc<Cipher>>,
+) -> Result<()> {
+  let tracker_path = storage.path(TRACKER_PATH);
+  if !fs::try_exists(&tracker_path).await.unwrap_or(false) {
+    return Err(anyhow!("no tracker state found at {}", tracker_path));
+  }
+  let mut tracker = NoticeTracker::load_from_disk(&tracker_path).await?;
+  let journal_path = storage.path(JOURNAL_PATH);
+  if fs::try_exists(&journal_path).await.unwrap_or(false) {
+    let replayed = Journal::open(&journal_path, cipher)?.replay_records(&mut tracker)?;
+    if replayed > 0 {
+      log::info(format!(
+        "Included {} notice(s) recorded in {} since the last checkpoint.",
+        replayed, journal_path
+      ));
+    }
+  }
+  let bundle = StateBundle {
+    version: BUNDLE_VERSION,
+    exported_at: SystemClock.utc().to_rfc3339(),
+    tracker,
+  };
+
+  fs::write(path, serde_json::to_string_pretty(&bundle)?)
+    .await
+    .with_context(|| format!("failed to write {}", path))?;
+  log::success(format!("Exported tracker state to {}", path));
+  Ok(())
+}
+
+// state import: 机器人运行时不要导入，退出时会用内存中的状态覆盖。
+// 旧的预写日志属于被替换的状态，导入后清空，否则下次启动会把它重放到导入的 tracker 上
+pub async fn import(path: &str, storage: &StorageConfig, force: bool) -> Result<()> {
+  let tracker_path = &storage.path(TRACKER_PATH);
+  let content = fs::read_to_string(path)
+    .await
+    .with_context(|| format!("failed to read {}", path))?;
+  let bundle: StateBundle =
+    serde_json::from_str(&content).with_context(|| format!("{} is not a state export", path))?;
+  if bundle.version != BUNDLE_VERSION {
+    return Err(anyhow!(
+      "unsupported state export version {} (expected {})",
+      bundle.version,
+      BUNDLE_VERSION
+    ));
+  }
+  if !force && fs::try_exists(tracker_path).await.unwrap_or(false) {
+    return Err(anyhow!(
+      "{} already exists, pass --force to overwrite it",
+      tracker_path
+    ));
+  }
+
+  if let Some(dir) = Path::new(tracker_path).parent() {
+    fs::create_dir_all(dir)
+      .await
+      .with_context(|| format!("failed to create {}", dir.display()))?;
+  }
+  let mut tracker = bundle.tracker;
+  tracker.set_persist_path(tracker_path.to_string());
+  tracker.save_to_disk().await?;
+  let journal_path = storage.path(JOURNAL_PATH);
+  if fs::try_exists(&journal_path).await.unwrap_or(false) {
+    fs::remove_file(&journal_path)
+      .await
+      .with_context(|| format!("failed to remove {}", journal_path))?;
+    log::info(format!("Removed stale journal {}", journal_path));
+  }
+  log::success(format!(
+    "Imported tracker state exported at {} into {}",
+    bundle.exported_at, tracker_path
+  ));
+  Ok(())
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use tokio::fs;
+use tokio::sync::RwLock;
+
+use crate::audit;
+use crate::log;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+  pub match_id: u32,
+  pub category: String,
+}
+
+impl Subscription {
+  fn matches(&self, match_id: u32, category: &str) -> bool {
+    self.match_id == match_id && self.category.eq_ignore_ascii_case(category)
+  }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SubscriptionData {
+  // Discord 用户 ID -> 订阅的 比赛 + 分类
+  users: HashMap<u64, Vec<Subscription>>,
+}
+
+// /subscribe 的订阅关系，对应分类的提示和血榜私信给订阅者
+pub struct Subscriptions {
+  persist_path: String,
+  data: RwLock<SubscriptionData>,
+}
+
+impl Subscriptions {
+  pub async fn load_from_disk(persist_path: &str) -> Result<Self> {
+    let data = if fs::try_exists(persist_path).await.unwrap_or(false) {
+      let content = fs::read_to_string(persist_path).await?;
+      let data: SubscriptionData = serde_json::from_str(&content)?;
+      log::success(format!(
+        "Loaded subscriptions of {} users from disk.",
+        data.users.len()
+      ));
+      data
+    } else {
+      SubscriptionData::default()
+    };
+
+    Ok(Self {
+      persist_path: persist_path.to_string(),
+      data: RwLock::new(data),
+    })
+  }
+
+  pub fn empty(persist_path: &str) -> Self {
+    Self {
+      persist_path: persist_path.to_string(),
+      data: RwLock::new(SubscriptionData::default()),
+    }
+  }
+
+  // 返回是否为新订阅
+  pub async fn subscribe(&self, user_id: u64, match_id: u32, category: &str) -> Result<bool> {
+    {
+      let mut data = self.data.write().await;
+      let subscriptions = data.users.entry(user_id).or_default();
+      if subscriptions.iter().any(|s| s.matches(match_id, category)) {
+        return Ok(false);
+      }
+      subscriptions.push(Subscription {
+        match_id,
+        category: category.to_string(),
+      });
+    }
+    self.save_to_disk().await?;
+    audit::record(
+      "subscribed",
+      &user_id.to_string(),
+      json!({ "match_id": match_id, "category": category }),
+    );
+    Ok(true)
+  }
+
+  // 未指定的条件不做限制，返回取消的订阅
+  pub async fn unsubscribe(
+    &self,
+    user_id: u64,
+    match_id: Option<u32>,
+    category: Option<&str>,
+  ) -> Result<Vec<Subscription>> {
+    let removed = {
+      let mut data = self.data.write().await;
+      let Some(subscriptions) = data.users.get_mut(&user_id) else {
+        return Ok(Vec::new());
+      };
+      let (removed, kept) = subscriptions.drain(..).partition(|s: &Subscription| {
+        match_id.is_none_or(|id| s.match_id == id)
+          && category.is_none_or(|c| s.category.eq_ignore_ascii_case(c))
+      });
+      *subscriptions = kept;
+      if subscriptions.is_empty() {
+        data.users.remove(&user_id);
+      }
+      removed
+    };
+    if !removed.is_empty() {
+      self.save_to_disk().await?;
+      audit::record(
+        "unsubscribed",
+        &user_id.to_string(),
+        json!({ "removed": removed }),
+      );
+    }
+    Ok(removed)
+  }
+
+  pub async fn of(&self, user_id: u64) -> Vec<Subscription> {
+    self
+      .data
+      .read()
+      .await
+      .users
+      .get(&user_id)
+      .cloned()
+      .unwrap_or_default()
+  }
+
+  pub async fn subscribers(&self, match_id: u32, category: &str) -> Vec<u64> {
+    let mut users: Vec<u64> = self
+      .data
+      .read()
+      .await
+      .users
+      .iter()
+      .filter(|(_, subscriptions)| subscriptions.iter().any(|s| s.matches(match_id, category)))
+      .map(|(user_id, _)| *user_id)
+      .collect();
+    users.sort_unstable();
+    users
+  }
+
+  async fn save_to_disk(&self) -> Result<()> {
+    let json = serde_json::to_string_pretty(&*self.data.read().await)?;
+
+    let tmp_path = format!("{}.tmp", self.persist_path);
+    fs::write(&tmp_path, &json).await?;
+    fs::rename(&tmp_path, &self.persist_path).await?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,220 @@
+use chrono::{DateTime, Local};
+use serenity::prelude::Context;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio::time::{Duration, sleep, timeout};
+
+use crate::alerts::AlertManager;
+use crate::log;
+
+// 重启间隔从 MIN_BACKOFF 开始每次翻倍；重启后稳定运行 STABLE_AFTER 即视为恢复
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+const STABLE_AFTER: Duration = Duration::from_secs(300);
+
+// 常驻后台任务（轮询、重试循环、调度任务）的启动和最后活动时间，
+// 供 /debug tasks 判断哪个任务卡住了
+static TASKS: Mutex<BTreeMap<String, Activity>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Copy)]
+struct Activity {
+  started: DateTime<Local>,
+  last_active: DateTime<Local>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskStatus {
+  pub name: String,
+  pub started: DateTime<Local>,
+  pub last_active: DateTime<Local>,
+}
+
+// 任务开始运行时调用，重复调用会重置启动时间
+pub fn started(name: &str) {
+  let now = Local::now();
+  TASKS.lock().unwrap().insert(
+    name.to_string(),
+    Activity {
+      started: now,
+      last_active: now,
+    },
+  );
+}
+
+// 循环每完成一轮调用一次；未登记的任务视为刚启动
+pub fn touch(name: &str) {
+  let now = Local::now();
+  TASKS
+    .lock()
+    .unwrap()
+    .entry(name.to_string())
+    .and_modify(|activity| activity.last_active = now)
+    .or_insert(Activity {
+      started: now,
+      last_active: now,
+    });
+}
+
+pub fn finished(name: &str) {
+  TASKS.lock().unwrap().remove(name);
+}
+
+// 重新加载配置时旧 runtime 上的任务直接被丢弃，来不及调用 finished，
+// 每次 run 开始时清空，/debug tasks 不再列出已经不存在的任务
+pub fn reset() {
+  TASKS.lock().unwrap().clear();
+}
+
+pub fn snapshot() -> Vec<TaskStatus> {
+  TASKS
+    .lock()
+    .unwrap()
+    .iter()
+    .map(|(name, activity)| TaskStatus {
+      name: name.clone(),
+      started: activity.started,
+      last_active: activity.last_active,
+    })
+    .collect()
+}
+
+// 外层任务被中止时一并中止正在运行的那一次
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+  fn drop(&mut self) {
+    self.0.abort();
+  }
+}
+
+// 在后台反复运行 make 创建的任务：正常结束或被中止时停止，panic 时记录日志、
+// 在管理频道告警并按退避间隔重启。中止返回的句柄会同时中止正在运行的任务
+pub fn supervise<F, Fut>(
+  name: &'static str,
+  alerting: Option<(Arc<Context>, Arc<AlertManager>)>,
+  make: F,
+) -> JoinHandle<()>
+where
+  F: Fn() -> Fut + Send + 'static,
+  Fut: Future<Output = ()> + Send + 'static,
+{
+  tokio::spawn(async move {
+    let key = format!("task:{}", name);
+    let mut backoff = MIN_BACKOFF;
+    let mut restarts = 0u32;
+    let mut alerted = false;
+
+    loop {
+      let mut handle = tokio::spawn(make());
+      let _guard = AbortOnDrop(handle.abort_handle());
+      let result = match timeout(STABLE_AFTER, &mut handle).await {
+        Ok(result) => result,
+        Err(_) => {
+          backoff = MIN_BACKOFF;
+          if alerted && let Some((ctx, alerts)) = &alerting {
+            alerts.resolve(ctx, &key).await;
+          }
+          alerted = false;
+          handle.await
+        }
+      };
+
+      let panic = match result {
+        Ok(()) => return,
+        Err(e) if e.is_cancelled() => return,
+        Err(e) => panic_message(e.into_panic()),
+      };
+      finished(name);
+      restarts += 1;
+      log::error(format!(
+        "Task '{}' panicked: {}. Restarting in {}s (restart #{})",
+        name,
+        panic,
+        backoff.as_secs(),
+        restarts
+      ));
+      if let Some((ctx, alerts)) = &alerting {
+        alerts
+          .raise(
+            ctx,
+            &key,
+            &format!("后台任务 {} 崩溃", name),
+            &format!(
+              "任务 panic: {}\n已自动重启 {} 次，下次重启在 {} 秒后",
+              panic,
+              restarts,
+              backoff.as_secs()
+            ),
+          )
+          .await;
+        alerted = true;
+      }
+
+      sleep(backoff).await;
+      backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+  })
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+  match payload.downcast::<String>() {
+    Ok(message) => *message,
+    Err(payload) => match payload.downcast::<&'static str>() {
+      Ok(message) => message.to_string(),
+      Err(_) => "unknown panic".to_string(),
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::{AtomicU32, Ordering};
+
+  #[test]
+  fn touch_keeps_start_time_and_finish_removes_the_task() {
+    started("tasks-test");
+    let before = snapshot()
+      .into_iter()
+      .find(|t| t.name == "tasks-test")
+      .unwrap();
+    touch("tasks-test");
+    let after = snapshot()
+      .into_iter()
+      .find(|t| t.name == "tasks-test")
+      .unwrap();
+    assert_eq!(before.started, after.started);
+    assert!(after.last_active >= before.last_active);
+
+    finished("tasks-test");
+    assert!(snapshot().iter().all(|t| t.name != "tasks-test"));
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn panicking_task_is_restarted_until_it_finishes() {
+    let runs = Arc::new(AtomicU32::new(0));
+    let counter = Arc::clone(&runs);
+    let handle = supervise("tasks-panic-test", None, move || {
+      let counter = Arc::clone(&counter);
+      async move {
+        if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+          panic!("boom");
+        }
+      }
+    });
+
+    handle.await.unwrap();
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn aborting_the_supervisor_aborts_the_running_task() {
+    let handle = supervise("tasks-abort-test", None, std::future::pending);
+    tokio::task::yield_now().await;
+    handle.abort();
+    assert!(handle.await.unwrap_err().is_cancelled());
+  }
+}
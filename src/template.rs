@@ -0,0 +1,42 @@
+// 简单的模板渲染：将 `{key}` 替换为对应的值，未知变量保持原样。
+// 只扫描一遍模板，值中出现的 `{key}`（例如队伍名）不会被再次展开
+pub fn render(template: &str, vars: &[(&str, &str)]) -> String {
+  let mut output = String::with_capacity(template.len());
+  let mut rest = template;
+  while let Some(start) = rest.find('{') {
+    output.push_str(&rest[..start]);
+    let after = &rest[start + 1..];
+    let value = after.find('}').and_then(|end| {
+      vars
+        .iter()
+        .find(|(key, _)| *key == &after[..end])
+        .map(|(_, value)| (*value, end))
+    });
+    match value {
+      Some((value, end)) => {
+        output.push_str(value);
+        rest = &after[end + 1..];
+      }
+      None => {
+        output.push('{');
+        rest = after;
+      }
+    }
+  }
+  output.push_str(rest);
+  output
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn values_are_not_expanded_again() {
+    let vars = [("team", "{challenge} @everyone"), ("challenge", "pwn1")];
+    assert_eq!(
+      render("{team} 拿下 {challenge} {unknown} {{team}", &vars),
+      "{challenge} @everyone 拿下 pwn1 {unknown} {{challenge} @everyone"
+    );
+  }
+}
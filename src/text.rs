@@ -0,0 +1,55 @@
+use serde_json::Value;
+use serenity::builder::CreateEmbed;
+
+use crate::gzctf::trunc_text;
+
+// Discord 消息正文的长度上限
+const MAX_CONTENT_LEN: usize = 2000;
+
+// 把 embed 渲染成 Markdown 文本，发往无法显示 embed 的目标
+pub fn render(content: Option<&str>, embed: &CreateEmbed) -> String {
+  trunc_text(&lines(content, embed, false).join("\n"), MAX_CONTENT_LEN)
+}
+
+// 预览额外带上颜色和 inline 标记，便于在终端和测试里检查格式
+pub fn preview(content: Option<&str>, embed: &CreateEmbed) -> String {
+  lines(content, embed, true).join("\n")
+}
+
+fn lines(content: Option<&str>, embed: &CreateEmbed, verbose: bool) -> Vec<String> {
+  let value = serde_json::to_value(embed).unwrap_or(Value::Null);
+  let text = |v: &Value, key: &str| v.get(key).and_then(Value::as_str).map(str::to_string);
+  let mut lines = Vec::new();
+
+  if let Some(content) = content {
+    lines.push(format!("> {}", content));
+  }
+  if let Some(title) = text(&value, "title") {
+    lines.push(format!("## {}", title));
+  }
+  if verbose && let Some(color) = value.get("color").and_then(Value::as_u64) {
+    lines.push(format!("color: #{:06x}", color));
+  }
+  if let Some(description) = text(&value, "description") {
+    lines.push(description);
+  }
+
+  let fields = value.get("fields").and_then(Value::as_array);
+  for field in fields.into_iter().flatten() {
+    let name = text(field, "name").unwrap_or_default();
+    let value = text(field, "value").unwrap_or_default();
+    let inline = field.get("inline").and_then(Value::as_bool) == Some(true);
+    lines.push(format!(
+      "- **{}**{}: {}",
+      name,
+      if inline && verbose { " (inline)" } else { "" },
+      value
+    ));
+  }
+
+  if let Some(footer) = value.get("footer").and_then(|f| text(f, "text")) {
+    lines.push(format!("_{}_", footer));
+  }
+
+  lines
+}
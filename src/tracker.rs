@@ -1,16 +1,31 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tokio::fs;
 
+use crate::audit;
+use crate::journal::Journal;
 use crate::log;
+use crate::models::Notice;
+
+pub const TRACKER_PATH: &str = "tracker.json";
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct NoticeTracker {
   // 每种类型公告的最新时间戳：match_id:notice_type -> max_timestamp
   max_timestamps: HashMap<String, u64>,
+  // 每种类型已处理的最大公告 ID；ID 单调递增，不受服务器时钟偏差影响，没有指纹的旧记录据此迁移
+  #[serde(default)]
+  max_ids: HashMap<String, u64>,
+  // 每种类型已处理的公告指纹（ID + 内容哈希）；不受服务器时钟偏差和 ID 复用影响
+  #[serde(default)]
+  seen: HashMap<String, HashSet<String>>,
   #[serde(skip)]
   persist_path: Option<String>,
+  #[serde(skip)]
+  journal: Option<Arc<Journal>>,
 }
 
 impl NoticeTracker {
@@ -18,17 +33,32 @@ impl NoticeTracker {
   pub fn new() -> Self {
     Self {
       max_timestamps: HashMap::new(),
+      max_ids: HashMap::new(),
+      seen: HashMap::new(),
       persist_path: None,
+      journal: None,
     }
   }
 
   pub fn with_persist_path(persist_path: String) -> Self {
     Self {
       max_timestamps: HashMap::new(),
+      max_ids: HashMap::new(),
+      seen: HashMap::new(),
       persist_path: Some(persist_path),
+      journal: None,
     }
   }
 
+  pub fn set_persist_path(&mut self, persist_path: String) {
+    self.persist_path = Some(persist_path);
+  }
+
+  // 之后每次 record 都先写入日志，两次保存之间崩溃也不会重复播报
+  pub fn set_journal(&mut self, journal: Arc<Journal>) {
+    self.journal = Some(journal);
+  }
+
   pub async fn load_from_disk(persist_path: &str) -> Result<Self> {
     if !fs::try_exists(persist_path).await.unwrap_or(false) {
       log::info("No persisted tracker found, starting fresh.");
@@ -62,16 +92,156 @@ impl NoticeTracker {
     Ok(())
   }
 
-  pub fn get_timestamp(&self, match_id: u32, notice_type: &str) -> u64 {
+  // 启动时按当前公告整理指纹集合：已删除的公告随之清理，停机期间发布的公告仍然算新公告。
+  // 已有指纹时只保留其中仍存在的；只有旧版 ID / 时间戳记录时按它们划分；从未见过的类型全部标记为已处理
+  pub fn init(&mut self, match_id: u32, notice_type: &str, notices: &[&Notice]) {
+    let key = format!("{}:{}", match_id, notice_type);
+    let handled: Vec<&Notice> = match (
+      self.seen.get(&key),
+      self.max_ids.get(&key),
+      self.max_timestamps.get(&key),
+    ) {
+      (Some(seen), _, _) => notices
+        .iter()
+        .copied()
+        .filter(|n| seen.contains(&n.fingerprint()))
+        .collect(),
+      (None, Some(max_id), _) => notices
+        .iter()
+        .copied()
+        .filter(|n| n.id <= *max_id)
+        .collect(),
+      (None, None, Some(max_time)) => notices
+        .iter()
+        .copied()
+        .filter(|n| n.time <= *max_time)
+        .collect(),
+      (None, None, None) => notices.to_vec(),
+    };
+
+    self.seen.insert(
+      key.clone(),
+      handled.iter().map(|n| n.fingerprint()).collect(),
+    );
+    if let Some(max_id) = handled.iter().map(|n| n.id).max() {
+      let current = self.max_ids.entry(key.clone()).or_insert(0);
+      *current = (*current).max(max_id);
+    }
+    if let Some(max_time) = handled.iter().map(|n| n.time).max() {
+      let current = self.max_timestamps.entry(key).or_insert(0);
+      *current = (*current).max(max_time);
+    }
+  }
+
+  // 手动重新初始化：与 init 不同，高水位直接设为当前最新的公告，不保留更大的旧值
+  pub fn reset(&mut self, match_id: u32, notice_type: &str, notices: &[&Notice]) {
     let key = format!("{}:{}", match_id, notice_type);
-    *self.max_timestamps.get(&key).unwrap_or(&0)
+    let fingerprints = notices.iter().map(|n| n.fingerprint()).collect();
+    self.seen.insert(key.clone(), fingerprints);
+    match notices.iter().map(|n| n.id).max() {
+      Some(max_id) => self.max_ids.insert(key.clone(), max_id),
+      None => self.max_ids.remove(&key),
+    };
+    match notices.iter().map(|n| n.time).max() {
+      Some(max_time) => self.max_timestamps.insert(key, max_time),
+      None => self.max_timestamps.remove(&key),
+    };
   }
 
-  pub fn update_timestamp(&mut self, match_id: u32, notice_type: &str, timestamp: u64) {
+  // 是否已为该比赛建立过指纹集合（一次性模式据此决定是否需要初始化）
+  pub fn knows_match(&self, match_id: u32) -> bool {
+    let prefix = format!("{}:", match_id);
+    self.seen.keys().any(|key| key.starts_with(&prefix))
+  }
+
+  // 初始化过的类型按指纹判断：复用的 ID 换了内容算新公告，完全相同的重复公告不算；
+  // 否则回退到 ID，旧的 tracker.json 没有 ID 时再回退到时间戳
+  pub fn is_new(&self, match_id: u32, notice_type: &str, notice: &Notice) -> bool {
     let key = format!("{}:{}", match_id, notice_type);
-    let current_max = self.max_timestamps.entry(key).or_insert(0);
-    if timestamp > *current_max {
-      *current_max = timestamp;
+    if let Some(seen) = self.seen.get(&key) {
+      return !seen.contains(&notice.fingerprint());
+    }
+    match self.max_ids.get(&key) {
+      Some(max_id) => notice.id > *max_id,
+      None => notice.time > self.max_timestamps.get(&key).copied().unwrap_or(0),
     }
   }
+
+  pub fn record(&mut self, match_id: u32, notice_type: &str, notice: &Notice) {
+    audit::record(
+      "notice_recorded",
+      "bot",
+      json!({ "match_id": match_id, "type": notice_type, "notice_id": notice.id }),
+    );
+    if let Some(journal) = &self.journal {
+      journal.recorded(match_id, notice_type, notice);
+    }
+    let key = format!("{}:{}", match_id, notice_type);
+    if let Some(seen) = self.seen.get_mut(&key) {
+      seen.insert(notice.fingerprint());
+    }
+    let max_id = self.max_ids.entry(key.clone()).or_insert(0);
+    *max_id = (*max_id).max(notice.id);
+    let max_time = self.max_timestamps.entry(key).or_insert(0);
+    *max_time = (*max_time).max(notice.time);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn notice(id: u64, text: &str, time: u64) -> Notice {
+    Notice {
+      id,
+      notice_type: "Normal".to_string(),
+      values: vec![text.to_string()],
+      time,
+    }
+  }
+
+  #[test]
+  fn reused_id_with_new_content_is_new() {
+    let mut tracker = NoticeTracker::new();
+    let original = notice(7, "比赛开始", 100);
+    tracker.init(1, "Normal", &[&original]);
+
+    assert!(!tracker.is_new(1, "Normal", &original));
+    assert!(!tracker.is_new(1, "Normal", &notice(7, "比赛开始", 100)));
+
+    let recreated = notice(7, "比赛延期", 200);
+    assert!(tracker.is_new(1, "Normal", &recreated));
+    tracker.record(1, "Normal", &recreated);
+    assert!(!tracker.is_new(1, "Normal", &recreated));
+  }
+
+  #[test]
+  fn notices_published_while_stopped_stay_new() {
+    let mut tracker = NoticeTracker::new();
+    let first = notice(1, "a", 100);
+    tracker.init(1, "Normal", &[&first]);
+
+    let deleted_later = notice(2, "b", 150);
+    tracker.record(1, "Normal", &deleted_later);
+    let missed = notice(3, "c", 200);
+    tracker.init(1, "Normal", &[&first, &missed]);
+
+    assert!(!tracker.is_new(1, "Normal", &first));
+    assert!(tracker.is_new(1, "Normal", &missed));
+    assert!(!tracker.seen["1:Normal"].contains(&deleted_later.fingerprint()));
+  }
+
+  #[test]
+  fn legacy_ids_seed_fingerprints() {
+    let mut tracker: NoticeTracker =
+      serde_json::from_str(r#"{"max_timestamps":{"1:Normal":100},"max_ids":{"1:Normal":1}}"#)
+        .unwrap();
+    let old = notice(1, "a", 100);
+    let missed = notice(2, "b", 90);
+    assert!(tracker.is_new(1, "Normal", &missed));
+
+    tracker.init(1, "Normal", &[&old, &missed]);
+    assert!(!tracker.is_new(1, "Normal", &old));
+    assert!(tracker.is_new(1, "Normal", &missed));
+  }
 }
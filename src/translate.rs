@@ -0,0 +1,89 @@
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::time::Duration;
+
+use crate::config::{TranslationConfig, TranslationProvider};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize)]
+struct DeeplResponse {
+  translations: Vec<DeeplTranslation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeeplTranslation {
+  text: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LibreResponse {
+  translated_text: String,
+}
+
+pub struct Translator {
+  config: TranslationConfig,
+  client: reqwest::Client,
+}
+
+impl Translator {
+  pub fn new(config: TranslationConfig) -> Result<Self> {
+    let client = reqwest::Client::builder()
+      .timeout(REQUEST_TIMEOUT)
+      .build()?;
+    Ok(Self { config, client })
+  }
+
+  pub async fn translate(&self, text: &str) -> Result<String> {
+    match self.config.provider {
+      TranslationProvider::Deepl => self.deepl(text).await,
+      TranslationProvider::Libretranslate => self.libretranslate(text).await,
+    }
+  }
+
+  async fn deepl(&self, text: &str) -> Result<String> {
+    let mut body = json!({
+      "text": [text],
+      "target_lang": self.config.target_lang,
+    });
+    if let Some(source) = &self.config.source_lang {
+      body["source_lang"] = json!(source);
+    }
+
+    let mut request = self.client.post(&self.config.endpoint).json(&body);
+    if let Some(key) = &self.config.api_key {
+      request = request.header("Authorization", format!("DeepL-Auth-Key {}", key));
+    }
+
+    let response: DeeplResponse = request.send().await?.error_for_status()?.json().await?;
+    response
+      .translations
+      .into_iter()
+      .next()
+      .map(|t| t.text)
+      .ok_or_else(|| anyhow!("DeepL returned no translations"))
+  }
+
+  async fn libretranslate(&self, text: &str) -> Result<String> {
+    let body = json!({
+      "q": text,
+      "source": self.config.source_lang.as_deref().unwrap_or("auto").to_lowercase(),
+      "target": self.config.target_lang.to_lowercase(),
+      "format": "text",
+      "api_key": self.config.api_key,
+    });
+
+    let response: LibreResponse = self
+      .client
+      .post(&self.config.endpoint)
+      .json(&body)
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await?;
+    Ok(response.translated_text)
+  }
+}
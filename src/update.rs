@@ -0,0 +1,249 @@
+use anyhow::{Result, anyhow, bail};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use crate::log;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/Swizzzer/GZCTF-DC-Bot/releases/latest";
+const CHANGELOG_LINES: usize = 10;
+// 发布包只能整体替换可执行文件，压缩包需要手动解压
+const ARCHIVE_SUFFIXES: [&str; 5] = [".tar.gz", ".tgz", ".tar.xz", ".zip", ".7z"];
+
+#[derive(Debug, Deserialize)]
+struct Release {
+  tag_name: String,
+  #[serde(default)]
+  body: Option<String>,
+  #[serde(default)]
+  html_url: String,
+  #[serde(default)]
+  assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+  name: String,
+  browser_download_url: String,
+}
+
+pub async fn run(install: bool) -> Result<()> {
+  let client = reqwest::Client::builder()
+    .user_agent(concat!("dc-bot/", env!("CARGO_PKG_VERSION")))
+    .build()?;
+
+  let release: Release = client
+    .get(RELEASES_URL)
+    .send()
+    .await?
+    .error_for_status()?
+    .json()
+    .await?;
+
+  let current = env!("CARGO_PKG_VERSION");
+  if !is_newer(&release.tag_name, current) {
+    log::success(format!("dc-bot {} is up to date.", current));
+    return Ok(());
+  }
+
+  log::info(format!(
+    "New version available: {} (current {})",
+    release.tag_name, current
+  ));
+  log::info(format!("Release page: {}", release.html_url));
+  print_highlights(release.body.as_deref().unwrap_or_default());
+
+  if install {
+    install_release(&client, &release).await?;
+  } else {
+    log::info("Run `dc-bot update --install` to download and replace this binary.");
+  }
+
+  Ok(())
+}
+
+fn print_highlights(body: &str) {
+  let lines: Vec<&str> = body
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty())
+    .collect();
+
+  for line in lines.iter().take(CHANGELOG_LINES) {
+    log::info(format!("   {}", line));
+  }
+  if lines.len() > CHANGELOG_LINES {
+    log::info(format!(
+      "   ... ({} more lines)",
+      lines.len() - CHANGELOG_LINES
+    ));
+  }
+}
+
+// 按 "v1.2.3" / "1.2.3" 逐段比较数字
+fn is_newer(tag: &str, current: &str) -> bool {
+  let parse = |v: &str| -> Vec<u64> {
+    v.trim_start_matches('v')
+      .split(['.', '-'])
+      .map_while(|part| part.parse().ok())
+      .collect()
+  };
+  parse(tag) > parse(current)
+}
+
+fn is_archive(name: &str) -> bool {
+  let name = name.to_ascii_lowercase();
+  ARCHIVE_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+fn is_checksum(name: &str) -> bool {
+  let name = name.to_ascii_lowercase();
+  name.ends_with(".sha256") || name.contains("sha256sums") || name.contains("checksums")
+}
+
+// 校验文件可以是单个 "<hash>" / "<hash>  <文件名>"，也可以是每行一个文件的清单
+fn expected_digest(checksums: &str, asset_name: &str) -> Option<String> {
+  let entries: Vec<(&str, Option<&str>)> = checksums
+    .lines()
+    .filter_map(|line| {
+      let mut parts = line.split_whitespace();
+      let hash = parts.next()?;
+      Some((hash, parts.next().map(|name| name.trim_start_matches('*'))))
+    })
+    .collect();
+
+  let hash = match entries.as_slice() {
+    [(hash, None)] => *hash,
+    _ => entries
+      .iter()
+      .find(|(_, name)| *name == Some(asset_name))
+      .map(|(hash, _)| *hash)?,
+  };
+  (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+    .then(|| hash.to_ascii_lowercase())
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Result<Vec<u8>> {
+  let bytes = client
+    .get(url)
+    .send()
+    .await?
+    .error_for_status()?
+    .bytes()
+    .await?;
+  Ok(bytes.to_vec())
+}
+
+async fn install_release(client: &reqwest::Client, release: &Release) -> Result<()> {
+  let (os, arch) = (std::env::consts::OS, std::env::consts::ARCH);
+  let candidates: Vec<&Asset> = release
+    .assets
+    .iter()
+    .filter(|a| a.name.contains(os) && a.name.contains(arch) && !is_checksum(&a.name))
+    .collect();
+  let asset = candidates
+    .iter()
+    .find(|a| !is_archive(&a.name))
+    .copied()
+    .ok_or_else(|| match candidates.first() {
+      Some(archive) => anyhow!(
+        "release asset {} is an archive; download and unpack it manually from {}",
+        archive.name,
+        release.html_url
+      ),
+      None => anyhow!("no release asset found for {}-{}", os, arch),
+    })?;
+
+  // 优先使用 "<文件名>.sha256"，否则在校验清单中查找；没有校验值时拒绝安装
+  let checksum_asset = release
+    .assets
+    .iter()
+    .find(|a| a.name == format!("{}.sha256", asset.name))
+    .or_else(|| release.assets.iter().find(|a| is_checksum(&a.name)))
+    .ok_or_else(|| anyhow!("release {} publishes no SHA256 checksum", release.tag_name))?;
+  let checksums = String::from_utf8(download(client, &checksum_asset.browser_download_url).await?)?;
+  let expected = expected_digest(&checksums, &asset.name).ok_or_else(|| {
+    anyhow!(
+      "{} has no SHA256 entry for {}",
+      checksum_asset.name,
+      asset.name
+    )
+  })?;
+
+  log::info(format!("Downloading {}...", asset.name));
+  let bytes = download(client, &asset.browser_download_url).await?;
+  let actual: String = Sha256::digest(&bytes)
+    .iter()
+    .map(|b| format!("{:02x}", b))
+    .collect();
+  if actual != expected {
+    bail!(
+      "checksum mismatch for {}: expected {}, got {}",
+      asset.name,
+      expected,
+      actual
+    );
+  }
+  log::success(format!("Verified SHA256 of {}.", asset.name));
+
+  let current_exe = std::env::current_exe()?;
+  replace_binary(&current_exe, &bytes)?;
+
+  log::success(format!(
+    "Installed {} to {}. Restart the bot to use it.",
+    release.tag_name,
+    current_exe.display()
+  ));
+  Ok(())
+}
+
+// 先写入临时文件再替换；Windows 不能覆盖正在运行的可执行文件，需先移开
+fn replace_binary(current_exe: &Path, bytes: &[u8]) -> Result<()> {
+  let new_path = current_exe.with_extension("new");
+  std::fs::write(&new_path, bytes)?;
+
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(&new_path, std::fs::Permissions::from_mode(0o755))?;
+  }
+
+  #[cfg(windows)]
+  {
+    let old_path = current_exe.with_extension("old");
+    let _ = std::fs::remove_file(&old_path);
+    std::fs::rename(current_exe, &old_path)?;
+  }
+
+  std::fs::rename(&new_path, current_exe)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+  #[test]
+  fn reads_single_and_listed_checksums() {
+    assert_eq!(expected_digest(HASH, "dc-bot").as_deref(), Some(HASH));
+    let list = format!(
+      "{}  dc-bot-linux-x86_64\n{}  *dc-bot-windows-x86_64.exe\n",
+      "0".repeat(64),
+      HASH
+    );
+    assert_eq!(
+      expected_digest(&list, "dc-bot-windows-x86_64.exe").as_deref(),
+      Some(HASH)
+    );
+    assert_eq!(expected_digest(&list, "dc-bot-macos-aarch64"), None);
+  }
+
+  #[test]
+  fn archives_are_not_installed_directly() {
+    assert!(is_archive("dc-bot-linux-x86_64.tar.gz"));
+    assert!(is_archive("dc-bot-windows-x86_64.ZIP"));
+    assert!(!is_archive("dc-bot-linux-x86_64"));
+  }
+}
@@ -0,0 +1,171 @@
+use reqwest::StatusCode;
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+use serenity::model::colour::Colour;
+use serenity::prelude::Context;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::time::{Duration, sleep};
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::{Config, MatchConfig, PlatformKind};
+use crate::discord::{DeliveryState, DiscordMessenger};
+use crate::gzctf::{format_time, game_url, poster_url};
+use crate::limiter::RequestLimiter;
+use crate::log;
+use crate::models::GameInfo;
+use crate::platform::{self, CtfPlatform};
+use crate::scheduler::Scheduler;
+use crate::tasks;
+
+// 隐藏的比赛在 /api/game/{id} 返回 404
+#[derive(Debug, Clone, PartialEq)]
+enum GameState {
+  Hidden,
+  Visible {
+    title: String,
+    poster: Option<String>,
+  },
+}
+
+// 定期拉取比赛详情，比赛从隐藏变为公开、标题或海报变化时播报；
+// 启动后的第一次结果只作为基准，不播报
+pub fn start(
+  ctx: Arc<Context>,
+  config: Arc<Config>,
+  scheduler: Arc<Scheduler>,
+  delivery: Arc<DeliveryState>,
+  limiter: Arc<RequestLimiter>,
+) {
+  let Some(visibility) = config.visibility.clone() else {
+    return;
+  };
+  if config.gzctf.platform != PlatformKind::Gzctf {
+    log::warn("Game visibility watching only supports GZCTF, skipping");
+    return;
+  }
+
+  let interval = Duration::from_secs(visibility.interval.max(30));
+  let task = async move {
+    let client = match platform::connect(&config.gzctf, &limiter) {
+      Ok(client) => client,
+      Err(e) => {
+        log::error(format!(
+          "Failed to create client for visibility watch: {}",
+          e
+        ));
+        return;
+      }
+    };
+    let messenger = DiscordMessenger::new(config.discord.channel_id, delivery);
+    let mut states: HashMap<u32, GameState> = HashMap::new();
+
+    loop {
+      for match_config in config.get_matches() {
+        let Some(state) = fetch_state(client.as_ref(), match_config.id).await else {
+          continue;
+        };
+        let previous = states.insert(match_config.id, state.clone());
+        let Some(embed) = previous.and_then(|p| change_embed(&config, &match_config, &p, &state))
+        else {
+          continue;
+        };
+
+        let channels = match visibility.channel_id {
+          Some(channel_id) => vec![channel_id],
+          None => config.broadcast_channels(match_config.id),
+        };
+        for channel_id in channels {
+          // 发送失败只记录日志，状态已更新，不会重复播报
+          let _ = messenger
+            .send_embed_to(&ctx, channel_id, None, embed.clone())
+            .await;
+        }
+      }
+      tasks::touch("scheduler:visibility");
+      sleep(interval).await;
+    }
+  };
+  if scheduler.schedule_at("visibility", scheduler.now(), task) {
+    log::info(format!(
+      "Watching game visibility every {}s",
+      interval.as_secs()
+    ));
+  }
+}
+
+// 其他错误不改变已知状态，等下一轮再确认
+async fn fetch_state(client: &dyn CtfPlatform, match_id: u32) -> Option<GameState> {
+  match client.fetch_game(match_id).await {
+    Ok(GameInfo { title, poster, .. }) => Some(GameState::Visible { title, poster }),
+    Err(e)
+      if e
+        .downcast_ref::<reqwest::Error>()
+        .and_then(reqwest::Error::status)
+        == Some(StatusCode::NOT_FOUND) =>
+    {
+      Some(GameState::Hidden)
+    }
+    Err(e) => {
+      log::error(format!(
+        "Failed to fetch game info for match {}: {}",
+        match_id, e
+      ));
+      None
+    }
+  }
+}
+
+fn change_embed(
+  config: &Config,
+  match_config: &MatchConfig,
+  previous: &GameState,
+  current: &GameState,
+) -> Option<CreateEmbed> {
+  let url = game_url(&config.gzctf.url, match_config.id, config.gzctf.platform);
+  let (title, poster, embed) = match (previous, current) {
+    (GameState::Hidden, GameState::Visible { title, poster }) => (
+      title,
+      poster,
+      CreateEmbed::new()
+        .title("**比赛已公开**")
+        .color(Colour::from_rgb(34, 197, 94))
+        .description(format!("**[{}]({})** 现已开放，快来报名参赛！", title, url)),
+    ),
+    (
+      GameState::Visible {
+        title: old_title,
+        poster: old_poster,
+      },
+      GameState::Visible { title, poster },
+    ) if old_title != title || old_poster != poster => {
+      let mut embed = CreateEmbed::new()
+        .title("**比赛信息更新**")
+        .color(Colour::from_rgb(59, 130, 246))
+        .description(format!("**赛事:** [{}]({})", title, url));
+      if old_title != title {
+        embed = embed.field("标题", format!("{} → {}", old_title, title), false);
+      }
+      if old_poster != poster {
+        embed = embed.field("海报", "已更新", false);
+      }
+      (title, poster, embed)
+    }
+    (GameState::Visible { .. }, GameState::Hidden) => {
+      log::info(format!("Match {} is hidden again", match_config.id));
+      return None;
+    }
+    _ => return None,
+  };
+
+  log::info(format!(
+    "Match {} ({}) changed: {:?} -> {:?}",
+    match_config.id, title, previous, current
+  ));
+  let mut embed = embed.footer(CreateEmbedFooter::new(format_time(
+    SystemClock.now_millis(),
+  )));
+  if let Some(poster) = poster {
+    embed = embed.image(poster_url(&config.gzctf.url, poster));
+  }
+  Some(embed)
+}
@@ -0,0 +1,141 @@
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hmac::{Hmac, Mac};
+use serde_json::{Value, json};
+use serenity::async_trait;
+use sha2::Sha256;
+use tokio::time::Duration;
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::{WebhookConfig, WebhookKind};
+use crate::models::NoticeType;
+use crate::relays::{PlainNotice, Relay};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 飞书 / Lark 和钉钉群机器人 webhook，按各自的卡片格式发送
+pub struct WebhookRelay {
+  config: WebhookConfig,
+  name: String,
+  client: reqwest::Client,
+}
+
+impl WebhookRelay {
+  pub fn new(config: WebhookConfig) -> Result<Self> {
+    let client = reqwest::Client::builder()
+      .timeout(REQUEST_TIMEOUT)
+      .build()?;
+    let name = format!("{:?} webhook", config.kind).to_lowercase();
+    Ok(Self {
+      config,
+      name,
+      client,
+    })
+  }
+
+  fn feishu_body(&self, notice: &PlainNotice) -> Result<Value> {
+    let mut body = json!({
+      "msg_type": "interactive",
+      "card": {
+        "header": {
+          "title": { "tag": "plain_text", "content": notice.title },
+          "template": feishu_template(&notice.notice_type),
+        },
+        "elements": [{ "tag": "markdown", "content": notice.body }],
+      },
+    });
+    // 签名校验：key 为 "timestamp\nsecret"，对空消息做 HMAC-SHA256
+    if let Some(secret) = &self.config.secret {
+      let timestamp = SystemClock.now().to_string();
+      let key = format!("{}\n{}", timestamp, secret);
+      body["timestamp"] = json!(timestamp);
+      body["sign"] = json!(sign(key.as_bytes(), b"")?);
+    }
+    Ok(body)
+  }
+
+  fn dingtalk_request(&self, notice: &PlainNotice) -> Result<(reqwest::Url, Value)> {
+    let mut url = reqwest::Url::parse(&self.config.url)?;
+    // 签名校验：key 为 secret，对 "timestamp\nsecret" 做 HMAC-SHA256，参数附在 URL 上
+    if let Some(secret) = &self.config.secret {
+      let timestamp = SystemClock.now_millis().to_string();
+      let signature = sign(
+        secret.as_bytes(),
+        format!("{}\n{}", timestamp, secret).as_bytes(),
+      )?;
+      url
+        .query_pairs_mut()
+        .append_pair("timestamp", &timestamp)
+        .append_pair("sign", &signature);
+    }
+
+    let body = json!({
+      "msgtype": "markdown",
+      "markdown": {
+        "title": notice.title,
+        // 钉钉 markdown 需要两个空格加换行才会换行
+        "text": format!("### {}\n\n{}", notice.title, notice.body.replace('\n', "  \n")),
+      },
+    });
+    Ok((url, body))
+  }
+}
+
+#[async_trait]
+impl Relay for WebhookRelay {
+  fn name(&self) -> &str {
+    &self.name
+  }
+
+  fn wants(&self, notice_type: &NoticeType) -> bool {
+    self.config.types.is_empty() || self.config.types.contains(notice_type)
+  }
+
+  async fn send(&self, notice: &PlainNotice) -> Result<()> {
+    let (url, body) = match self.config.kind {
+      WebhookKind::Feishu => (
+        reqwest::Url::parse(&self.config.url)?,
+        self.feishu_body(notice)?,
+      ),
+      WebhookKind::Dingtalk => self.dingtalk_request(notice)?,
+    };
+
+    // 两者在业务错误时都返回 200，需要检查响应中的错误码
+    let response: Value = self
+      .client
+      .post(url)
+      .json(&body)
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await?;
+    let code = response
+      .get("code")
+      .or_else(|| response.get("errcode"))
+      .and_then(Value::as_i64)
+      .unwrap_or(0);
+    if code != 0 {
+      return Err(anyhow!("webhook returned error {}: {}", code, response));
+    }
+    Ok(())
+  }
+}
+
+fn sign(key: &[u8], message: &[u8]) -> Result<String> {
+  let mut mac = Hmac::<Sha256>::new_from_slice(key)?;
+  mac.update(message);
+  Ok(BASE64.encode(mac.finalize().into_bytes()))
+}
+
+fn feishu_template(notice_type: &NoticeType) -> &'static str {
+  match notice_type {
+    NoticeType::Normal => "blue",
+    NoticeType::NewChallenge => "green",
+    NoticeType::NewHint => "yellow",
+    NoticeType::FirstBlood => "red",
+    NoticeType::SecondBlood => "orange",
+    NoticeType::ThirdBlood => "carmine",
+  }
+}